@@ -0,0 +1,94 @@
+using System;
+using System.Collections.Generic;
+namespace net.rs64.TexTransCoreEngineForWgpu
+{
+    public sealed class TTCEWgpuAdapterInfo
+    {
+        public uint Index { get; }
+        public string Name { get; }
+        public string Driver { get; }
+        public string DriverInfo { get; }
+        public uint Vendor { get; }
+        public uint Device { get; }
+        public string DeviceType { get; }
+        public string Backend { get; }
+        public bool IsSupported { get; }
+        public ulong Features { get; }
+
+        public uint MaxTextureDimension2D { get; }
+        public uint MaxStorageTexturesPerShaderStage { get; }
+        public uint MaxBindGroups { get; }
+        public uint MaxStorageBufferBindingSize { get; }
+        public ulong MaxBufferSize { get; }
+        public (uint x, uint y, uint z) MaxComputeWorkgroupSize { get; }
+        public uint MaxComputeInvocationsPerWorkgroup { get; }
+        public uint MaxComputeWorkgroupsPerDimension { get; }
+
+        internal TTCEWgpuAdapterInfo(uint index, AdapterInfo info, string name, string driver, string driverInfo)
+        {
+            Index = index;
+            Name = name;
+            Driver = driver;
+            DriverInfo = driverInfo;
+            Vendor = info.vendor;
+            Device = info.device;
+            DeviceType = info.device_type.ToString();
+            Backend = info.backend.ToString();
+            IsSupported = info.is_supported;
+            Features = info.features;
+
+            MaxTextureDimension2D = info.max_texture_dimension_2d;
+            MaxStorageTexturesPerShaderStage = info.max_storage_textures_per_shader_stage;
+            MaxBindGroups = info.max_bind_groups;
+            MaxStorageBufferBindingSize = info.max_storage_buffer_binding_size;
+            MaxBufferSize = info.max_buffer_size;
+            MaxComputeWorkgroupSize = (info.max_compute_workgroup_size_x, info.max_compute_workgroup_size_y, info.max_compute_workgroup_size_z);
+            MaxComputeInvocationsPerWorkgroup = info.max_compute_invocations_per_workgroup;
+            MaxComputeWorkgroupsPerDimension = info.max_compute_workgroups_per_dimension;
+        }
+
+        public override string ToString()
+        {
+            return $"[{Index}] {Name} ({DeviceType}, {Backend}) Driver:{Driver} {DriverInfo} Supported:{IsSupported}";
+        }
+    }
+
+    public static class TTCEWgpuAdapter
+    {
+        public static TTCEWgpuAdapterInfo[] EnumerateAdapters()
+        {
+            unsafe
+            {
+                uint count;
+                NativeMethod.get_adapter_count(&count).ThrowIfFailed();
+
+                var adapters = new List<TTCEWgpuAdapterInfo>((int)count);
+                for (uint i = 0; count > i; i += 1)
+                {
+                    AdapterInfo info;
+                    NativeMethod.get_adapter_info(i, &info).ThrowIfFailed();
+
+                    adapters.Add(new TTCEWgpuAdapterInfo(i, info,
+                        GetAdapterInfoString(i, AdapterInfoString.Name),
+                        GetAdapterInfoString(i, AdapterInfoString.Driver),
+                        GetAdapterInfoString(i, AdapterInfoString.DriverInfo)
+                    ));
+                }
+                return adapters.ToArray();
+            }
+        }
+
+        static unsafe string GetAdapterInfoString(uint adapterIndex, AdapterInfoString infoString)
+        {
+            int strLen;
+            NativeMethod.get_adapter_info_string(adapterIndex, infoString, null, 0, &strLen).ThrowIfFailed();
+
+            var buffer = new char[strLen];
+            fixed (char* bufferPtr = buffer)
+            {
+                NativeMethod.get_adapter_info_string(adapterIndex, infoString, (ushort*)bufferPtr, buffer.Length, &strLen).ThrowIfFailed();
+            }
+            return new string(buffer);
+        }
+    }
+}
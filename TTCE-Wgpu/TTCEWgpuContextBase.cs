@@ -31,14 +31,28 @@ namespace net.rs64.TexTransCoreEngineForWgpu
         }
 
 
-        public TTWgpuRenderTexture GetRenderTexture(uint width, uint height, TexTransCore.TexTransCoreTextureChannel channel = TexTransCore.TexTransCoreTextureChannel.RGBA)
+        /// <summary>
+        /// mipLevelCount は 1 から最大のミップの数までで、ミップは GenerateMipmap か UploadTextureMip で埋める必要がある。
+        /// </summary>
+        public TTWgpuRenderTexture GetRenderTexture(uint width, uint height, TexTransCore.TexTransCoreTextureChannel channel = TexTransCore.TexTransCoreTextureChannel.RGBA, uint mipLevelCount = 1)
         {
-            if (width == 0 || height == 0) { throw new ArgumentException(); }
+            return GetRenderTexture(width, height, 1, TTWgpuTextureDimension.D2, channel, mipLevelCount);
+        }
+        /// <summary>
+        /// dimension が D2 の場合 depthOrArrayLayers は 2D 配列のレイヤー数、 D3 の場合は奥行きになる。
+        /// シェーダー側では RWTexture2DArray や RWTexture3D として受け取る必要がある。
+        /// </summary>
+        public TTWgpuRenderTexture GetRenderTexture(uint width, uint height, uint depthOrArrayLayers, TTWgpuTextureDimension dimension, TexTransCore.TexTransCoreTextureChannel channel = TexTransCore.TexTransCoreTextureChannel.RGBA, uint mipLevelCount = 1)
+        {
+            if (width == 0 || height == 0 || depthOrArrayLayers == 0) { throw new ArgumentException(); }
+            if (mipLevelCount == 0) { throw new ArgumentOutOfRangeException(nameof(mipLevelCount)); }
             if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
 
             unsafe
             {
-                var ptr = new IntPtr(NativeMethod.get_render_texture((void*)_handler.DangerousGetHandle(), width, height, (ChannelFFI)channel));
+                void* rtPtr;
+                NativeMethod.get_render_texture_with_dimension((void*)_handler.DangerousGetHandle(), width, height, depthOrArrayLayers, (TTRtDimension)dimension, (ChannelFFI)channel, mipLevelCount, &rtPtr).ThrowIfFailed();
+                var ptr = new IntPtr(rtPtr);
                 var rt = new TTWgpuRenderTexture(this, new TTRenderTextureHandler(ptr), channel);
                 _renderTextures.Add(rt);
                 return rt;
@@ -50,7 +64,9 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
             unsafe
             {
-                var ptr = new IntPtr(NativeMethod.get_compute_handler((void*)_handler.DangerousGetHandle(), computeShaderID.GetID()));
+                void* chPtr;
+                NativeMethod.get_compute_handler((void*)_handler.DangerousGetHandle(), computeShaderID.GetID(), &chPtr).ThrowIfFailed();
+                var ptr = new IntPtr(chPtr);
                 var ttCH = new TTWgpuComputeHandler(this, new TTComputeHandlerPtrHandler(ptr));
                 _computeHandlers.Add(ttCH);
                 return ttCH;
@@ -65,7 +81,81 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
             unsafe
             {
-                NativeMethod.copy_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), (void*)src.GetPtr());
+                NativeMethod.copy_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), (void*)src.GetPtr()).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// src の srcRegion を dist の distRegion にコピーする。二つの領域は同じ大きさでないといけず、形式の変換は行えない。
+        /// </summary>
+        public void CopyTextureRegion(TTWgpuRenderTexture dist, TTWgpuTextureRegion distRegion, TTWgpuRenderTexture src, TTWgpuTextureRegion srcRegion)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+            if (distRegion.Width != srcRegion.Width || distRegion.Height != srcRegion.Height) { throw new ArgumentException(); }
+
+            unsafe
+            {
+                NativeMethod.copy_texture_region((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), distRegion.ToNative(), (void*)src.GetPtr(), srcRegion.ToNative()).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// src を dist のフォーマットとチャンネルに変換してコピーする。ミップ 0 のみで、配列や 3D のテクスチャーには使えない。
+        /// </summary>
+        public void ConvertTexture(TTWgpuRenderTexture dist, TTWgpuRenderTexture src, TTWgpuChannelMapping mapping = TTWgpuChannelMapping.Default)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.convert_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), (void*)src.GetPtr(), (TTChannelMapping)mapping).ThrowIfFailed();
+            }
+        }
+
+        /// <summary>
+        /// src を dist の解像度とフォーマットとチャンネルに変換して書き込む。ミップ 0 のみで、配列や 3D のテクスチャー、同じテクスチャー同士には使えない。
+        /// </summary>
+        public void BlitTexture(TTWgpuRenderTexture dist, TTWgpuRenderTexture src, TTWgpuBlitFilter filter = TTWgpuBlitFilter.Bilinear, TTWgpuChannelMapping mapping = TTWgpuChannelMapping.Default)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.blit_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), (void*)src.GetPtr(), (TTBlitFilter)filter, (TTChannelMapping)mapping).ThrowIfFailed();
+            }
+        }
+
+        /// <summary>
+        /// PNG, TGA, EXR, DDS のファイルを読み込む。フォーマットとチャンネルはファイルのビット深度とチャンネル数から選ばれ、色空間の印も付けられる。
+        /// </summary>
+        public TTWgpuRenderTexture LoadImageFile(string path)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                void* rtPtr;
+                ChannelFFI channel;
+                fixed (char* pathPtr = path)
+                {
+                    NativeMethod.load_image_file((void*)_handler.DangerousGetHandle(), (ushort*)pathPtr, path.Length, &rtPtr, &channel).ThrowIfFailed();
+                }
+                var rt = new TTWgpuRenderTexture(this, new TTRenderTextureHandler(new IntPtr(rtPtr)), (TexTransCore.TexTransCoreTextureChannel)channel);
+                _renderTextures.Add(rt);
+                return rt;
+            }
+        }
+        /// <summary>
+        /// ミップ 0 を PNG か EXR で書き出す。配列や 3D のテクスチャーには使えず、色空間の変換は行わない。
+        /// </summary>
+        public void SaveImageFile(TTWgpuRenderTexture source, string path, TTWgpuImageFileFormat fileFormat = TTWgpuImageFileFormat.Png)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                fixed (char* pathPtr = path)
+                {
+                    NativeMethod.save_image_file((void*)_handler.DangerousGetHandle(), (void*)source.GetPtr(), (ushort*)pathPtr, path.Length, (TTImageFileFormat)fileFormat).ThrowIfFailed();
+                }
             }
         }
 
@@ -77,7 +167,7 @@ namespace net.rs64.TexTransCoreEngineForWgpu
             {
                 fixed (T* ptr = dataSource)
                 {
-                    NativeMethod.upload_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), (byte*)ptr, dataSource.Length * sizeof(T), (TexTransCoreTextureFormat)format);
+                    NativeMethod.upload_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), (byte*)ptr, dataSource.Length * sizeof(T), (TexTransCoreTextureFormat)format).ThrowIfFailed();
                 }
             }
         }
@@ -85,7 +175,6 @@ namespace net.rs64.TexTransCoreEngineForWgpu
         public void DownloadTexture<T>(Span<T> dataDist, TexTransCore.TexTransCoreTextureFormat format, TTWgpuRenderTexture source) where T : unmanaged
         {
             if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
-            if (source.GetWidth() < 64 || source.GetHeight() < 64) { throw new InvalidOperationException("Texture downloading of 64x64 or above are allowed."); }
 
             unsafe
             {
@@ -95,24 +184,270 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
                 fixed (T* ptr = dataDist)
                 {
-                    NativeMethod.download_texture((void*)_handler.DangerousGetHandle(), (byte*)ptr, ptrLen, (TexTransCoreTextureFormat)format, (void*)source.GetPtr());
+                    NativeMethod.download_texture((void*)_handler.DangerousGetHandle(), (byte*)ptr, ptrLen, (TexTransCoreTextureFormat)format, (void*)source.GetPtr()).ThrowIfFailed();
+                }
+            }
+        }
+        /// <summary>
+        /// ミップ 0 をブロック圧縮し、ブロックを左上から行ごとに詰めたものを dataDist に書き込む。 TTCEWgpuDevice.RegisterBlockCompressor で登録しておく必要がある。
+        /// </summary>
+        public void EncodeBlockCompression(Span<byte> dataDist, TTWgpuBlockCompressionFormat format, TTWgpuRenderTexture source, TTWgpuBlockCompressionQuality quality = TTWgpuBlockCompressionQuality.Normal)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+            if (dataDist.Length != GetBlockCompressedSize(format, source.GetWidth(), source.GetHeight())) { throw new ArgumentOutOfRangeException(); }
+
+            unsafe
+            {
+                fixed (byte* ptr = dataDist)
+                {
+                    NativeMethod.encode_block_compression((void*)_handler.DangerousGetHandle(), ptr, dataDist.Length, (TTBlockCompressionFormat)format, (TTBlockCompressionQuality)quality, (void*)source.GetPtr()).ThrowIfFailed();
+                }
+            }
+        }
+        /// <summary>
+        /// ブロックを左上から行ごとに詰めた dataSource をデコードし、 dist のミップ 0 に書き込む。
+        /// </summary>
+        public void UploadBlockCompressedTexture(TTWgpuRenderTexture dist, ReadOnlySpan<byte> dataSource, TTWgpuBlockCompressionFormat format, TTWgpuBlockDecompressionPath path = TTWgpuBlockDecompressionPath.Auto)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+            if (dataSource.Length != GetBlockCompressedSize(format, dist.GetWidth(), dist.GetHeight())) { throw new ArgumentOutOfRangeException(); }
+
+            unsafe
+            {
+                fixed (byte* ptr = dataSource)
+                {
+                    NativeMethod.upload_block_compressed_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), ptr, dataSource.Length, (TTBlockCompressionFormat)format, (TTBlockDecompressionPath)path).ThrowIfFailed();
+                }
+            }
+        }
+        public static int GetBlockCompressedSize(TTWgpuBlockCompressionFormat format, uint width, uint height)
+        {
+            var blockSize = format is TTWgpuBlockCompressionFormat.BC1 or TTWgpuBlockCompressionFormat.BC4 ? 8 : 16;
+            return (int)((width + 3) / 4 * ((height + 3) / 4)) * blockSize;
+        }
+        public void UploadTextureMip<T>(TTWgpuRenderTexture dist, uint mipLevel, ReadOnlySpan<T> dataSource, TexTransCore.TexTransCoreTextureFormat format) where T : unmanaged
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                fixed (T* ptr = dataSource)
+                {
+                    NativeMethod.upload_texture_mip((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), mipLevel, (byte*)ptr, dataSource.Length * sizeof(T), (TexTransCoreTextureFormat)format).ThrowIfFailed();
+                }
+            }
+        }
+
+        public void DownloadTextureMip<T>(Span<T> dataDist, TexTransCore.TexTransCoreTextureFormat format, TTWgpuRenderTexture source, uint mipLevel) where T : unmanaged
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                fixed (T* ptr = dataDist)
+                {
+                    NativeMethod.download_texture_mip((void*)_handler.DangerousGetHandle(), (byte*)ptr, dataDist.Length * sizeof(T), (TexTransCoreTextureFormat)format, (void*)source.GetPtr(), mipLevel).ThrowIfFailed();
                 }
             }
         }
-        public TTWgpuStorageBuffer AllocateStorageBuffer<T>(int length, bool downloadable = false) where T : unmanaged
+
+        /// <summary>
+        /// 2D 配列の場合は layer 番目のレイヤー、 3D の場合は奥行き layer の位置の一枚に書き込む。
+        /// </summary>
+        public void UploadTextureLayer<T>(TTWgpuRenderTexture dist, uint mipLevel, uint layer, ReadOnlySpan<T> dataSource, TexTransCore.TexTransCoreTextureFormat format) where T : unmanaged
         {
             if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
 
             unsafe
             {
-                var storageBufferPtr = new IntPtr(NativeMethod.allocate_storage_buffer((void*)_handler.DangerousGetHandle(), sizeof(T) * length, downloadable));
+                fixed (T* ptr = dataSource)
+                {
+                    NativeMethod.upload_texture_layer((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), mipLevel, layer, (byte*)ptr, dataSource.Length * sizeof(T), (TexTransCoreTextureFormat)format).ThrowIfFailed();
+                }
+            }
+        }
+
+        public void DownloadTextureLayer<T>(Span<T> dataDist, TexTransCore.TexTransCoreTextureFormat format, TTWgpuRenderTexture source, uint mipLevel, uint layer) where T : unmanaged
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                fixed (T* ptr = dataDist)
+                {
+                    NativeMethod.download_texture_layer((void*)_handler.DangerousGetHandle(), (byte*)ptr, dataDist.Length * sizeof(T), (TexTransCoreTextureFormat)format, (void*)source.GetPtr(), mipLevel, layer).ThrowIfFailed();
+                }
+            }
+        }
+
+        /// <summary>
+        /// region の矩形のみに書き込む。 dataSource の長さは region の大きさに合っていないといけない。
+        /// </summary>
+        public void UploadTextureRegion<T>(TTWgpuRenderTexture dist, TTWgpuTextureRegion region, ReadOnlySpan<T> dataSource, TexTransCore.TexTransCoreTextureFormat format) where T : unmanaged
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                fixed (T* ptr = dataSource)
+                {
+                    NativeMethod.upload_texture_region((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), region.ToNative(), (byte*)ptr, dataSource.Length * sizeof(T), (TexTransCoreTextureFormat)format).ThrowIfFailed();
+                }
+            }
+        }
+
+        /// <summary>
+        /// すべてのミップとレイヤーを r g b a の値で塗りつぶす。値は色空間の変換をせずにそのまま書き込まれる。
+        /// </summary>
+        public void ClearRenderTexture(TTWgpuRenderTexture dist, float r, float g, float b, float a)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.clear_render_texture((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), r, g, b, a).ThrowIfFailed();
+            }
+        }
+
+        /// <summary>
+        /// region の矩形のみを r g b a の値で塗りつぶす。 3D のテクスチャーの場合は region の layer が奥行きの位置になる。
+        /// </summary>
+        public void FillRect(TTWgpuRenderTexture dist, TTWgpuTextureRegion region, float r, float g, float b, float a)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.fill_rect((void*)_handler.DangerousGetHandle(), (void*)dist.GetPtr(), region.ToNative(), r, g, b, a).ThrowIfFailed();
+            }
+        }
+
+        /// <summary>
+        /// region の矩形のみを読み出す。 dataDist の長さは region の大きさに合っていないといけない。
+        /// </summary>
+        public void DownloadTextureRegion<T>(Span<T> dataDist, TexTransCore.TexTransCoreTextureFormat format, TTWgpuRenderTexture source, TTWgpuTextureRegion region) where T : unmanaged
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                fixed (T* ptr = dataDist)
+                {
+                    NativeMethod.download_texture_region((void*)_handler.DangerousGetHandle(), (byte*)ptr, dataDist.Length * sizeof(T), (TexTransCoreTextureFormat)format, (void*)source.GetPtr(), region.ToNative()).ThrowIfFailed();
+                }
+            }
+        }
+
+        /// <summary>
+        /// ミップ 0 から残りのミップを生成する。 gammaAware の場合は RGB を sRGB としてリニアで平均する。
+        /// </summary>
+        public void GenerateMipmap(TTWgpuRenderTexture renderTexture, TTWgpuMipmapFilter filter = TTWgpuMipmapFilter.Box, bool gammaAware = false)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.generate_mipmap((void*)_handler.DangerousGetHandle(), (void*)renderTexture.GetPtr(), (MipmapFilter)filter, gammaAware).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// UploadConstantsBuffer の定数を詰めているチャンクの状態。 BufferCreatedCount が Dispatch のたびに増えていなければ再利用されている。
+        /// </summary>
+        public TTCEWgpuConstantsBufferStatistics GetConstantsBufferStatistics()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                TTConstantsBufferStatistics statistics;
+                NativeMethod.get_constants_buffer_statistics((void*)_handler.DangerousGetHandle(), &statistics).ThrowIfFailed();
+                return new TTCEWgpuConstantsBufferStatistics(statistics);
+            }
+        }
+        /// <summary>
+        /// すべてのミップの RGB を現在の色空間から colorSpace に変換し、色空間の印も付け替える。 A はそのまま残る。
+        /// </summary>
+        public void ConvertColorSpace(TTWgpuRenderTexture renderTexture, TTWgpuColorSpace colorSpace)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.convert_color_space((void*)_handler.DangerousGetHandle(), (void*)renderTexture.GetPtr(), (TTColorSpace)colorSpace).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// GPU の完了を待たずにダウンロードを要求する。 onCompleted は TTCEWgpuDevice.Poll や TTWgpuDownloadTicket.Poll などを呼んだスレッドで呼ばれる。
+        /// </summary>
+        public TTWgpuDownloadTicket RequestDownloadTexture(TexTransCore.TexTransCoreTextureFormat format, TTWgpuRenderTexture source, Action<TTWgpuDownloadTicket.DownloadState>? onCompleted = null)
+        {
+            return RequestDownloadTexture(format, source, new TTWgpuTextureRegion(0, 0, source.GetWidth(), source.GetHeight()), onCompleted);
+        }
+        /// <summary>
+        /// region の矩形のみを読み出す RequestDownloadTexture 。
+        /// </summary>
+        public TTWgpuDownloadTicket RequestDownloadTexture(TexTransCore.TexTransCoreTextureFormat format, TTWgpuRenderTexture source, TTWgpuTextureRegion region, Action<TTWgpuDownloadTicket.DownloadState>? onCompleted = null)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                var userData = onCompleted is not null ? GCHandle.ToIntPtr(GCHandle.Alloc(onCompleted)) : IntPtr.Zero;
+                var callback = onCompleted is not null ? TTWgpuDownloadTicket.GetCompletedCallbackPointer() : null;
+
+                void* ticketPtr;
+                var status = NativeMethod.request_download_texture((void*)_handler.DangerousGetHandle(), (TexTransCoreTextureFormat)format, (void*)source.GetPtr(), region.ToNative(), callback, (void*)userData, &ticketPtr);
+                if (status is not TTCEWgpuStatus.Ok && userData != IntPtr.Zero) { GCHandle.FromIntPtr(userData).Free(); }
+                status.ThrowIfFailed();
+
+                return new TTWgpuDownloadTicket(new TTDownloadTicketHandler(new IntPtr(ticketPtr)));
+            }
+        }
+        /// <summary>
+        /// anisotropyClamp は 1 から 16 までで、 2 以上の場合は filterMode が Trilinear でないといけない。
+        /// </summary>
+        public TTWgpuSampler CreateSampler(TTWgpuSamplerFilterMode filterMode, TTWgpuSamplerAddressMode addressMode = TTWgpuSamplerAddressMode.Clamp, ushort anisotropyClamp = 1)
+        {
+            return CreateSampler(filterMode, addressMode, addressMode, addressMode, anisotropyClamp);
+        }
+        public TTWgpuSampler CreateSampler(TTWgpuSamplerFilterMode filterMode, TTWgpuSamplerAddressMode addressModeU, TTWgpuSamplerAddressMode addressModeV, TTWgpuSamplerAddressMode addressModeW, ushort anisotropyClamp = 1)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                var descriptor = new TTSamplerDescriptor()
+                {
+                    filter_mode = (TTSamplerFilterMode)filterMode,
+                    address_mode_u = (TTSamplerAddressMode)addressModeU,
+                    address_mode_v = (TTSamplerAddressMode)addressModeV,
+                    address_mode_w = (TTSamplerAddressMode)addressModeW,
+                    anisotropy_clamp = anisotropyClamp,
+                };
+                void* samplerPtr;
+                NativeMethod.create_sampler((void*)_handler.DangerousGetHandle(), descriptor, &samplerPtr).ThrowIfFailed();
+                return new TTWgpuSampler(new TTSamplerHandler(new IntPtr(samplerPtr)));
+            }
+        }
+
+        /// <param name="indirect"> true の場合 TTWgpuComputeHandler.DispatchIndirect に渡せるようになる。 </param>
+        public TTWgpuStorageBuffer AllocateStorageBuffer<T>(int length, bool downloadable = false, bool indirect = false) where T : unmanaged
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
+
+            unsafe
+            {
+                void* sbPtr;
+                NativeMethod.allocate_storage_buffer((void*)_handler.DangerousGetHandle(), sizeof(T) * length, downloadable, indirect, &sbPtr).ThrowIfFailed();
+                var storageBufferPtr = new IntPtr(sbPtr);
                 var sb = new TTWgpuStorageBuffer(this, new TTStorageBufferHandler(storageBufferPtr), downloadable);
                 _storageBuffers.Add(sb);
                 return sb;
             }
         }
 
-        public TTWgpuStorageBuffer UploadStorageBuffer<T>(ReadOnlySpan<T> data, bool downloadable = false) where T : unmanaged
+        /// <param name="indirect"> true の場合 TTWgpuComputeHandler.DispatchIndirect に渡せるようになる。 </param>
+        public TTWgpuStorageBuffer UploadStorageBuffer<T>(ReadOnlySpan<T> data, bool downloadable = false, bool indirect = false) where T : unmanaged
         {
             if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineContextHandler is dropped"); }
 
@@ -121,8 +456,9 @@ namespace net.rs64.TexTransCoreEngineForWgpu
                 var dataLen = data.Length * sizeof(T);
                 fixed (T* dataPtr = data)
                 {
-
-                    var storageBufferPtr = new IntPtr(NativeMethod.upload_storage_buffer((void*)_handler.DangerousGetHandle(), (byte*)dataPtr, dataLen, downloadable));
+                    void* sbPtr;
+                    NativeMethod.upload_storage_buffer((void*)_handler.DangerousGetHandle(), (byte*)dataPtr, dataLen, downloadable, indirect, &sbPtr).ThrowIfFailed();
+                    var storageBufferPtr = new IntPtr(sbPtr);
                     var sb = new TTWgpuStorageBuffer(this, new TTStorageBufferHandler(storageBufferPtr), downloadable);
                     _storageBuffers.Add(sb);
                     return sb;
@@ -140,7 +476,7 @@ namespace net.rs64.TexTransCoreEngineForWgpu
                 using (buffer)
                     fixed (T* bufPtr = dist)
                     {
-                        NativeMethod.download_storage_buffer((void*)_handler.DangerousGetHandle(), (byte*)bufPtr, dataLen, (void*)buffer.GetPtr());
+                        NativeMethod.download_storage_buffer((void*)_handler.DangerousGetHandle(), (byte*)bufPtr, dataLen, (void*)buffer.GetPtr()).ThrowIfFailed();
                     }
             }
         }
@@ -197,6 +533,92 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
     }
 
+    /// <summary>
+    /// mipLevel のミップの layer 番目のレイヤー上の矩形。 3D の場合 layer は奥行きの位置を指す。
+    /// </summary>
+    public readonly struct TTWgpuTextureRegion
+    {
+        public readonly uint X;
+        public readonly uint Y;
+        public readonly uint Width;
+        public readonly uint Height;
+        public readonly uint MipLevel;
+        public readonly uint Layer;
+
+        public TTWgpuTextureRegion(uint x, uint y, uint width, uint height, uint mipLevel = 0, uint layer = 0)
+        {
+            X = x;
+            Y = y;
+            Width = width;
+            Height = height;
+            MipLevel = mipLevel;
+            Layer = layer;
+        }
+        internal TTTextureRegion ToNative()
+        {
+            return new TTTextureRegion()
+            {
+                mip_level = MipLevel,
+                layer = Layer,
+                x = X,
+                y = Y,
+                width = Width,
+                height = Height,
+            };
+        }
+    }
+    public enum TTWgpuBlockCompressionFormat : uint
+    {
+        /// <summary> A が 0.5 未満のピクセルは透明になる </summary>
+        BC1 = 0,
+        BC3 = 1,
+        BC4 = 2,
+        BC5 = 3,
+        BC7 = 4,
+        /// <summary> デコードのみ </summary>
+        BC2 = 5,
+        /// <summary> デコードのみ、符号なしの HDR </summary>
+        BC6H = 6,
+    }
+    public enum TTWgpuBlockDecompressionPath : uint
+    {
+        /// <summary> デバイスが対応していればハードウェア、そうでなければコンピュートシェーダー </summary>
+        Auto = 0,
+        Hardware = 1,
+        ComputeShader = 2,
+    }
+    public enum TTWgpuBlockCompressionQuality : uint
+    {
+        Fast = 0,
+        Normal = 1,
+        High = 2,
+    }
+    public enum TTWgpuImageFileFormat : uint
+    {
+        /// <summary> Byte のものは 8bit 、それ以外は 16bit </summary>
+        Png = 0,
+        /// <summary> Float の RGBA </summary>
+        Exr = 1,
+    }
+
+    public readonly struct TTCEWgpuConstantsBufferStatistics
+    {
+        public readonly uint ChunkCount;
+        public readonly ulong BufferCreatedCount;
+        public readonly ulong SuballocatedCount;
+
+        internal TTCEWgpuConstantsBufferStatistics(TTConstantsBufferStatistics statistics)
+        {
+            ChunkCount = statistics.chunk_count;
+            BufferCreatedCount = statistics.buffer_created_count;
+            SuballocatedCount = statistics.suballocated_count;
+        }
+        public override string ToString()
+        {
+            return $"chunk {ChunkCount} buffer created {BufferCreatedCount} suballocated {SuballocatedCount}";
+        }
+    }
+
     internal static class TTCEWgpuEngineUtil
     {
         public static TTWgpuRenderTexture Unwrap(this ITTRenderTexture renderTexture) => (TTWgpuRenderTexture)renderTexture;
@@ -215,8 +637,7 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
         protected override bool ReleaseHandle()
         {
-            unsafe { NativeMethod.drop_ttce_context((void*)handle); }
-            return true;
+            unsafe { return NativeMethod.drop_ttce_context((void*)handle) is TTCEWgpuStatus.Ok; }
         }
     }
 }
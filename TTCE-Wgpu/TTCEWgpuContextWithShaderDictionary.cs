@@ -9,9 +9,12 @@ namespace net.rs64.TexTransCoreEngineForWgpu
         public TTCEWgpuDeviceWidthShaderDictionary(
                 RequestDevicePreference preference = RequestDevicePreference.Auto
                 , TexTransCore.TexTransCoreTextureFormat format = TexTransCore.TexTransCoreTextureFormat.Byte
-            ): base(preference)
+                , string? shaderCacheDirectory = null
+                , string? pipelineCachePath = null
+            ): base(preference, pipelineCachePath)
         {
             SetDefaultTextureFormat(format);
+            if (shaderCacheDirectory is not null) { SetShaderCacheDirectory(shaderCacheDirectory); }
             _shaderDictionary = this.RegisterShadersWithCurrentDirectory();
         }
 
@@ -7,6 +7,46 @@ using net.rs64.TexTransCore;
 namespace net.rs64.TexTransCoreEngineForWgpu
 {
 
+    public readonly struct TTCEWgpuRenderTexturePoolStatistics
+    {
+        public readonly ulong BudgetByte;
+        public readonly ulong PooledByte;
+        public readonly uint PooledCount;
+        public readonly ulong HitCount;
+        public readonly ulong MissCount;
+        public readonly ulong EvictedCount;
+
+        internal TTCEWgpuRenderTexturePoolStatistics(RenderTexturePoolStatistics statistics)
+        {
+            BudgetByte = statistics.budget_byte;
+            PooledByte = statistics.pooled_byte;
+            PooledCount = statistics.pooled_count;
+            HitCount = statistics.hit_count;
+            MissCount = statistics.miss_count;
+            EvictedCount = statistics.evicted_count;
+        }
+        public override string ToString()
+        {
+            return $"pooled {PooledCount} ({PooledByte}/{BudgetByte} byte) hit {HitCount} miss {MissCount} evicted {EvictedCount}";
+        }
+    }
+
+    public readonly struct TTCEWgpuBindGroupStatistics
+    {
+        public readonly ulong CreatedCount;
+        public readonly ulong ReusedCount;
+
+        internal TTCEWgpuBindGroupStatistics(TTBindGroupStatistics statistics)
+        {
+            CreatedCount = statistics.created_count;
+            ReusedCount = statistics.reused_count;
+        }
+        public override string ToString()
+        {
+            return $"bind group created {CreatedCount} reused {ReusedCount}";
+        }
+    }
+
     public class TTCEWgpuDevice : IDisposable
     {
         TexTransCoreEngineDeviceHandler? _handler;
@@ -14,10 +54,23 @@ namespace net.rs64.TexTransCoreEngineForWgpu
         private HashSet<TTCEWgpuContextBase> _contexts;
         public bool AllowShaderCreation => _contexts.Count == 0;
         private bool isShaderRegistered = false;
-        public TTCEWgpuDevice(RequestDevicePreference preference = RequestDevicePreference.Auto)
+        private string? _pipelineCachePath;
+        /// <param name="pipelineCachePath"> compute pipeline のキャッシュファイルのパス、存在しない場合は SavePipelineCache で作られる。 null の場合は使用しない。 </param>
+        public TTCEWgpuDevice(RequestDevicePreference preference = RequestDevicePreference.Auto, string? pipelineCachePath = null)
         {
-            _handler = TexTransCoreEngineDeviceHandler.Create(preference);
+            _handler = TexTransCoreEngineDeviceHandler.Create(preference, pipelineCachePath);
             _contexts = new();
+            _pipelineCachePath = pipelineCachePath;
+
+            RegisterFormatConvertor();
+            SetDefaultTextureFormat(TexTransCore.TexTransCoreTextureFormat.Byte);
+        }
+        /// <param name="adapterIndex"> TTCEWgpuAdapter.EnumerateAdapters で得た TTCEWgpuAdapterInfo.Index </param>
+        public TTCEWgpuDevice(uint adapterIndex, string? pipelineCachePath = null)
+        {
+            _handler = TexTransCoreEngineDeviceHandler.CreateFromAdapterIndex(adapterIndex, pipelineCachePath);
+            _contexts = new();
+            _pipelineCachePath = pipelineCachePath;
 
             RegisterFormatConvertor();
             SetDefaultTextureFormat(TexTransCore.TexTransCoreTextureFormat.Byte);
@@ -27,8 +80,25 @@ namespace net.rs64.TexTransCoreEngineForWgpu
             Auto,
             DiscreteGPU,
             IntegratedGPUOrCPU,
+            SoftwareFallback,
         }
 
+        /// <summary>
+        /// コンストラクタで指定された pipelineCachePath に compute pipeline のキャッシュを保存する。指定されていないか、バックエンドが対応していない場合は何もしない。
+        /// </summary>
+        public void SavePipelineCache()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+            if (_pipelineCachePath is null) { return; }
+
+            unsafe
+            {
+                fixed (char* pathPtr = _pipelineCachePath)
+                {
+                    NativeMethod.save_pipeline_cache((void*)_handler.DangerousGetHandle(), (ushort*)pathPtr, _pipelineCachePath.Length).ThrowIfFailed();
+                }
+            }
+        }
         private void RegisterFormatConvertor()
         {
             if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
@@ -36,7 +106,20 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
             unsafe
             {
-                NativeMethod.register_format_convertor((void*)_handler.DangerousGetHandle());
+                NativeMethod.register_format_convertor((void*)_handler.DangerousGetHandle()).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// EncodeBlockCompression で使うエンコーダーを登録する。パイプラインはフォーマットと品質の組み合わせごとに、初めて使われるときに作られる。
+        /// </summary>
+        public void RegisterBlockCompressor()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+            if (AllowShaderCreation is false) { throw new InvalidOperationException("shader creation is not allowed"); }
+
+            unsafe
+            {
+                NativeMethod.register_block_compressor((void*)_handler.DangerousGetHandle()).ThrowIfFailed();
             }
         }
         protected void SetDefaultTextureFormat(TexTransCore.TexTransCoreTextureFormat format)
@@ -47,7 +130,96 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
             unsafe
             {
-                NativeMethod.set_default_texture_format((void*)_handler.DangerousGetHandle(), (TexTransCoreTextureFormat)format);
+                NativeMethod.set_default_texture_format((void*)_handler.DangerousGetHandle(), (TexTransCoreTextureFormat)format).ThrowIfFailed();
+            }
+        }
+        public const ulong DefaultShaderCacheMaxSize = 256 * 1024 * 1024;
+        /// <summary>
+        /// HLSL のコンパイル結果をディスクにキャッシュするディレクトリを指定する。 null の場合はキャッシュを使用しなくなる。
+        /// シェーダーを登録する前に行うように。
+        /// </summary>
+        public void SetShaderCacheDirectory(string? directory, ulong maxSizeByte = DefaultShaderCacheMaxSize)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+
+            unsafe
+            {
+                if (directory is null)
+                {
+                    NativeMethod.set_shader_cache_directory((void*)_handler.DangerousGetHandle(), null, 0, maxSizeByte).ThrowIfFailed();
+                    return;
+                }
+                fixed (char* directoryPtr = directory)
+                {
+                    NativeMethod.set_shader_cache_directory((void*)_handler.DangerousGetHandle(), (ushort*)directoryPtr, directory.Length, maxSizeByte).ThrowIfFailed();
+                }
+            }
+        }
+        public void ClearShaderCache()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.clear_shader_cache((void*)_handler.DangerousGetHandle()).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// ブロックせずに GPU の状態を進め、完了した TTWgpuDownloadTicket の onCompleted を呼ぶ。 onCompleted を使う場合は毎フレームなど定期的に呼ぶように。
+        /// </summary>
+        public void Poll()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.poll_tex_trans_core_engine_device((void*)_handler.DangerousGetHandle()).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// ドロップされたレンダーテクスチャーを再利用のために保持しておく合計サイズの上限。 0 の場合はプールしなくなる。
+        /// </summary>
+        public void SetRenderTexturePoolBudget(ulong budgetByte)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.set_render_texture_pool_budget((void*)_handler.DangerousGetHandle(), budgetByte).ThrowIfFailed();
+            }
+        }
+        public void ClearRenderTexturePool()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.clear_render_texture_pool((void*)_handler.DangerousGetHandle()).ThrowIfFailed();
+            }
+        }
+        public TTCEWgpuRenderTexturePoolStatistics GetRenderTexturePoolStatistics()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+
+            unsafe
+            {
+                RenderTexturePoolStatistics statistics;
+                NativeMethod.get_render_texture_pool_statistics((void*)_handler.DangerousGetHandle(), &statistics).ThrowIfFailed();
+                return new TTCEWgpuRenderTexturePoolStatistics(statistics);
+            }
+        }
+        /// <summary>
+        /// TTWgpuComputeHandler の Dispatch で bind group が作られた回数と、バインディングが変わらなかったために再利用された回数。
+        /// </summary>
+        public TTCEWgpuBindGroupStatistics GetBindGroupStatistics()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
+
+            unsafe
+            {
+                TTBindGroupStatistics statistics;
+                NativeMethod.get_bind_group_statistics((void*)_handler.DangerousGetHandle(), &statistics).ThrowIfFailed();
+                return new TTCEWgpuBindGroupStatistics(statistics);
             }
         }
         public TTComputeShaderID RegisterComputeShaderFromHLSL(string hlslPath, string? hlslSource = null)
@@ -62,9 +234,10 @@ namespace net.rs64.TexTransCoreEngineForWgpu
                     fixed (char* pathPtr = hlslPath)
                     fixed (char* sourcePtr = hlslSource)
                     {
-                        var idResult = NativeMethod.register_compute_shader_from_hlsl((void*)_handler.DangerousGetHandle(), (ushort*)pathPtr, hlslPath.Length, (ushort*)sourcePtr, hlslSource.Length);
-                        if (idResult.result is false) { throw new Exception("register hlsl failed!, Please see log! \nSourceHLSLPath:" + hlslPath + "\nHLSLSource\n" + hlslSource); }
-                        return new TTComputeShaderID(idResult.compute_shader_id);
+                        uint id;
+                        var status = NativeMethod.register_compute_shader_from_hlsl((void*)_handler.DangerousGetHandle(), (ushort*)pathPtr, hlslPath.Length, (ushort*)sourcePtr, hlslSource.Length, &id);
+                        if (status is not TTCEWgpuStatus.Ok) { throw TTCEWgpuNativeError.FromLastError(status, "register hlsl failed! \nSourceHLSLPath:" + hlslPath + "\nHLSLSource\n" + hlslSource); }
+                        return new TTComputeShaderID(id);
                     }
                 }
             else
@@ -72,9 +245,10 @@ namespace net.rs64.TexTransCoreEngineForWgpu
                 {
                     fixed (char* pathPtr = hlslPath)
                     {
-                        var idResult = NativeMethod.register_compute_shader_from_hlsl((void*)_handler.DangerousGetHandle(), (ushort*)pathPtr, hlslPath.Length, (ushort*)IntPtr.Zero, 0);
-                        if (idResult.result is false) { throw new Exception("register hlsl failed!, Please see log! \nSourceHLSLPath:" + hlslPath + "\nSource is file original text"); }
-                        return new TTComputeShaderID(idResult.compute_shader_id);
+                        uint id;
+                        var status = NativeMethod.register_compute_shader_from_hlsl((void*)_handler.DangerousGetHandle(), (ushort*)pathPtr, hlslPath.Length, (ushort*)IntPtr.Zero, 0, &id);
+                        if (status is not TTCEWgpuStatus.Ok) { throw TTCEWgpuNativeError.FromLastError(status, "register hlsl failed! \nSourceHLSLPath:" + hlslPath + "\nSource is file original text"); }
+                        return new TTComputeShaderID(id);
                     }
                 }
         }
@@ -84,7 +258,9 @@ namespace net.rs64.TexTransCoreEngineForWgpu
             if (_handler is null) { throw new ObjectDisposedException("TexTransCoreEngineDeviceHandler is dropped"); }
             unsafe
             {
-                var ptr = new IntPtr(NativeMethod.get_ttce_context((void*)_handler.DangerousGetHandle()));
+                void* ctxPtr;
+                NativeMethod.get_ttce_context((void*)_handler.DangerousGetHandle(), &ctxPtr).ThrowIfFailed();
+                var ptr = new IntPtr(ctxPtr);
 
                 var ctx = new TTCE();
                 ctx.NativeInitialize(this, new TexTransCoreEngineContextHandler(ptr));
@@ -170,7 +346,25 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
     class TexTransCoreEngineDeviceHandler : SafeHandle
     {
-        unsafe public static TexTransCoreEngineDeviceHandler Create(TTCEWgpuDevice.RequestDevicePreference preference) { return new TexTransCoreEngineDeviceHandler(new IntPtr(NativeMethod.create_tex_trans_core_engine_device((RequestDevicePreference)preference))); }
+        // pipelineCachePath が null の場合は null pointer が渡り、キャッシュは使われない
+        unsafe public static TexTransCoreEngineDeviceHandler Create(TTCEWgpuDevice.RequestDevicePreference preference, string? pipelineCachePath)
+        {
+            void* devicePtr;
+            fixed (char* pathPtr = pipelineCachePath)
+            {
+                NativeMethod.create_tex_trans_core_engine_device((RequestDevicePreference)preference, (ushort*)pathPtr, pipelineCachePath?.Length ?? 0, &devicePtr).ThrowIfFailed();
+            }
+            return new TexTransCoreEngineDeviceHandler(new IntPtr(devicePtr));
+        }
+        unsafe public static TexTransCoreEngineDeviceHandler CreateFromAdapterIndex(uint adapterIndex, string? pipelineCachePath)
+        {
+            void* devicePtr;
+            fixed (char* pathPtr = pipelineCachePath)
+            {
+                NativeMethod.create_device_from_adapter_index(adapterIndex, (ushort*)pathPtr, pipelineCachePath?.Length ?? 0, &devicePtr).ThrowIfFailed();
+            }
+            return new TexTransCoreEngineDeviceHandler(new IntPtr(devicePtr));
+        }
         public TexTransCoreEngineDeviceHandler(IntPtr handle) : base(IntPtr.Zero, true)
         {
             SetHandle(handle);
@@ -180,8 +374,7 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
         protected override bool ReleaseHandle()
         {
-            unsafe { NativeMethod.drop_tex_trans_core_engine_device((void*)handle); }
-            return true;
+            unsafe { return NativeMethod.drop_tex_trans_core_engine_device((void*)handle) is TTCEWgpuStatus.Ok; }
         }
     }
 
@@ -206,10 +399,17 @@ namespace net.rs64.TexTransCoreEngineForWgpu
     [Serializable]
     internal class TTCEWgpuNativeError : Exception
     {
+        public TTCEWgpuError? Kind { get; }
+
         public TTCEWgpuNativeError()
         {
         }
 
+        public TTCEWgpuNativeError(TTCEWgpuError? kind, string message) : base(message)
+        {
+            Kind = kind;
+        }
+
         public TTCEWgpuNativeError(string message) : base(message)
         {
         }
@@ -221,6 +421,32 @@ namespace net.rs64.TexTransCoreEngineForWgpu
         protected TTCEWgpuNativeError(SerializationInfo info, StreamingContext context) : base(info, context)
         {
         }
+
+        internal static unsafe TTCEWgpuNativeError FromLastError(TTCEWgpuStatus status, string? context = null)
+        {
+            TTCEWgpuError kind;
+            TTCEWgpuError? kindOrNull = NativeMethod.get_last_error_kind(&kind) ? kind : null;
+
+            var messageLen = NativeMethod.get_last_error_message(null, 0);
+            var messageBuffer = new char[messageLen];
+            fixed (char* messagePtr = messageBuffer)
+            {
+                NativeMethod.get_last_error_message((ushort*)messagePtr, messageBuffer.Length);
+            }
+            NativeMethod.clear_last_error();
+
+            var message = status + " " + kindOrNull + " : " + new string(messageBuffer);
+            if (context is not null) { message = context + "\n" + message; }
+            return new TTCEWgpuNativeError(kindOrNull, message);
+        }
+    }
+    internal static class TTCEWgpuStatusExtension
+    {
+        public static void ThrowIfFailed(this TTCEWgpuStatus status)
+        {
+            if (status is TTCEWgpuStatus.Ok) { return; }
+            throw TTCEWgpuNativeError.FromLastError(status);
+        }
     }
 
 }
@@ -26,59 +26,67 @@ namespace net.rs64.TexTransCoreEngineForWgpu
             {
                 fixed (char* namePtr = name)
                 {
-                    var result = NativeMethod.get_bind_index((void*)_handler.DangerousGetHandle(), (ushort*)namePtr, name.Length);
+                    TTBindingPoint bindPoint;
+                    var status = NativeMethod.get_bind_index((void*)_handler.DangerousGetHandle(), (ushort*)namePtr, name.Length, &bindPoint);
 
-                    if (result.result is false) { throw new ArgumentException(); }
+                    if (status is not TTCEWgpuStatus.Ok) { throw new ArgumentException(TTCEWgpuNativeError.FromLastError(status).Message); }
 
-                    return (int)result.bind_index;
+                    return PackBindingPoint(bindPoint);
                 }
             }
         }
 
+        // ITTComputeHandler の ID は int のため、 register の space (group) を上位 8 bit 、番号 (binding) を下位 24 bit に詰める
+        const int BindingBits = 24;
+        const uint BindingMask = (1u << BindingBits) - 1;
+        static int PackBindingPoint(TTBindingPoint bindPoint)
+        {
+            if (bindPoint.group > byte.MaxValue || bindPoint.binding > BindingMask) { throw new NotSupportedException($"binding point is too large : space{bindPoint.group} {bindPoint.binding}"); }
+            return (int)((bindPoint.group << BindingBits) | bindPoint.binding);
+        }
+        static TTBindingPoint UnpackBindingPoint(int nameID)
+        {
+            return new TTBindingPoint() { group = (uint)nameID >> BindingBits, binding = (uint)nameID & BindingMask };
+        }
+
 
         public void UploadConstantsBuffer<T>(int nameID, ReadOnlySpan<T> buffer) where T : unmanaged
         {
             if (_handler is null) { throw new ObjectDisposedException("TTComputeHandlerPtrHandler is dropped"); }
 
-            bool result;
             unsafe
             {
                 fixed (T* bufferPtr = buffer)
                 {
-                    result = NativeMethod.upload_constants_buffer((void*)_handler.DangerousGetHandle(), (uint)nameID, (byte*)bufferPtr, buffer.Length * sizeof(T));
+                    NativeMethod.upload_constants_buffer((void*)_handler.DangerousGetHandle(), UnpackBindingPoint(nameID), (byte*)bufferPtr, buffer.Length * sizeof(T)).ThrowIfFailed();
                 }
             }
-            if (result is false)
-            {
-                throw new TTCEWgpuNativeError("Buffer upload failed! please see log!");
-            }
         }
         public void SetStorageBuffer(int nameID, TTWgpuStorageBuffer bufferHolder)
         {
             if (_handler is null) { throw new ObjectDisposedException("TTComputeHandlerPtrHandler is dropped"); }
 
-            bool result;
             unsafe
             {
-                result = NativeMethod.set_storage_buffer((void*)_handler.DangerousGetHandle(), (uint)nameID, (void*)bufferHolder.GetPtr());
-            }
-            if (result is false)
-            {
-                throw new TTCEWgpuNativeError("Buffer upload failed! please see log!");
+                NativeMethod.set_storage_buffer((void*)_handler.DangerousGetHandle(), UnpackBindingPoint(nameID), (void*)bufferHolder.GetPtr()).ThrowIfFailed();
             }
         }
         public void SetRenderTexture(int nameID, TTWgpuRenderTexture renderTexture)
         {
             if (_handler is null) { throw new ObjectDisposedException("TTComputeHandlerPtrHandler is dropped"); }
 
-            bool result;
             unsafe
             {
-                result = NativeMethod.set_render_texture((void*)_handler.DangerousGetHandle(), (uint)nameID, (void*)renderTexture.GetPtr());
+                NativeMethod.set_render_texture((void*)_handler.DangerousGetHandle(), UnpackBindingPoint(nameID), (void*)renderTexture.GetPtr()).ThrowIfFailed();
             }
-            if (result is false)
+        }
+        public void SetSampler(int nameID, TTWgpuSampler sampler)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTComputeHandlerPtrHandler is dropped"); }
+
+            unsafe
             {
-                throw new TTCEWgpuNativeError("Buffer upload failed! please see log!");
+                NativeMethod.set_sampler((void*)_handler.DangerousGetHandle(), UnpackBindingPoint(nameID), (void*)sampler.GetPtr()).ThrowIfFailed();
             }
         }
 
@@ -87,7 +95,8 @@ namespace net.rs64.TexTransCoreEngineForWgpu
             if (_handler is null) { throw new ObjectDisposedException("TTComputeHandlerPtrHandler is dropped"); }
             unsafe
             {
-                var wgs = NativeMethod.get_work_group_size((void*)_handler.DangerousGetHandle());
+                WorkGroupSize wgs;
+                NativeMethod.get_work_group_size((void*)_handler.DangerousGetHandle(), &wgs).ThrowIfFailed();
                 return (wgs.x, wgs.y, wgs.z);
             }
         }
@@ -99,7 +108,31 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
             unsafe
             {
-                NativeMethod.dispatch((void*)_handler.DangerousGetHandle(), x, y, z);
+                NativeMethod.dispatch((void*)_handler.DangerousGetHandle(), x, y, z).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// renderTexture のすべてのピクセルを覆うように、 GetWorkGroupSize で割って切り上げた数で Dispatch する。
+        /// </summary>
+        public void DispatchForTexture(TTWgpuRenderTexture renderTexture)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTComputeHandlerPtrHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.dispatch_for_texture((void*)_handler.DangerousGetHandle(), (void*)renderTexture.GetPtr()).ThrowIfFailed();
+            }
+        }
+        /// <summary>
+        /// indirectBuffer の indirectOffset (byte) から uint の x y z を読んで Dispatch する。 indirectBuffer は indirect を有効にして作られている必要がある。
+        /// </summary>
+        public void DispatchIndirect(TTWgpuStorageBuffer indirectBuffer, ulong indirectOffset = 0)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTComputeHandlerPtrHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.dispatch_indirect((void*)_handler.DangerousGetHandle(), (void*)indirectBuffer.GetPtr(), indirectOffset).ThrowIfFailed();
             }
         }
 
@@ -150,8 +183,7 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
         protected override bool ReleaseHandle()
         {
-            unsafe { NativeMethod.drop_compute_handler((void*)handle); }
-            return true;
+            unsafe { return NativeMethod.drop_compute_handler((void*)handle) is TTCEWgpuStatus.Ok; }
         }
     }
 }
@@ -0,0 +1,111 @@
+using System;
+using System.Runtime.InteropServices;
+namespace net.rs64.TexTransCoreEngineForWgpu
+{
+    /// <summary>
+    /// 完了を待たずに要求されたダウンロード。 Poll か Wait で完了を確認してから ReadTo で読み出すように。
+    /// 読み出した後は Dispose すると読み戻し用のバッファーが再利用される。
+    /// </summary>
+    public sealed class TTWgpuDownloadTicket : IDisposable
+    {
+        public enum DownloadState : uint
+        {
+            Pending,
+            Ready,
+            Failed,
+        }
+
+        TTDownloadTicketHandler? _handler;
+        private bool _isDisposed;
+        public int DataSize { get; }
+
+        internal TTWgpuDownloadTicket(TTDownloadTicketHandler handler)
+        {
+            _handler = handler;
+            unsafe
+            {
+                ulong dataSize;
+                NativeMethod.get_download_ticket_data_size((void*)_handler.DangerousGetHandle(), &dataSize).ThrowIfFailed();
+                DataSize = checked((int)dataSize);
+            }
+        }
+
+        /// <summary> ブロックせずに状態を進める。 </summary>
+        public DownloadState Poll()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTDownloadTicket is dropped"); }
+
+            unsafe
+            {
+                TTDownloadTicketState state;
+                NativeMethod.poll_download_ticket((void*)_handler.DangerousGetHandle(), &state).ThrowIfFailed();
+                return (DownloadState)state;
+            }
+        }
+        /// <summary> 完了するまでブロックする。 </summary>
+        public DownloadState Wait()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTDownloadTicket is dropped"); }
+
+            unsafe
+            {
+                TTDownloadTicketState state;
+                NativeMethod.wait_download_ticket((void*)_handler.DangerousGetHandle(), &state).ThrowIfFailed();
+                return (DownloadState)state;
+            }
+        }
+        public void ReadTo<T>(Span<T> dataDist) where T : unmanaged
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTDownloadTicket is dropped"); }
+
+            unsafe
+            {
+                var ptrLen = dataDist.Length * sizeof(T);
+                if (ptrLen != DataSize) { throw new ArgumentOutOfRangeException(); }
+
+                fixed (T* ptr = dataDist)
+                {
+                    NativeMethod.read_download_ticket((void*)_handler.DangerousGetHandle(), (byte*)ptr, ptrLen).ThrowIfFailed();
+                }
+            }
+        }
+
+        [UnmanagedFunctionPointer(CallingConvention.Cdecl)]
+        unsafe delegate void CompletedCallbackDelegate(void* userData, TTDownloadTicketState state);
+        static CompletedCallbackDelegate? s_completedCallback;
+        unsafe static void CompletedCallback(void* userData, TTDownloadTicketState state)
+        {
+            var handle = GCHandle.FromIntPtr((IntPtr)userData);
+            var onCompleted = (Action<DownloadState>)handle.Target!;
+            handle.Free();
+            onCompleted.Invoke((DownloadState)state);
+        }
+        internal static unsafe delegate* unmanaged[Cdecl]<void*, TTDownloadTicketState, void> GetCompletedCallbackPointer()
+        {
+            s_completedCallback ??= new(CompletedCallback);
+            return (delegate* unmanaged[Cdecl]<void*, TTDownloadTicketState, void>)Marshal.GetFunctionPointerForDelegate(s_completedCallback);
+        }
+
+        public void Dispose()
+        {
+            if (_isDisposed) { return; }
+            _handler?.Dispose();
+            _handler = null;
+            _isDisposed = true;
+        }
+    }
+    class TTDownloadTicketHandler : SafeHandle
+    {
+        public TTDownloadTicketHandler(IntPtr handle) : base(IntPtr.Zero, true)
+        {
+            SetHandle(handle);
+        }
+
+        public override bool IsInvalid => handle == IntPtr.Zero;
+
+        protected override bool ReleaseHandle()
+        {
+            unsafe { return NativeMethod.drop_download_ticket((void*)handle) is TTCEWgpuStatus.Ok; }
+        }
+    }
+}
@@ -36,7 +36,9 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
             unsafe
             {
-                return NativeMethod.get_width((void*)_handler.DangerousGetHandle());
+                uint width;
+                NativeMethod.get_width((void*)_handler.DangerousGetHandle(), &width).ThrowIfFailed();
+                return width;
             }
         }
         public uint GetHeight()
@@ -45,7 +47,67 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
             unsafe
             {
-                return NativeMethod.get_height((void*)_handler.DangerousGetHandle());
+                uint height;
+                NativeMethod.get_height((void*)_handler.DangerousGetHandle(), &height).ThrowIfFailed();
+                return height;
+            }
+        }
+
+        public uint GetMipLevelCount()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTRenderTextureHandler is dropped"); }
+
+            unsafe
+            {
+                uint mipLevelCount;
+                NativeMethod.get_mip_level_count((void*)_handler.DangerousGetHandle(), &mipLevelCount).ThrowIfFailed();
+                return mipLevelCount;
+            }
+        }
+
+        public uint GetDepthOrArrayLayers()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTRenderTextureHandler is dropped"); }
+
+            unsafe
+            {
+                uint depthOrArrayLayers;
+                NativeMethod.get_depth_or_array_layers((void*)_handler.DangerousGetHandle(), &depthOrArrayLayers).ThrowIfFailed();
+                return depthOrArrayLayers;
+            }
+        }
+        public TTWgpuTextureDimension GetDimension()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTRenderTextureHandler is dropped"); }
+
+            unsafe
+            {
+                TTRtDimension dimension;
+                NativeMethod.get_dimension((void*)_handler.DangerousGetHandle(), &dimension).ThrowIfFailed();
+                return (TTWgpuTextureDimension)dimension;
+            }
+        }
+        public TTWgpuColorSpace GetColorSpace()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTRenderTextureHandler is dropped"); }
+
+            unsafe
+            {
+                TTColorSpace colorSpace;
+                NativeMethod.get_color_space((void*)_handler.DangerousGetHandle(), &colorSpace).ThrowIfFailed();
+                return (TTWgpuColorSpace)colorSpace;
+            }
+        }
+        /// <summary>
+        /// 中身がどの色空間かの印を付け替える。中身は変換されないので、変換する場合は TTCEWgpuContextBase.ConvertColorSpace を使うように。
+        /// </summary>
+        public void SetColorSpace(TTWgpuColorSpace colorSpace)
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTRenderTextureHandler is dropped"); }
+
+            unsafe
+            {
+                NativeMethod.set_color_space((void*)_handler.DangerousGetHandle(), (TTColorSpace)colorSpace).ThrowIfFailed();
             }
         }
 
@@ -80,6 +142,42 @@ namespace net.rs64.TexTransCoreEngineForWgpu
         }
 
     }
+    public enum TTWgpuMipmapFilter : uint
+    {
+        Box = 0,
+        Kaiser = 1,
+    }
+    public enum TTWgpuBlitFilter : uint
+    {
+        Nearest = 0,
+        Bilinear = 1,
+        /// <summary> Catmull-Rom </summary>
+        Bicubic = 2,
+        /// <summary> Lanczos3 </summary>
+        Lanczos = 3,
+    }
+    public enum TTWgpuTextureDimension : uint
+    {
+        /// <summary> レイヤー数が 2 以上の場合は 2D 配列 </summary>
+        D2 = 0,
+        D3 = 1,
+    }
+    public enum TTWgpuChannelMapping : uint
+    {
+        /// <summary> 減る場合は先頭のチャンネルを残し、 R から増やす場合は RGB に広げる </summary>
+        Default = 0,
+        /// <summary> RGBA から R のみ、 RGB の輝度を R にする </summary>
+        Luminance = 1,
+        /// <summary> RGBA から R のみ、 A を R にする </summary>
+        Alpha = 2,
+    }
+    public enum TTWgpuColorSpace : uint
+    {
+        Linear = 0,
+        /// <summary> Byte の RGBA の場合、 Texture2D としてサンプリングするとリニアにデコードされる </summary>
+        Srgb = 1,
+        AcesCg = 2,
+    }
     class TTRenderTextureHandler : SafeHandle
     {
         public TTRenderTextureHandler(IntPtr handle) : base(IntPtr.Zero, true)
@@ -91,8 +189,7 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
         protected override bool ReleaseHandle()
         {
-            unsafe { NativeMethod.drop_render_texture((void*)handle); }
-            return true;
+            unsafe { return NativeMethod.drop_render_texture((void*)handle) is TTCEWgpuStatus.Ok; }
         }
     }
 }
@@ -0,0 +1,69 @@
+using System;
+using System.Runtime.InteropServices;
+namespace net.rs64.TexTransCoreEngineForWgpu
+{
+    /// <summary>
+    /// SamplerState として宣言されたバインディングに渡すもの。コンテキストをまたいで使いまわしてよい。
+    /// </summary>
+    public sealed class TTWgpuSampler : IDisposable
+    {
+        TTSamplerHandler? _handler;
+        private bool _isDisposed;
+
+        internal TTWgpuSampler(TTSamplerHandler handler)
+        {
+            _handler = handler;
+        }
+
+        internal IntPtr GetPtr()
+        {
+            if (_handler is null) { throw new ObjectDisposedException("TTSampler is dropped"); }
+
+            return _handler.DangerousGetHandle();
+        }
+
+        void Dispose(bool disposing)
+        {
+            if (_isDisposed) { return; }
+
+            if (disposing)
+            {
+                _handler?.Dispose();
+                _handler = null;
+            }
+
+            _isDisposed = true;
+        }
+        public void Dispose()
+        {
+            Dispose(true);
+            GC.SuppressFinalize(this);
+        }
+    }
+    public enum TTWgpuSamplerFilterMode : uint
+    {
+        Point = 0,
+        Bilinear = 1,
+        Trilinear = 2,
+    }
+    public enum TTWgpuSamplerAddressMode : uint
+    {
+        Clamp = 0,
+        Repeat = 1,
+        Mirror = 2,
+    }
+    class TTSamplerHandler : SafeHandle
+    {
+        public TTSamplerHandler(IntPtr handle) : base(IntPtr.Zero, true)
+        {
+            SetHandle(handle);
+        }
+
+        public override bool IsInvalid => handle == IntPtr.Zero;
+
+        protected override bool ReleaseHandle()
+        {
+            unsafe { return NativeMethod.drop_sampler((void*)handle) is TTCEWgpuStatus.Ok; }
+        }
+    }
+}
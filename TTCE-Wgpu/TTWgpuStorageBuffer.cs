@@ -61,8 +61,7 @@ namespace net.rs64.TexTransCoreEngineForWgpu
 
         protected override bool ReleaseHandle()
         {
-            unsafe { NativeMethod.drop_storage_buffer((void*)handle); }
-            return true;
+            unsafe { return NativeMethod.drop_storage_buffer((void*)handle) is TTCEWgpuStatus.Ok; }
         }
     }
 }
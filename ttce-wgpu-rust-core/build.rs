@@ -1,6 +1,19 @@
 fn main() {
     csbindgen::Builder::default()
         .input_extern_file("src/lib.rs")
+        .input_extern_file("src/tex_trans_core_engine.rs")
+        .input_extern_file("src/compute_shader.rs")
+        .input_extern_file("src/render_texture.rs")
+        .input_extern_file("src/render_texture_pool.rs")
+        .input_extern_file("src/download_ticket.rs")
+        .input_extern_file("src/mipmap.rs")
+        .input_extern_file("src/blit.rs")
+        .input_extern_file("src/sampler.rs")
+        .input_extern_file("src/constants_buffer.rs")
+        .input_extern_file("src/color_space.rs")
+        .input_extern_file("src/image_file.rs")
+        .input_extern_file("src/block_compression.rs")
+        .input_extern_file("src/block_decompression.rs")
         .csharp_dll_name("ttce_wgpu_rust_core")
         .csharp_namespace("net.rs64.TexTransCoreEngineForWgpu")
         .csharp_class_name("NativeMethod")
@@ -0,0 +1,172 @@
+use once_cell::sync::OnceCell;
+use wgpu::{Backends, DeviceType};
+
+use crate::tex_trans_core_engine::{TTCEWgpuError, TTCEWgpuErrorRecord};
+use crate::{debug_log, get_tokio_runtime, RequestDevicePreference};
+
+static WGPU_INSTANCE: OnceCell<wgpu::Instance> = OnceCell::new();
+fn get_wgpu_instance() -> &'static wgpu::Instance {
+    WGPU_INSTANCE.get_or_init(wgpu::Instance::default)
+}
+
+/// 列挙される順番は同じ環境であれば毎回同じになり、 index はこの順番を指す。
+pub fn enumerate_adapters() -> Vec<wgpu::Adapter> {
+    get_wgpu_instance().enumerate_adapters(Backends::all())
+}
+
+pub fn get_adapter(adapter_index: u32) -> Result<wgpu::Adapter, TTCEWgpuErrorRecord> {
+    let mut adapters = enumerate_adapters();
+    let adapter_count = adapters.len();
+    if adapter_index as usize >= adapter_count {
+        return Err(TTCEWgpuError::AdapterNotFound.with_message(format!(
+            "adapter index {} is out of range, adapter count is {}",
+            adapter_index, adapter_count
+        )));
+    }
+    Ok(adapters.swap_remove(adapter_index as usize))
+}
+
+pub(crate) fn ttce_required_features() -> wgpu::Features {
+    wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+        | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM
+}
+/// アダプターが対応している場合のみ有効にする機能
+pub(crate) fn ttce_optional_features() -> wgpu::Features {
+    // FLOAT32_FILTERABLE が無い場合、 32bit float のテクスチャーはサンプラーで読まれる Texture2D などにバインドできない
+    // TEXTURE_COMPRESSION_BC が無い場合、ブロック圧縮のデコードはコンピュートシェーダーで行う
+    // PUSH_CONSTANTS が無い場合、 HLSL の cbuffer はすべて dynamic offset の uniform buffer になる
+    wgpu::Features::PIPELINE_CACHE
+        | wgpu::Features::FLOAT32_FILTERABLE
+        | wgpu::Features::TEXTURE_COMPRESSION_BC
+        | wgpu::Features::PUSH_CONSTANTS
+}
+/// PUSH_CONSTANTS が有効な場合に要求する push constant の大きさ、 Vulkan で保証されている最小値
+const TTCE_MAX_PUSH_CONSTANT_SIZE: u32 = 128;
+pub(crate) fn ttce_required_limits() -> wgpu::Limits {
+    wgpu::Limits {
+        max_storage_textures_per_shader_stage: 8,
+        max_bind_groups: 4,
+        ..Default::default()
+    }
+}
+pub fn is_supported_adapter(adapter: &wgpu::Adapter) -> bool {
+    adapter.features().contains(ttce_required_features())
+        && ttce_required_limits().check_limits(&adapter.limits())
+}
+
+// ソフトウェアのアダプターは Vulkan (lavapipe) のほうが GL (llvmpipe) より機能が揃っているのでそちらを優先する。
+fn software_backend_rank(backend: wgpu::Backend) -> u32 {
+    match backend {
+        wgpu::Backend::Vulkan => 0,
+        wgpu::Backend::Dx12 | wgpu::Backend::Metal => 1,
+        wgpu::Backend::Gl => 2,
+        wgpu::Backend::Empty | wgpu::Backend::BrowserWebGpu => 3,
+    }
+}
+
+fn select_adapter(preference: &RequestDevicePreference) -> Option<wgpu::Adapter> {
+    let mut candidates = enumerate_adapters().into_iter().filter(|a| {
+        let supported = is_supported_adapter(a);
+        if !supported {
+            debug_log(&format!(
+                "adapter skipped, required features or limits are not supported : {}",
+                a.get_info().name
+            ));
+        }
+        supported
+    });
+
+    match preference {
+        // 種類による順位付けはせず列挙順で最初のものを選ぶ、選び分けたい場合は adapter index で指定する。
+        RequestDevicePreference::Auto => candidates.find(|a| {
+            // OpenGL 系を排除する
+            matches!(
+                a.get_info().backend,
+                wgpu::Backend::Dx12 | wgpu::Backend::Vulkan | wgpu::Backend::Metal
+            )
+        }),
+        RequestDevicePreference::IntegratedGPUOrCPU => candidates.find(|a| {
+            let device_type = a.get_info().device_type;
+            device_type == DeviceType::IntegratedGpu || device_type == DeviceType::Cpu
+        }),
+        RequestDevicePreference::DiscreteGPU => {
+            candidates.find(|a| a.get_info().device_type == DeviceType::DiscreteGpu)
+        }
+        RequestDevicePreference::SoftwareFallback => candidates
+            .filter(|a| a.get_info().device_type == DeviceType::Cpu)
+            .min_by_key(|a| software_backend_rank(a.get_info().backend)),
+    }
+}
+
+pub fn request_device_with_preference(
+    preference: RequestDevicePreference,
+) -> Result<(wgpu::Device, wgpu::Queue), TTCEWgpuErrorRecord> {
+    request_device_with_preference_and_features(preference, ttce_optional_features())
+}
+/// optional_features に含まれていない任意の機能は、アダプターが対応していても有効にしない。
+pub fn request_device_with_preference_and_features(
+    preference: RequestDevicePreference,
+    optional_features: wgpu::Features,
+) -> Result<(wgpu::Device, wgpu::Queue), TTCEWgpuErrorRecord> {
+    let adapter = if let Some(adapter) = select_adapter(&preference) {
+        adapter
+    } else {
+        let force_fallback_adapter = preference == RequestDevicePreference::SoftwareFallback;
+        debug_log(&format!(
+            "no adapter matched with {:?}, fallback to default adapter request (force_fallback_adapter : {})",
+            preference, force_fallback_adapter
+        ));
+        let request_adapter_option = wgpu::RequestAdapterOptions {
+            force_fallback_adapter,
+            ..Default::default()
+        };
+        get_tokio_runtime()
+            .block_on(get_wgpu_instance().request_adapter(&request_adapter_option))
+            .ok_or_else(|| {
+                TTCEWgpuError::AdapterNotFound.with_message(format!(
+                    "fallback adapter request failed when ttce device creation with {:?}",
+                    preference
+                ))
+            })?
+    };
+
+    request_device_from_adapter_with_features(&adapter, optional_features)
+}
+
+pub fn request_device_from_adapter(
+    adapter: &wgpu::Adapter,
+) -> Result<(wgpu::Device, wgpu::Queue), TTCEWgpuErrorRecord> {
+    request_device_from_adapter_with_features(adapter, ttce_optional_features())
+}
+pub fn request_device_from_adapter_with_features(
+    adapter: &wgpu::Adapter,
+    optional_features: wgpu::Features,
+) -> Result<(wgpu::Device, wgpu::Queue), TTCEWgpuErrorRecord> {
+    debug_log(&format!("Adapter : \n{:?}", adapter.get_info()));
+
+    if !is_supported_adapter(adapter) {
+        return Err(TTCEWgpuError::DeviceRequestFailed.with_message(format!(
+            "adapter is not supported required features or limits : {}",
+            adapter.get_info().name
+        )));
+    }
+
+    let required_features = ttce_required_features()
+        | (adapter.features() & ttce_optional_features() & optional_features);
+    let mut required_limits = ttce_required_limits();
+    if required_features.contains(wgpu::Features::PUSH_CONSTANTS) {
+        required_limits.max_push_constant_size = adapter
+            .limits()
+            .max_push_constant_size
+            .min(TTCE_MAX_PUSH_CONSTANT_SIZE);
+    }
+    let device_feature = wgpu::DeviceDescriptor {
+        required_features,
+        required_limits,
+        ..Default::default()
+    };
+
+    get_tokio_runtime()
+        .block_on(adapter.request_device(&device_feature, None))
+        .map_err(|e| TTCEWgpuError::DeviceRequestFailed.with_message(e.to_string()))
+}
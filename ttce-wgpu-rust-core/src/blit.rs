@@ -0,0 +1,299 @@
+use std::sync::Arc;
+
+use crate::{
+    compute_shader::AsTypeStr,
+    render_texture::TTRenderTexture,
+    tex_trans_core_engine::{
+        RequestFormat, TTCEWgpuError, TTRtDimension, TTRtRequestDescriptor,
+        TexTransCoreEngineContext, TexTransCoreEngineDevice,
+    },
+    TTChannelMapping,
+};
+
+/// blit で解像度が変わる場合のフィルター。範囲外は端のピクセルが続いているものとして扱う。
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TTBlitFilter {
+    /// 最も近いピクセル
+    Nearest = 0,
+    /// 三角フィルター、拡大時はバイリニア補間と同じになる
+    Bilinear = 1,
+    /// Catmull-Rom のバイキュービック
+    Bicubic = 2,
+    /// Lanczos3
+    Lanczos = 3,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct BlitPipelineKey {
+    format: wgpu::TextureFormat,
+    filter: TTBlitFilter,
+}
+
+#[derive(Debug)]
+pub(crate) struct BlitPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+const BLIT_WORK_GROUP_SIZE: u32 = 8;
+
+impl TexTransCoreEngineDevice {
+    fn get_or_create_blit_pipeline(&self, key: BlitPipelineKey) -> Arc<BlitPipeline> {
+        let mut pipelines = self
+            .blit_pipelines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.create_blit_pipeline(key)))
+            .clone()
+    }
+
+    fn create_blit_pipeline(&self, key: BlitPipelineKey) -> BlitPipeline {
+        let wgsl_str = BLIT_TEMPLATE
+            .replace("$$$FORMAT$$$", key.format.as_type_str())
+            .replace("$$$FILTER$$$", &format!("{}u", key.filter as u32));
+
+        let cs_module = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("blit shader module"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
+            });
+
+        // mipmap と同じく、フィルタリングできないフォーマットも読めるように textureLoad のみで読む
+        let bind_group_layout =
+            self.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("blit bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::StorageTexture {
+                                access: wgpu::StorageTextureAccess::WriteOnly,
+                                format: key.format,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("blit pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = self
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("blit compute pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &cs_module,
+                entry_point: Some("CSMain"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: self.pipeline_cache(),
+            });
+
+        BlitPipeline {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+}
+
+impl TexTransCoreEngineContext<'_> {
+    /// src の mip 0 を dist の mip 0 の全体に、解像度とフォーマットとチャンネルを変換しながら書き込む。
+    /// 配列でない 2D のもの同士で、同じテクスチャーは指定できない。チャンネルの扱いは convert_texture と同じ。
+    /// 同じ解像度の場合はフィルターに関係なく convert_texture と同じ結果になる。
+    pub fn blit(
+        &mut self,
+        dist: &TTRenderTexture,
+        src: &TTRenderTexture,
+        filter: TTBlitFilter,
+        mapping: TTChannelMapping,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        let (_, src_channel) = src.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
+        let (dist_format, dist_channel) =
+            dist.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
+        if !dist.is_single_layer_2d() || !src.is_single_layer_2d() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch);
+        }
+        if dist.texture == src.texture {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
+        if dist.width() == src.width() && dist.height() == src.height() {
+            return self.convert_texture(dist, src, mapping);
+        }
+
+        // リサイズは src のチャンネルのまま dist のフォーマットで行い、チャンネルはコンバーターで変える
+        let resized_temp;
+        let resized = if src_channel == dist_channel {
+            dist
+        } else {
+            resized_temp = self.get_temporary_render_texture(&TTRtRequestDescriptor {
+                width: dist.width(),
+                height: dist.height(),
+                format: RequestFormat::Manual(dist_format, src_channel),
+                mip_level_count: 1,
+                dimension: TTRtDimension::D2,
+                depth_or_array_layers: 1,
+            });
+            &resized_temp
+        };
+
+        let engine = self.engine;
+        let blit_pipeline = engine.get_or_create_blit_pipeline(BlitPipelineKey {
+            format: resized.format(),
+            filter,
+        });
+        let mip_0_view = |render_texture: &TTRenderTexture| {
+            render_texture.create_view(&wgpu::TextureViewDescriptor {
+                base_mip_level: 0,
+                mip_level_count: Some(1),
+                ..Default::default()
+            })
+        };
+        let src_view = mip_0_view(src);
+        let dist_view = mip_0_view(resized);
+        let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("blit bind group"),
+            layout: &blit_pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&src_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&dist_view),
+                },
+            ],
+        });
+
+        {
+            let encoder = self.get_command_encoder_as_mut();
+            let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+
+            compute_pass.set_pipeline(&blit_pipeline.pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                resized.width().div_ceil(BLIT_WORK_GROUP_SIZE),
+                resized.height().div_ceil(BLIT_WORK_GROUP_SIZE),
+                1,
+            );
+        }
+        self.check_command_stack();
+
+        if !std::ptr::eq(resized, dist) {
+            self.convert_texture(dist, resized, mapping)?;
+        }
+        Ok(())
+    }
+}
+
+// 出力のピクセルの中心を src の座標に写し、その周りをフィルターの重みで平均する。
+// 縮小する場合はフィルターを縮小率に合わせて広げ、エイリアスを抑える。範囲外の座標は端に丸めて読む。
+pub const BLIT_TEMPLATE: &str = r#"
+@group(0) @binding(0)
+var SrcTex: texture_2d<f32>;
+@group(0) @binding(1)
+var DistTex: texture_storage_2d<$$$FORMAT$$$,write>;
+
+const FILTER: u32 = $$$FILTER$$$;
+
+const NEAREST: u32 = 0u;
+const BILINEAR: u32 = 1u;
+const BICUBIC: u32 = 2u;
+
+const PI: f32 = 3.14159265358979;
+
+fn filter_radius() -> f32 {
+    if (FILTER == BILINEAR) {
+        return 1.0;
+    }
+    if (FILTER == BICUBIC) {
+        return 2.0;
+    }
+    return 3.0;
+}
+
+fn filter_weight(t: f32) -> f32 {
+    let x = abs(t);
+    if (FILTER == BILINEAR) {
+        return max(0.0, 1.0 - x);
+    }
+    if (FILTER == BICUBIC) {
+        if (x < 1.0) {
+            return (1.5 * x - 2.5) * x * x + 1.0;
+        }
+        if (x < 2.0) {
+            return ((-0.5 * x + 2.5) * x - 4.0) * x + 2.0;
+        }
+        return 0.0;
+    }
+
+    if (x >= 3.0) {
+        return 0.0;
+    }
+    if (x < 0.00001) {
+        return 1.0;
+    }
+    let px = PI * x;
+    return 3.0 * sin(px) * sin(px / 3.0) / (px * px);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
+    let dist_size = textureDimensions(DistTex);
+    if (param.x >= dist_size.x || param.y >= dist_size.y) {
+        return;
+    }
+    let src_size = vec2<i32>(textureDimensions(SrcTex));
+    let last = src_size - vec2<i32>(1);
+
+    let scale = vec2<f32>(src_size) / vec2<f32>(dist_size);
+    let center = (vec2<f32>(param.xy) + 0.5) * scale;
+
+    if (FILTER == NEAREST) {
+        let pos = min(vec2<i32>(floor(center)), last);
+        textureStore(DistTex, vec2<i32>(param.xy), textureLoad(SrcTex, pos, 0));
+        return;
+    }
+
+    let filter_scale = max(scale, vec2<f32>(1.0));
+    let radius = filter_radius() * filter_scale;
+    let begin = vec2<i32>(floor(center - radius));
+    let end = vec2<i32>(ceil(center + radius));
+
+    var sum = vec4<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var y = begin.y; y < end.y; y++) {
+        let weight_y = filter_weight((f32(y) + 0.5 - center.y) / filter_scale.y);
+        if (weight_y == 0.0) {
+            continue;
+        }
+        for (var x = begin.x; x < end.x; x++) {
+            let weight = weight_y * filter_weight((f32(x) + 0.5 - center.x) / filter_scale.x);
+            let pos = clamp(vec2<i32>(x, y), vec2<i32>(0), last);
+            sum += textureLoad(SrcTex, pos, 0) * weight;
+            weight_sum += weight;
+        }
+    }
+    textureStore(DistTex, vec2<i32>(param.xy), sum / weight_sum);
+}
+"#;
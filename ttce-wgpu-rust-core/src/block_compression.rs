@@ -0,0 +1,674 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    compute_shader::{
+        TTBindingPoint, TTBindingType, TTComputeShader, TTComputeShaderEntry, TTComputeShaderID,
+        TTComputeShaderLayout, WorkGroupSize,
+    },
+    render_texture::TTRenderTexture,
+    storage_buffer::TTStorageBuffer,
+    tex_trans_core_engine::{
+        map_read_and_wait, RequestFormat, TTCEWgpuError, TTRtDimension, TTRtRequestDescriptor,
+        TexTransCoreEngineContext, TexTransCoreEngineDevice,
+    },
+    TTChannelMapping, TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+/// ブロック圧縮のフォーマット。 BC2 と BC6H はデコードのみ対応している。
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TTBlockCompressionFormat {
+    /// RGB と 1bit の A 、 A が 0.5 未満のピクセルは透明になる
+    BC1 = 0,
+    /// RGBA
+    BC3 = 1,
+    /// R
+    BC4 = 2,
+    /// RG
+    BC5 = 3,
+    /// RGBA 、エンコードはモード 6 のみを使う
+    BC7 = 4,
+    /// RGB と 4bit の A
+    BC2 = 5,
+    /// 符号なしの HDR の RGB
+    BC6H = 6,
+}
+impl TTBlockCompressionFormat {
+    /// 4x4 のブロック一つのバイト数
+    pub fn block_size(self) -> u32 {
+        match self {
+            TTBlockCompressionFormat::BC1 | TTBlockCompressionFormat::BC4 => 8,
+            _ => 16,
+        }
+    }
+    /// width x height を圧縮したときのバイト数、端の 4 に満たないブロックも一つとして数える。
+    pub fn compressed_size(self, width: u32, height: u32) -> u64 {
+        width.div_ceil(4) as u64 * height.div_ceil(4) as u64 * self.block_size() as u64
+    }
+}
+
+/// エンコードの品質、高いほど遅い。
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TTBlockCompressionQuality {
+    /// ブロックの最小値と最大値を端点にする
+    Fast = 0,
+    /// 主成分の方向に端点を取る
+    Normal = 1,
+    /// Normal の端点を最小二乗法で二回詰めなおす。 BC4 と BC5 は Normal と変わらない
+    High = 2,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct BlockCompressorKey {
+    format: TTBlockCompressionFormat,
+    quality: TTBlockCompressionQuality,
+}
+
+const BLOCK_COMPRESSION_FORMATS: [TTBlockCompressionFormat; 5] = [
+    TTBlockCompressionFormat::BC1,
+    TTBlockCompressionFormat::BC3,
+    TTBlockCompressionFormat::BC4,
+    TTBlockCompressionFormat::BC5,
+    TTBlockCompressionFormat::BC7,
+];
+const BLOCK_COMPRESSION_QUALITIES: [TTBlockCompressionQuality; 3] = [
+    TTBlockCompressionQuality::Fast,
+    TTBlockCompressionQuality::Normal,
+    TTBlockCompressionQuality::High,
+];
+
+impl TexTransCoreEngineDevice {
+    /// フォーマットと品質の組み合わせごとにエンコーダーの ID を割り当て、パイプラインは初めて使われるときに作る。
+    /// register_format_convertor の後に行うように。
+    pub fn register_block_compressor(&mut self) {
+        for format in BLOCK_COMPRESSION_FORMATS {
+            for quality in BLOCK_COMPRESSION_QUALITIES {
+                let key = BlockCompressorKey { format, quality };
+                let id = TTComputeShaderID::from(self.compute_shader.len() as u32);
+                self.compute_shader
+                    .push(TTComputeShaderEntry::BlockCompressor(key));
+                self.block_compressor_id.insert(key, id);
+            }
+        }
+    }
+
+    fn take_block_compression_buffer(&self, size: u64) -> TTStorageBuffer {
+        let recycled = self
+            .block_compression_buffer_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take(size);
+        let buffer = recycled.unwrap_or_else(|| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("block compression output buffer"),
+                size,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        });
+        TTStorageBuffer {
+            buffer: Arc::new(buffer),
+        }
+    }
+    /// GPU から使われ終わった後に返却するように。
+    fn recycle_block_compression_buffer(&self, blocks: TTStorageBuffer) {
+        // ハンドラーのドロップ後はほかに持っているものはいない
+        if let Ok(buffer) = Arc::try_unwrap(blocks.buffer) {
+            self.block_compression_buffer_pool
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .recycle(buffer);
+        }
+    }
+
+    pub(crate) fn get_or_create_block_compressor(
+        &self,
+        key: BlockCompressorKey,
+    ) -> Arc<TTComputeShader> {
+        let mut pipelines = self
+            .block_compressor_pipelines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.create_block_compressor(key)))
+            .clone()
+    }
+
+    fn create_block_compressor(&self, key: BlockCompressorKey) -> TTComputeShader {
+        let src_point = TTBindingPoint::new(0, 0);
+        let blocks_point = TTBindingPoint::new(0, 1);
+        let mut bind_map = HashMap::new();
+        bind_map.insert("SrcTex".to_string(), src_point);
+        bind_map.insert("Blocks".to_string(), blocks_point);
+        let mut bind_type = HashMap::new();
+        bind_type.insert(
+            src_point,
+            TTBindingType::RWRenderTexture(wgpu::TextureViewDimension::D2),
+        );
+        bind_type.insert(blocks_point, TTBindingType::StorageBuffer);
+
+        let wgsl_str = BLOCK_COMPRESSOR_TEMPLATE
+            .replace("$$$FORMAT$$$", &format!("{}u", key.format as u32))
+            .replace("$$$QUALITY$$$", &format!("{}u", key.quality as u32))
+            .replace(
+                "$$$BLOCK_WORDS$$$",
+                &format!("{}u", key.format.block_size() / 4),
+            );
+
+        let cs_module = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("block compressor shader module"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
+            });
+        let compute_pipeline =
+            self.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("block compressor compute pipeline"),
+                    layout: None,
+                    module: &cs_module,
+                    entry_point: Some("CSMain"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    cache: self.pipeline_cache(),
+                });
+
+        // 内部のシェーダーはすべてのバインディングを使っているため自動生成のレイアウトでよい
+        let layout = TTComputeShaderLayout::from_auto_layout(&compute_pipeline, &bind_type);
+        TTComputeShader::new(
+            cs_module,
+            compute_pipeline,
+            bind_map,
+            bind_type,
+            WorkGroupSize { x: 8, y: 8, z: 1 },
+            layout,
+        )
+    }
+}
+
+impl TexTransCoreEngineContext<'_> {
+    /// source の mip 0 をブロック圧縮し、ブロックを左上から行ごとに詰めたものを dist に書き込む。
+    /// dist の長さは TTBlockCompressionFormat::compressed_size と一致しないといけない。配列でない 2D のもののみ圧縮できる。
+    /// 値は色空間の印に関係なくそのまま圧縮されるため、 sRGB のものは _SRGB の BC フォーマットとして扱うように。
+    pub async fn encode_block_compression(
+        &mut self,
+        source: &TTRenderTexture,
+        format: TTBlockCompressionFormat,
+        quality: TTBlockCompressionQuality,
+        dist: &mut [u8],
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        if !BLOCK_COMPRESSION_FORMATS.contains(&format) {
+            return Err(TTCEWgpuError::UnsupportedFormat);
+        }
+        let Some(compressor_id) = self
+            .engine
+            .block_compressor_id
+            .get(&BlockCompressorKey { format, quality })
+            .copied()
+        else {
+            return Err(TTCEWgpuError::UnregisteredComputeShader);
+        };
+        if !source.is_single_layer_2d() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch);
+        }
+        let width = source.width();
+        let height = source.height();
+        let compressed_size = format.compressed_size(width, height);
+        if dist.len() as u64 != compressed_size {
+            return Err(TTCEWgpuError::SizeMismatch);
+        }
+
+        // エンコーダーは Rgba32Float からしか読まないため、それ以外は変換してから読ませる
+        let converted;
+        let rgba_source = if source.format() == wgpu::TextureFormat::Rgba32Float {
+            source
+        } else {
+            converted = self.get_temporary_render_texture(&TTRtRequestDescriptor {
+                width,
+                height,
+                format: RequestFormat::Manual(
+                    TexTransCoreTextureFormat::Float,
+                    TexTransCoreTextureChannel::RGBA,
+                ),
+                mip_level_count: 1,
+                dimension: TTRtDimension::D2,
+                depth_or_array_layers: 1,
+            });
+            self.convert_texture(&converted, source, TTChannelMapping::Default)?;
+            &converted
+        };
+
+        let blocks = self.engine.take_block_compression_buffer(compressed_size);
+        if let Err(e) = self.dispatch_block_compressor(&compressor_id, rgba_source, &blocks) {
+            self.engine.recycle_block_compression_buffer(blocks);
+            return Err(e);
+        }
+
+        let read_back_buffer = self.engine.take_staging_buffer(compressed_size);
+        self.get_command_encoder_as_mut().copy_buffer_to_buffer(
+            &blocks.buffer,
+            0,
+            &read_back_buffer,
+            0,
+            compressed_size,
+        );
+        self.send_command();
+
+        let rb_buffer_slice = read_back_buffer.slice(..);
+        // マップに失敗したものやタイムアウトしたものは状態がわからないため、どちらのバッファーも再利用しない
+        map_read_and_wait(&self.engine.device, &rb_buffer_slice)?;
+        dist.copy_from_slice(&rb_buffer_slice.get_mapped_range());
+        read_back_buffer.unmap();
+        self.engine.recycle_staging_buffer(read_back_buffer);
+        self.engine.recycle_block_compression_buffer(blocks);
+        Ok(())
+    }
+
+    fn dispatch_block_compressor(
+        &mut self,
+        compressor_id: &TTComputeShaderID,
+        source: &TTRenderTexture,
+        blocks: &TTStorageBuffer,
+    ) -> Result<(), TTCEWgpuError> {
+        let mut compressor = self.get_compute_handler(compressor_id)?;
+        let src_index = compressor
+            .get_bind_index("SrcTex")
+            .ok_or(TTCEWgpuError::BindingNotFound)?;
+        compressor.set_render_texture(src_index, source)?;
+        let blocks_index = compressor
+            .get_bind_index("Blocks")
+            .ok_or(TTCEWgpuError::BindingNotFound)?;
+        compressor.set_storage_buffer(blocks_index, blocks)?;
+
+        let wg_size = compressor.get_work_group_size();
+        compressor.dispatch(
+            source.width().div_ceil(4).div_ceil(wg_size.x),
+            source.height().div_ceil(4).div_ceil(wg_size.y),
+            1,
+        )
+    }
+}
+
+// 一つの invocation が 4x4 のブロックを一つエンコードする。
+// 端の 4 に満たないブロックは、範囲外を端のピクセルで埋めてエンコードする。
+pub const BLOCK_COMPRESSOR_TEMPLATE: &str = r#"
+@group(0) @binding(0)
+var SrcTex: texture_storage_2d<rgba32float,read>;
+@group(0) @binding(1)
+var<storage, read_write> Blocks: array<u32>;
+
+const FORMAT: u32 = $$$FORMAT$$$;
+const QUALITY: u32 = $$$QUALITY$$$;
+const BLOCK_WORDS: u32 = $$$BLOCK_WORDS$$$;
+
+const BC1: u32 = 0u;
+const BC3: u32 = 1u;
+const BC4: u32 = 2u;
+const BC5: u32 = 3u;
+
+const FAST: u32 = 0u;
+const HIGH: u32 = 2u;
+
+var<private> pixels: array<vec4<f32>, 16>;
+// 端点を求める対象、 BC1 の場合は A を 0 にした RGB が入る
+var<private> samples: array<vec4<f32>, 16>;
+// samples の e1 側の重み、最小二乗法で端点を詰めなおすときに使う
+var<private> sample_weights: array<f32, 16>;
+var<private> indices: array<u32, 16>;
+var<private> out_bits: array<u32, 4>;
+
+var<private> bc7_weights: array<u32, 16> = array<u32, 16>(0u, 4u, 9u, 13u, 17u, 21u, 26u, 30u, 34u, 38u, 43u, 47u, 51u, 55u, 60u, 64u);
+
+struct Endpoints {
+    e0: vec4<f32>,
+    e1: vec4<f32>,
+}
+
+fn put_bits(offset: u32, count: u32, value: u32) {
+    let word = offset / 32u;
+    let shift = offset % 32u;
+    let masked = value & ((1u << count) - 1u);
+    out_bits[word] |= masked << shift;
+    if (shift + count > 32u) {
+        out_bits[word + 1u] |= masked >> (32u - shift);
+    }
+}
+
+fn is_masked(mask: u32, i: u32) -> bool {
+    return (mask & (1u << i)) != 0u;
+}
+
+fn fit_endpoints(mask: u32) -> Endpoints {
+    var lo = vec4<f32>(1.0);
+    var hi = vec4<f32>(0.0);
+    var mean = vec4<f32>(0.0);
+    var count = 0.0;
+    for (var i = 0u; i < 16u; i++) {
+        if (!is_masked(mask, i)) { continue; }
+        lo = min(lo, samples[i]);
+        hi = max(hi, samples[i]);
+        mean += samples[i];
+        count += 1.0;
+    }
+    if (count == 0.0) {
+        return Endpoints(vec4<f32>(0.0), vec4<f32>(0.0));
+    }
+    if (QUALITY == FAST) {
+        return Endpoints(lo, hi);
+    }
+    mean /= count;
+
+    // 共分散行列の最大固有値の固有ベクトルを power iteration で求める
+    var cov = mat4x4<f32>();
+    for (var i = 0u; i < 16u; i++) {
+        if (!is_masked(mask, i)) { continue; }
+        let d = samples[i] - mean;
+        cov += mat4x4<f32>(d * d.x, d * d.y, d * d.z, d * d.w);
+    }
+    var axis = hi - lo;
+    for (var iteration = 0u; iteration < 8u; iteration++) {
+        let next = cov * axis;
+        let len = length(next);
+        if (len < 1e-8) { break; }
+        axis = next / len;
+    }
+    let axis_len2 = dot(axis, axis);
+    if (axis_len2 < 1e-12) {
+        return Endpoints(mean, mean);
+    }
+
+    var t_min = 1e9;
+    var t_max = -1e9;
+    for (var i = 0u; i < 16u; i++) {
+        if (!is_masked(mask, i)) { continue; }
+        let t = dot(samples[i] - mean, axis) / axis_len2;
+        t_min = min(t_min, t);
+        t_max = max(t_max, t);
+    }
+    return Endpoints(saturate(mean + axis * t_min), saturate(mean + axis * t_max));
+}
+
+// sample_weights で補間したものと samples の二乗誤差が最小になる端点を求める
+fn refine_endpoints(current: Endpoints, mask: u32) -> Endpoints {
+    var a = 0.0;
+    var b = 0.0;
+    var d = 0.0;
+    var x0 = vec4<f32>(0.0);
+    var x1 = vec4<f32>(0.0);
+    for (var i = 0u; i < 16u; i++) {
+        if (!is_masked(mask, i)) { continue; }
+        let w = sample_weights[i];
+        a += (1.0 - w) * (1.0 - w);
+        b += (1.0 - w) * w;
+        d += w * w;
+        x0 += (1.0 - w) * samples[i];
+        x1 += w * samples[i];
+    }
+    let det = a * d - b * b;
+    if (abs(det) < 1e-6) {
+        return current;
+    }
+    return Endpoints(saturate((d * x0 - b * x1) / det), saturate((a * x1 - b * x0) / det));
+}
+
+fn to_565(c: vec3<f32>) -> u32 {
+    let q = vec3<u32>(round(saturate(c) * vec3<f32>(31.0, 63.0, 31.0)));
+    return (q.x << 11u) | (q.y << 5u) | q.z;
+}
+fn from_565(v: u32) -> vec3<f32> {
+    return vec3<f32>(f32((v >> 11u) & 31u), f32((v >> 5u) & 63u), f32(v & 31u)) / vec3<f32>(31.0, 63.0, 31.0);
+}
+
+// bit_offset から 64bit の BC1 のカラーブロックを書き込む。
+// allow_transparent の場合、 A が 0.5 未満のピクセルがあれば 3 色モードにしてそのピクセルを透明にする。
+fn encode_color_block(bit_offset: u32, allow_transparent: bool) {
+    var opaque_mask = 0xFFFFu;
+    for (var i = 0u; i < 16u; i++) {
+        samples[i] = vec4<f32>(pixels[i].rgb, 0.0);
+        if (allow_transparent && pixels[i].a < 0.5) {
+            opaque_mask &= ~(1u << i);
+        }
+    }
+    let three_color = opaque_mask != 0xFFFFu;
+
+    var endpoints = fit_endpoints(opaque_mask);
+    var c0 = 0u;
+    var c1 = 0u;
+    let passes = select(1u, 3u, QUALITY == HIGH);
+    for (var iteration = 0u; iteration < passes; iteration++) {
+        c0 = to_565(endpoints.e0.rgb);
+        c1 = to_565(endpoints.e1.rgb);
+        // 4 色モードは c0 > c1 、 3 色モードは c0 <= c1 で表される
+        if ((!three_color && c0 < c1) || (three_color && c0 > c1)) {
+            let tmp = c0;
+            c0 = c1;
+            c1 = tmp;
+        }
+        let p0 = from_565(c0);
+        let p1 = from_565(c1);
+        var palette: array<vec3<f32>, 4>;
+        var palette_weights: array<f32, 4>;
+        palette[0] = p0;
+        palette[1] = p1;
+        palette_weights[0] = 0.0;
+        palette_weights[1] = 1.0;
+        if (three_color) {
+            palette[2] = (p0 + p1) * 0.5;
+            palette_weights[2] = 0.5;
+        } else {
+            palette[2] = (2.0 * p0 + p1) / 3.0;
+            palette[3] = (p0 + 2.0 * p1) / 3.0;
+            palette_weights[2] = 1.0 / 3.0;
+            palette_weights[3] = 2.0 / 3.0;
+        }
+        let color_count = select(4u, 3u, three_color);
+
+        for (var i = 0u; i < 16u; i++) {
+            if (!is_masked(opaque_mask, i)) {
+                indices[i] = 3u;
+                continue;
+            }
+            // c0 と c1 が同じ場合は 3 色モードになってしまうため、 3 番目の透明を使わないようにする
+            if (c0 == c1) {
+                indices[i] = 0u;
+                continue;
+            }
+            var best = 0u;
+            var best_error = 1e9;
+            for (var k = 0u; k < color_count; k++) {
+                let diff = samples[i].rgb - palette[k];
+                let err = dot(diff, diff);
+                if (err < best_error) {
+                    best_error = err;
+                    best = k;
+                }
+            }
+            indices[i] = best;
+            sample_weights[i] = palette_weights[best];
+        }
+
+        if (iteration + 1u < passes) {
+            endpoints = refine_endpoints(Endpoints(vec4<f32>(p0, 0.0), vec4<f32>(p1, 0.0)), opaque_mask);
+        }
+    }
+
+    put_bits(bit_offset, 16u, c0);
+    put_bits(bit_offset + 16u, 16u, c1);
+    for (var i = 0u; i < 16u; i++) {
+        put_bits(bit_offset + 32u + i * 2u, 2u, indices[i]);
+    }
+}
+
+// samples[i].x を bit_offset から 64bit の BC4 のブロックとして書き込む
+fn encode_single_channel_block(bit_offset: u32) {
+    var lo = 1.0;
+    var hi = 0.0;
+    for (var i = 0u; i < 16u; i++) {
+        lo = min(lo, samples[i].x);
+        hi = max(hi, samples[i].x);
+    }
+    // r0 > r1 の場合、 r0 と r1 の間を 8 段階に補間するモードになる
+    let r0 = u32(round(hi * 255.0));
+    let r1 = u32(round(lo * 255.0));
+    put_bits(bit_offset, 8u, r0);
+    put_bits(bit_offset + 8u, 8u, r1);
+    for (var i = 0u; i < 16u; i++) {
+        var index = 0u;
+        if (r0 > r1) {
+            let t = (samples[i].x * 255.0 - f32(r1)) / f32(r0 - r1);
+            // level は r1 から r0 に向かって何段階目か、インデックスは 0 が r0 、 1 が r1 、 2 から 7 が r0 から r1 への補間になる
+            let level = u32(clamp(round(t * 7.0), 0.0, 7.0));
+            if (level == 7u) {
+                index = 0u;
+            } else if (level == 0u) {
+                index = 1u;
+            } else {
+                index = 8u - level;
+            }
+        }
+        put_bits(bit_offset + 16u + i * 3u, 3u, index);
+    }
+}
+
+struct Mode6Endpoint {
+    color: vec4<u32>,
+    p: u32,
+}
+
+// 7bit の値と共有の 1bit の P ビットから、元の 8bit の値に近いものを選ぶ
+fn quantize_mode6(e: vec4<f32>) -> Mode6Endpoint {
+    let v = saturate(e) * 255.0;
+    var best = Mode6Endpoint(vec4<u32>(0u), 0u);
+    var best_error = 1e30;
+    for (var p = 0u; p < 2u; p++) {
+        let c = vec4<u32>(clamp(round((v - f32(p)) * 0.5), vec4<f32>(0.0), vec4<f32>(127.0)));
+        let diff = vec4<f32>((c << vec4<u32>(1u)) | vec4<u32>(p)) - v;
+        let err = dot(diff, diff);
+        if (err < best_error) {
+            best_error = err;
+            best = Mode6Endpoint(c, p);
+        }
+    }
+    return best;
+}
+fn mode6_value(e: Mode6Endpoint) -> vec4<f32> {
+    return vec4<f32>((e.color << vec4<u32>(1u)) | vec4<u32>(e.p));
+}
+
+// 128bit の BC7 のモード 6 のブロックを書き込む
+fn encode_bc7_block() {
+    for (var i = 0u; i < 16u; i++) {
+        samples[i] = pixels[i];
+    }
+
+    var endpoints = fit_endpoints(0xFFFFu);
+    var q0 = Mode6Endpoint(vec4<u32>(0u), 0u);
+    var q1 = Mode6Endpoint(vec4<u32>(0u), 0u);
+    let passes = select(1u, 3u, QUALITY == HIGH);
+    for (var iteration = 0u; iteration < passes; iteration++) {
+        q0 = quantize_mode6(endpoints.e0);
+        q1 = quantize_mode6(endpoints.e1);
+        let v0 = mode6_value(q0);
+        let v1 = mode6_value(q1);
+
+        for (var i = 0u; i < 16u; i++) {
+            let s = samples[i] * 255.0;
+            var best = 0u;
+            var best_error = 1e30;
+            for (var k = 0u; k < 16u; k++) {
+                let w = f32(bc7_weights[k]);
+                let diff = (v0 * (64.0 - w) + v1 * w) / 64.0 - s;
+                let err = dot(diff, diff);
+                if (err < best_error) {
+                    best_error = err;
+                    best = k;
+                }
+            }
+            indices[i] = best;
+            sample_weights[i] = f32(bc7_weights[best]) / 64.0;
+        }
+
+        if (iteration + 1u < passes) {
+            endpoints = refine_endpoints(Endpoints(v0 / 255.0, v1 / 255.0), 0xFFFFu);
+        }
+    }
+
+    // 先頭のインデックスは最上位ビットが省略されるため、 8 以上の場合は端点を入れ替えて反転する
+    if (indices[0] >= 8u) {
+        let tmp = q0;
+        q0 = q1;
+        q1 = tmp;
+        for (var i = 0u; i < 16u; i++) {
+            indices[i] = 15u - indices[i];
+        }
+    }
+
+    // モード番号 6 は 6 つの 0 の後の 1 で表される
+    put_bits(0u, 7u, 1u << 6u);
+    var offset = 7u;
+    for (var ch = 0u; ch < 4u; ch++) {
+        put_bits(offset, 7u, q0.color[ch]);
+        put_bits(offset + 7u, 7u, q1.color[ch]);
+        offset += 14u;
+    }
+    put_bits(63u, 1u, q0.p);
+    put_bits(64u, 1u, q1.p);
+    offset = 65u;
+    for (var i = 0u; i < 16u; i++) {
+        let bits = select(4u, 3u, i == 0u);
+        put_bits(offset, bits, indices[i]);
+        offset += bits;
+    }
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
+    let size = textureDimensions(SrcTex);
+    let block_count = (size + 3u) / 4u;
+    let block_pos = param.xy;
+    if (any(block_pos >= block_count)) {
+        return;
+    }
+
+    for (var i = 0u; i < 16u; i++) {
+        let pos = min(block_pos * 4u + vec2<u32>(i % 4u, i / 4u), size - 1u);
+        pixels[i] = saturate(textureLoad(SrcTex, pos));
+    }
+    for (var i = 0u; i < 4u; i++) {
+        out_bits[i] = 0u;
+    }
+
+    if (FORMAT == BC1) {
+        encode_color_block(0u, true);
+    } else if (FORMAT == BC3) {
+        for (var i = 0u; i < 16u; i++) {
+            samples[i] = vec4<f32>(pixels[i].a, 0.0, 0.0, 0.0);
+        }
+        encode_single_channel_block(0u);
+        encode_color_block(64u, false);
+    } else if (FORMAT == BC4) {
+        for (var i = 0u; i < 16u; i++) {
+            samples[i] = vec4<f32>(pixels[i].r, 0.0, 0.0, 0.0);
+        }
+        encode_single_channel_block(0u);
+    } else if (FORMAT == BC5) {
+        for (var i = 0u; i < 16u; i++) {
+            samples[i] = vec4<f32>(pixels[i].r, 0.0, 0.0, 0.0);
+        }
+        encode_single_channel_block(0u);
+        for (var i = 0u; i < 16u; i++) {
+            samples[i] = vec4<f32>(pixels[i].g, 0.0, 0.0, 0.0);
+        }
+        encode_single_channel_block(64u);
+    } else {
+        encode_bc7_block();
+    }
+
+    let base = (block_pos.y * block_count.x + block_pos.x) * BLOCK_WORDS;
+    for (var i = 0u; i < BLOCK_WORDS; i++) {
+        Blocks[base + i] = out_bits[i];
+    }
+}
+"#;
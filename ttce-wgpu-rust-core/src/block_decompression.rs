@@ -0,0 +1,957 @@
+use std::sync::Arc;
+
+use wgpu::util::DeviceExt;
+
+use crate::{
+    block_compression::TTBlockCompressionFormat,
+    render_texture::TTRenderTexture,
+    tex_trans_core_engine::{
+        RequestFormat, TTCEWgpuError, TTRtDimension, TTRtRequestDescriptor,
+        TexTransCoreEngineContext, TexTransCoreEngineDevice,
+    },
+    TTChannelMapping, TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+/// ブロック圧縮されたデータをどのようにデコードするか。
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TTBlockDecompressionPath {
+    /// TEXTURE_COMPRESSION_BC が有効なデバイスでは Hardware 、そうでなければ ComputeShader
+    Auto = 0,
+    /// Bc* のテクスチャーにそのままアップロードし、 GPU のサンプラーにデコードさせる
+    Hardware = 1,
+    /// コンピュートシェーダーでデコードする
+    ComputeShader = 2,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) enum BlockDecompressionPipelineKey {
+    /// Bc* のテクスチャーから textureLoad で読み出すだけなので、フォーマットに関係なく一つ
+    Hardware,
+    ComputeShader(TTBlockCompressionFormat),
+}
+
+#[derive(Debug)]
+pub(crate) struct BlockDecompressionPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+const BLOCK_DECOMPRESSION_WORK_GROUP_SIZE: u32 = 8;
+
+impl TTBlockCompressionFormat {
+    fn to_wgpu(self) -> wgpu::TextureFormat {
+        match self {
+            TTBlockCompressionFormat::BC1 => wgpu::TextureFormat::Bc1RgbaUnorm,
+            TTBlockCompressionFormat::BC2 => wgpu::TextureFormat::Bc2RgbaUnorm,
+            TTBlockCompressionFormat::BC3 => wgpu::TextureFormat::Bc3RgbaUnorm,
+            TTBlockCompressionFormat::BC4 => wgpu::TextureFormat::Bc4RUnorm,
+            TTBlockCompressionFormat::BC5 => wgpu::TextureFormat::Bc5RgUnorm,
+            TTBlockCompressionFormat::BC6H => wgpu::TextureFormat::Bc6hRgbUfloat,
+            TTBlockCompressionFormat::BC7 => wgpu::TextureFormat::Bc7RgbaUnorm,
+        }
+    }
+}
+
+impl TexTransCoreEngineDevice {
+    fn get_or_create_block_decompression_pipeline(
+        &self,
+        key: BlockDecompressionPipelineKey,
+    ) -> Arc<BlockDecompressionPipeline> {
+        let mut pipelines = self
+            .block_decompression_pipelines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.create_block_decompression_pipeline(key)))
+            .clone()
+    }
+
+    fn create_block_decompression_pipeline(
+        &self,
+        key: BlockDecompressionPipelineKey,
+    ) -> BlockDecompressionPipeline {
+        let (wgsl_str, source_binding_type) = match key {
+            BlockDecompressionPipelineKey::Hardware => (
+                BLOCK_TEXTURE_COPY_SHADER.to_string(),
+                wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+            ),
+            BlockDecompressionPipelineKey::ComputeShader(format) => (
+                BLOCK_DECOMPRESSOR_TEMPLATE
+                    .replace("$$$FORMAT$$$", &format!("{}u", format as u32))
+                    .replace(
+                        "$$$BLOCK_WORDS$$$",
+                        &format!("{}u", format.block_size() / 4),
+                    ),
+                wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Storage { read_only: true },
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+            ),
+        };
+
+        let cs_module = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("block decompressor shader module"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
+            });
+        let bind_group_layout =
+            self.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("block decompressor bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: source_binding_type,
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::StorageTexture {
+                                access: wgpu::StorageTextureAccess::WriteOnly,
+                                format: wgpu::TextureFormat::Rgba32Float,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("block decompressor pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = self
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("block decompressor compute pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &cs_module,
+                entry_point: Some("CSMain"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: self.pipeline_cache(),
+            });
+
+        BlockDecompressionPipeline {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+}
+
+impl TexTransCoreEngineContext<'_> {
+    /// ブロックを左上から行ごとに詰めた data をデコードし、 target の mip 0 に書き込む。
+    /// data の長さは TTBlockCompressionFormat::compressed_size と一致しないといけない。配列でない 2D のもののみ対象にできる。
+    pub fn upload_block_compressed_texture(
+        &mut self,
+        target: &TTRenderTexture,
+        data: &[u8],
+        format: TTBlockCompressionFormat,
+    ) -> Result<(), TTCEWgpuError> {
+        self.upload_block_compressed_texture_with(
+            target,
+            data,
+            format,
+            TTBlockDecompressionPath::Auto,
+        )
+    }
+
+    /// デコードの方法を指定する upload_block_compressed_texture 。
+    /// Hardware を TEXTURE_COMPRESSION_BC が無いデバイスで指定した場合は UnsupportedFormat になる。
+    pub fn upload_block_compressed_texture_with(
+        &mut self,
+        target: &TTRenderTexture,
+        data: &[u8],
+        format: TTBlockCompressionFormat,
+        path: TTBlockDecompressionPath,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        if target.tt_format().is_none() {
+            return Err(TTCEWgpuError::UnsupportedFormat);
+        }
+        if !target.is_single_layer_2d() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch);
+        }
+        let width = target.width();
+        let height = target.height();
+        if data.len() as u64 != format.compressed_size(width, height) {
+            return Err(TTCEWgpuError::SizeMismatch);
+        }
+
+        let hardware_supported = self
+            .engine
+            .device
+            .features()
+            .contains(wgpu::Features::TEXTURE_COMPRESSION_BC);
+        let use_hardware = match path {
+            TTBlockDecompressionPath::Auto => hardware_supported,
+            TTBlockDecompressionPath::Hardware => true,
+            TTBlockDecompressionPath::ComputeShader => false,
+        };
+        if use_hardware && !hardware_supported {
+            return Err(TTCEWgpuError::UnsupportedFormat);
+        }
+
+        // デコーダーは Rgba32Float にしか書き込まないため、それ以外は一時的なものに書き込んでから変換する
+        let converted;
+        let rgba_target = if target.format() == wgpu::TextureFormat::Rgba32Float {
+            target
+        } else {
+            converted = self.get_temporary_render_texture(&TTRtRequestDescriptor {
+                width,
+                height,
+                format: RequestFormat::Manual(
+                    TexTransCoreTextureFormat::Float,
+                    TexTransCoreTextureChannel::RGBA,
+                ),
+                mip_level_count: 1,
+                dimension: TTRtDimension::D2,
+                depth_or_array_layers: 1,
+            });
+            &converted
+        };
+
+        let engine = self.engine;
+        let blocks_x = width.div_ceil(4);
+        let blocks_y = height.div_ceil(4);
+        let (pipeline_key, source_resource) = if use_hardware {
+            // BC のテクスチャーは mip 0 の大きさがブロックの倍数でないといけないため、端のブロックを含む大きさで作る
+            let block_texture = engine.device.create_texture_with_data(
+                &engine.queue,
+                &wgpu::TextureDescriptor {
+                    label: Some("block compressed texture"),
+                    size: wgpu::Extent3d {
+                        width: blocks_x * 4,
+                        height: blocks_y * 4,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: format.to_wgpu(),
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                data,
+            );
+            (
+                BlockDecompressionPipelineKey::Hardware,
+                BlockSource::Texture(block_texture.create_view(&Default::default())),
+            )
+        } else {
+            let block_buffer =
+                engine
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("block compressed buffer"),
+                        contents: data,
+                        usage: wgpu::BufferUsages::STORAGE,
+                    });
+            (
+                BlockDecompressionPipelineKey::ComputeShader(format),
+                BlockSource::Buffer(block_buffer),
+            )
+        };
+        let pipeline = engine.get_or_create_block_decompression_pipeline(pipeline_key);
+
+        let dist_view = rgba_target.create_view(&wgpu::TextureViewDescriptor {
+            base_mip_level: 0,
+            mip_level_count: Some(1),
+            ..Default::default()
+        });
+        let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("block decompressor bind group"),
+            layout: &pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: match &source_resource {
+                        BlockSource::Texture(view) => wgpu::BindingResource::TextureView(view),
+                        BlockSource::Buffer(buffer) => buffer.as_entire_binding(),
+                    },
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&dist_view),
+                },
+            ],
+        });
+
+        // Hardware はピクセルごと、 ComputeShader はブロックごとに invocation を割り当てる
+        let (dispatch_width, dispatch_height) = if use_hardware {
+            (width, height)
+        } else {
+            (blocks_x, blocks_y)
+        };
+        {
+            let encoder = self.get_command_encoder_as_mut();
+            let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+
+            compute_pass.set_pipeline(&pipeline.pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                dispatch_width.div_ceil(BLOCK_DECOMPRESSION_WORK_GROUP_SIZE),
+                dispatch_height.div_ceil(BLOCK_DECOMPRESSION_WORK_GROUP_SIZE),
+                1,
+            );
+        }
+        self.check_command_stack();
+
+        if !std::ptr::eq(rgba_target, target) {
+            self.convert_texture(target, rgba_target, TTChannelMapping::Default)?;
+        }
+        Ok(())
+    }
+}
+
+enum BlockSource {
+    Texture(wgpu::TextureView),
+    Buffer(wgpu::Buffer),
+}
+
+pub const BLOCK_TEXTURE_COPY_SHADER: &str = r#"
+@group(0) @binding(0)
+var SrcTex: texture_2d<f32>;
+@group(0) @binding(1)
+var DistTex: texture_storage_2d<rgba32float,write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
+    let dist_size = textureDimensions(DistTex);
+    if (param.x >= dist_size.x || param.y >= dist_size.y) {
+        return;
+    }
+    textureStore(DistTex, vec2<i32>(param.xy), textureLoad(SrcTex, vec2<i32>(param.xy), 0));
+}
+"#;
+
+// 一つの invocation が 4x4 のブロックを一つデコードし、テクスチャーの範囲内のピクセルのみ書き込む。
+// BC7 の無効なモードのブロックは (0, 0, 0, 0) 、 BC6H の予約されたモードのブロックは (0, 0, 0, 1) になる。
+// BC6H は符号なしの Bc6hRgbUfloat としてデコードする。
+pub const BLOCK_DECOMPRESSOR_TEMPLATE: &str = r#"
+@group(0) @binding(0)
+var<storage, read> Blocks: array<u32>;
+@group(0) @binding(1)
+var DistTex: texture_storage_2d<rgba32float,write>;
+
+const FORMAT: u32 = $$$FORMAT$$$;
+const BLOCK_WORDS: u32 = $$$BLOCK_WORDS$$$;
+
+const BC1: u32 = 0u;
+const BC3: u32 = 1u;
+const BC4: u32 = 2u;
+const BC5: u32 = 3u;
+const BC7: u32 = 4u;
+const BC2: u32 = 5u;
+const BC6H: u32 = 6u;
+
+var<private> block_data: array<u32, 4>;
+var<private> pixels: array<vec4<f32>, 16>;
+var<private> channel_values: array<f32, 16>;
+var<private> bit_offset: u32;
+
+// count は 32 未満
+fn get_bits(offset: u32, count: u32) -> u32 {
+    if (count == 0u) {
+        return 0u;
+    }
+    let word = offset / 32u;
+    let shift = offset % 32u;
+    var value = block_data[word] >> shift;
+    if (shift + count > 32u) {
+        value |= block_data[word + 1u] << (32u - shift);
+    }
+    return value & ((1u << count) - 1u);
+}
+fn read_bits(count: u32) -> u32 {
+    let value = get_bits(bit_offset, count);
+    bit_offset += count;
+    return value;
+}
+
+fn expand_565(v: u32) -> vec3<f32> {
+    let r = (v >> 11u) & 31u;
+    let g = (v >> 5u) & 63u;
+    let b = v & 31u;
+    return vec3<f32>(
+        f32((r << 3u) | (r >> 2u)),
+        f32((g << 2u) | (g >> 4u)),
+        f32((b << 3u) | (b >> 2u)),
+    ) / 255.0;
+}
+
+fn decode_color_block(word_offset: u32, allow_three_color: bool) {
+    let c0 = block_data[word_offset] & 0xFFFFu;
+    let c1 = block_data[word_offset] >> 16u;
+    let indices = block_data[word_offset + 1u];
+    let e0 = expand_565(c0);
+    let e1 = expand_565(c1);
+
+    var palette: array<vec4<f32>, 4>;
+    palette[0] = vec4<f32>(e0, 1.0);
+    palette[1] = vec4<f32>(e1, 1.0);
+    if (c0 > c1 || !allow_three_color) {
+        palette[2] = vec4<f32>(mix(e0, e1, 1.0 / 3.0), 1.0);
+        palette[3] = vec4<f32>(mix(e0, e1, 2.0 / 3.0), 1.0);
+    } else {
+        palette[2] = vec4<f32>(mix(e0, e1, 0.5), 1.0);
+        palette[3] = vec4<f32>(0.0);
+    }
+    for (var i = 0u; i < 16u; i++) {
+        pixels[i] = palette[(indices >> (i * 2u)) & 3u];
+    }
+}
+
+fn decode_single_channel_block(word_offset: u32) {
+    let r0 = f32(block_data[word_offset] & 0xFFu);
+    let r1 = f32((block_data[word_offset] >> 8u) & 0xFFu);
+
+    var palette: array<f32, 8>;
+    palette[0] = r0;
+    palette[1] = r1;
+    if (r0 > r1) {
+        for (var k = 2u; k < 8u; k++) {
+            palette[k] = (f32(8u - k) * r0 + f32(k - 1u) * r1) / 7.0;
+        }
+    } else {
+        for (var k = 2u; k < 6u; k++) {
+            palette[k] = (f32(6u - k) * r0 + f32(k - 1u) * r1) / 5.0;
+        }
+        palette[6] = 0.0;
+        palette[7] = 255.0;
+    }
+    for (var i = 0u; i < 16u; i++) {
+        channel_values[i] = palette[get_bits(word_offset * 32u + 16u + i * 3u, 3u)] / 255.0;
+    }
+}
+
+// モードごとの subset 数、 partition 、 rotation 、 index selection 、 色 、 A のビット数、
+// 端点ごとの p-bit の有無、 subset ごとの p-bit の有無、 index と二つ目の index のビット数
+var<private> bc7_subsets: array<u32, 8> = array<u32, 8>(3u, 2u, 3u, 2u, 1u, 1u, 1u, 2u);
+var<private> bc7_partition_bits: array<u32, 8> = array<u32, 8>(4u, 6u, 6u, 6u, 0u, 0u, 0u, 6u);
+var<private> bc7_rotation_bits: array<u32, 8> = array<u32, 8>(0u, 0u, 0u, 0u, 2u, 2u, 0u, 0u);
+var<private> bc7_index_selection_bits: array<u32, 8> = array<u32, 8>(0u, 0u, 0u, 0u, 1u, 0u, 0u, 0u);
+var<private> bc7_color_bits: array<u32, 8> = array<u32, 8>(4u, 6u, 5u, 7u, 5u, 7u, 7u, 5u);
+var<private> bc7_alpha_bits: array<u32, 8> = array<u32, 8>(0u, 0u, 0u, 0u, 6u, 8u, 7u, 5u);
+var<private> bc7_endpoint_pbits: array<u32, 8> = array<u32, 8>(1u, 0u, 0u, 1u, 0u, 0u, 1u, 1u);
+var<private> bc7_shared_pbits: array<u32, 8> = array<u32, 8>(0u, 1u, 0u, 0u, 0u, 0u, 0u, 0u);
+var<private> bc7_index_bits: array<u32, 8> = array<u32, 8>(3u, 3u, 2u, 2u, 2u, 2u, 4u, 2u);
+var<private> bc7_secondary_index_bits: array<u32, 8> = array<u32, 8>(0u, 0u, 0u, 0u, 3u, 2u, 0u, 0u);
+
+// 2 subset はピクセルごとに 1bit 、 3 subset は 2bit
+var<private> bc7_partition2: array<u32, 64> = array<u32, 64>(
+    0xCCCCu, 0x8888u, 0xEEEEu, 0xECC8u, 0xC880u, 0xFEECu, 0xFEC8u, 0xEC80u,
+    0xC800u, 0xFFECu, 0xFE80u, 0xE800u, 0xFFE8u, 0xFF00u, 0xFFF0u, 0xF000u,
+    0xF710u, 0x008Eu, 0x7100u, 0x08CEu, 0x008Cu, 0x7310u, 0x3100u, 0x8CCEu,
+    0x088Cu, 0x3110u, 0x6666u, 0x366Cu, 0x17E8u, 0x0FF0u, 0x718Eu, 0x399Cu,
+    0xAAAAu, 0xF0F0u, 0x5A5Au, 0x33CCu, 0x3C3Cu, 0x55AAu, 0x9696u, 0xA55Au,
+    0x73CEu, 0x13C8u, 0x324Cu, 0x3BDCu, 0x6996u, 0xC33Cu, 0x9966u, 0x0660u,
+    0x0272u, 0x04E4u, 0x4E40u, 0x2720u, 0xC936u, 0x936Cu, 0x39C6u, 0x639Cu,
+    0x9336u, 0x9CC6u, 0x817Eu, 0xE718u, 0xCCF0u, 0x0FCCu, 0x7744u, 0xEE22u,
+);
+var<private> bc7_partition3: array<u32, 64> = array<u32, 64>(
+    0xAA685050u, 0x6A5A5040u, 0x5A5A4200u, 0x5450A0A8u, 0xA5A50000u, 0xA0A05050u, 0x5555A0A0u, 0x5A5A5050u,
+    0xAA550000u, 0xAA555500u, 0xAAAA5500u, 0x90909090u, 0x94949494u, 0xA4A4A4A4u, 0xA9A59450u, 0x2A0A4250u,
+    0xA5945040u, 0x0A425054u, 0xA5A5A500u, 0x55A0A0A0u, 0xA8A85454u, 0x6A6A4040u, 0xA4A45000u, 0x1A1A0500u,
+    0x0050A4A4u, 0xAAA59090u, 0x14696914u, 0x69691400u, 0xA08585A0u, 0xAA821414u, 0x50A4A450u, 0x6A5A0200u,
+    0xA9A58000u, 0x5090A0A8u, 0xA8A09050u, 0x24242424u, 0x00AA5500u, 0x24924924u, 0x24499224u, 0x50A50A50u,
+    0x500AA550u, 0xAAAA4444u, 0x66660000u, 0xA5A0A5A0u, 0x50A050A0u, 0x69286928u, 0x44AAAA44u, 0x66666600u,
+    0xAA444444u, 0x54A854A8u, 0x95809580u, 0x96969600u, 0xA85454A8u, 0x80959580u, 0xAA141414u, 0x96960000u,
+    0xAAAA1414u, 0xA05050A0u, 0xA0A5A5A0u, 0x96000000u, 0x40804080u, 0xA9A8A9A8u, 0xAAAAAA44u, 0x2A4A5254u,
+);
+// subset 0 の anchor は常にピクセル 0
+var<private> bc7_anchor2: array<u32, 64> = array<u32, 64>(
+    15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u,
+    15u, 2u, 8u, 2u, 2u, 8u, 8u, 15u, 2u, 8u, 2u, 2u, 8u, 8u, 2u, 2u,
+    15u, 15u, 6u, 8u, 2u, 8u, 15u, 15u, 2u, 8u, 2u, 2u, 2u, 15u, 15u, 6u,
+    6u, 2u, 6u, 8u, 15u, 15u, 2u, 2u, 15u, 15u, 15u, 15u, 15u, 2u, 2u, 15u,
+);
+var<private> bc7_anchor3_second: array<u32, 64> = array<u32, 64>(
+    3u, 3u, 15u, 15u, 8u, 3u, 15u, 15u, 8u, 8u, 6u, 6u, 6u, 5u, 3u, 3u,
+    3u, 3u, 8u, 15u, 3u, 3u, 6u, 10u, 5u, 8u, 8u, 6u, 8u, 5u, 15u, 15u,
+    8u, 15u, 3u, 5u, 6u, 10u, 8u, 15u, 15u, 3u, 15u, 5u, 15u, 15u, 15u, 15u,
+    3u, 15u, 5u, 5u, 5u, 8u, 5u, 10u, 5u, 10u, 8u, 13u, 15u, 12u, 3u, 3u,
+);
+var<private> bc7_anchor3_third: array<u32, 64> = array<u32, 64>(
+    15u, 8u, 8u, 3u, 15u, 15u, 3u, 8u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 8u,
+    15u, 8u, 15u, 3u, 15u, 8u, 15u, 8u, 3u, 15u, 6u, 10u, 15u, 15u, 10u, 8u,
+    15u, 3u, 15u, 10u, 10u, 8u, 9u, 10u, 6u, 15u, 8u, 15u, 3u, 6u, 6u, 8u,
+    15u, 3u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 15u, 3u, 15u, 15u, 8u,
+);
+
+var<private> bc7_weights2: array<u32, 4> = array<u32, 4>(0u, 21u, 43u, 64u);
+var<private> bc7_weights3: array<u32, 8> = array<u32, 8>(0u, 9u, 18u, 27u, 37u, 46u, 55u, 64u);
+var<private> bc7_weights4: array<u32, 16> = array<u32, 16>(
+    0u, 4u, 9u, 13u, 17u, 21u, 26u, 30u, 34u, 38u, 43u, 47u, 51u, 55u, 60u, 64u,
+);
+
+fn bc7_weight(bits: u32, index: u32) -> u32 {
+    if (bits == 2u) {
+        return bc7_weights2[index];
+    }
+    if (bits == 3u) {
+        return bc7_weights3[index];
+    }
+    return bc7_weights4[index];
+}
+
+fn bc7_unquantize(value: u32, bits: u32) -> u32 {
+    let v = value << (8u - bits);
+    return v | (v >> bits);
+}
+
+fn bc7_subset_of(subsets: u32, partition_index: u32, pixel: u32) -> u32 {
+    if (subsets == 2u) {
+        return (bc7_partition2[partition_index] >> pixel) & 1u;
+    }
+    if (subsets == 3u) {
+        return (bc7_partition3[partition_index] >> (pixel * 2u)) & 3u;
+    }
+    return 0u;
+}
+
+fn bc7_is_anchor(subsets: u32, partition_index: u32, pixel: u32) -> bool {
+    if (pixel == 0u) {
+        return true;
+    }
+    if (subsets == 2u) {
+        return pixel == bc7_anchor2[partition_index];
+    }
+    if (subsets == 3u) {
+        return pixel == bc7_anchor3_second[partition_index] || pixel == bc7_anchor3_third[partition_index];
+    }
+    return false;
+}
+
+fn decode_bc7_block() {
+    var mode = 8u;
+    for (var m = 0u; m < 8u; m++) {
+        if ((block_data[0] & (1u << m)) != 0u) {
+            mode = m;
+            break;
+        }
+    }
+    if (mode == 8u) {
+        for (var i = 0u; i < 16u; i++) {
+            pixels[i] = vec4<f32>(0.0);
+        }
+        return;
+    }
+
+    bit_offset = mode + 1u;
+    let subsets = bc7_subsets[mode];
+    let partition_index = read_bits(bc7_partition_bits[mode]);
+    let rotation = read_bits(bc7_rotation_bits[mode]);
+    let index_selection = read_bits(bc7_index_selection_bits[mode]);
+    let alpha_bits = bc7_alpha_bits[mode];
+    let endpoint_count = subsets * 2u;
+
+    // 端点は R をすべて、 G をすべて、 B をすべて、 A をすべての順に並ぶ
+    var endpoints: array<vec4<u32>, 6>;
+    for (var c = 0u; c < 3u; c++) {
+        for (var e = 0u; e < endpoint_count; e++) {
+            endpoints[e][c] = read_bits(bc7_color_bits[mode]);
+        }
+    }
+    for (var e = 0u; e < endpoint_count; e++) {
+        endpoints[e].w = read_bits(alpha_bits);
+    }
+
+    var color_precision = bc7_color_bits[mode];
+    var alpha_precision = alpha_bits;
+    if (bc7_endpoint_pbits[mode] != 0u) {
+        for (var e = 0u; e < endpoint_count; e++) {
+            endpoints[e] = (endpoints[e] << vec4<u32>(1u)) | vec4<u32>(read_bits(1u));
+        }
+        color_precision += 1u;
+        alpha_precision += 1u;
+    } else if (bc7_shared_pbits[mode] != 0u) {
+        for (var s = 0u; s < subsets; s++) {
+            let pbit = vec4<u32>(read_bits(1u));
+            endpoints[s * 2u] = (endpoints[s * 2u] << vec4<u32>(1u)) | pbit;
+            endpoints[s * 2u + 1u] = (endpoints[s * 2u + 1u] << vec4<u32>(1u)) | pbit;
+        }
+        color_precision += 1u;
+        alpha_precision += 1u;
+    }
+    for (var e = 0u; e < endpoint_count; e++) {
+        var alpha = 255u;
+        if (alpha_bits != 0u) {
+            alpha = bc7_unquantize(endpoints[e].w, alpha_precision);
+        }
+        endpoints[e] = vec4<u32>(
+            bc7_unquantize(endpoints[e].x, color_precision),
+            bc7_unquantize(endpoints[e].y, color_precision),
+            bc7_unquantize(endpoints[e].z, color_precision),
+            alpha,
+        );
+    }
+
+    // 各 subset の anchor のピクセルは最上位ビットが 0 として省略されている
+    let index_bits = bc7_index_bits[mode];
+    let secondary_index_bits = bc7_secondary_index_bits[mode];
+    var indices: array<u32, 16>;
+    var secondary_indices: array<u32, 16>;
+    for (var i = 0u; i < 16u; i++) {
+        indices[i] = read_bits(select(index_bits, index_bits - 1u, bc7_is_anchor(subsets, partition_index, i)));
+    }
+    if (secondary_index_bits != 0u) {
+        for (var i = 0u; i < 16u; i++) {
+            secondary_indices[i] = read_bits(select(secondary_index_bits, secondary_index_bits - 1u, i == 0u));
+        }
+    }
+
+    for (var i = 0u; i < 16u; i++) {
+        var color_weight = bc7_weight(index_bits, indices[i]);
+        var alpha_weight = color_weight;
+        if (secondary_index_bits != 0u) {
+            let secondary_weight = bc7_weight(secondary_index_bits, secondary_indices[i]);
+            if (index_selection == 0u) {
+                alpha_weight = secondary_weight;
+            } else {
+                alpha_weight = color_weight;
+                color_weight = secondary_weight;
+            }
+        }
+
+        let subset = bc7_subset_of(subsets, partition_index, i);
+        let e0 = endpoints[subset * 2u];
+        let e1 = endpoints[subset * 2u + 1u];
+        let rgb = ((64u - color_weight) * e0.xyz + color_weight * e1.xyz + 32u) >> vec3<u32>(6u);
+        let a = ((64u - alpha_weight) * e0.w + alpha_weight * e1.w + 32u) >> 6u;
+        let col = vec4<f32>(vec4<u32>(rgb, a)) / 255.0;
+
+        switch (rotation) {
+            case 1u: { pixels[i] = col.agbr; }
+            case 2u: { pixels[i] = col.rabg; }
+            case 3u: { pixels[i] = col.rgab; }
+            default: { pixels[i] = col; }
+        }
+    }
+}
+
+// BC6H の端点、 0 と 1 が region 0 の 、 2 と 3 が region 1 のもので、 DirectX のドキュメントの w x y z にあたる
+var<private> bc6h_endpoints: array<vec3<u32>, 4>;
+
+// 端点の channel の first_bit から count ビットに、ブロックの続きのビットを下位から書き込む
+fn bc6h_bits(endpoint: u32, channel: u32, first_bit: u32, count: u32) {
+    bc6h_endpoints[endpoint][channel] |= read_bits(count) << first_bit;
+}
+// 上位から書き込む、モード 13 と 14 の端点の上位ビットはこの順に並ぶ
+fn bc6h_reversed_bits(endpoint: u32, channel: u32, first_bit: u32, count: u32) {
+    bc6h_endpoints[endpoint][channel] |= (reverseBits(read_bits(count)) >> (32u - count)) << first_bit;
+}
+
+// モード 1 から 14 の順の、差分の変換の有無、端点のビット数、差分の R G B のビット数
+var<private> bc6h_transformed: array<u32, 14> = array<u32, 14>(1u, 1u, 1u, 1u, 1u, 1u, 1u, 1u, 1u, 0u, 0u, 1u, 1u, 1u);
+var<private> bc6h_endpoint_bits: array<u32, 14> = array<u32, 14>(10u, 7u, 11u, 11u, 11u, 9u, 8u, 8u, 8u, 6u, 10u, 11u, 12u, 16u);
+var<private> bc6h_delta_bits: array<vec3<u32>, 14> = array<vec3<u32>, 14>(
+    vec3<u32>(5u, 5u, 5u), vec3<u32>(6u, 6u, 6u), vec3<u32>(5u, 4u, 4u), vec3<u32>(4u, 5u, 4u),
+    vec3<u32>(4u, 4u, 5u), vec3<u32>(5u, 5u, 5u), vec3<u32>(6u, 5u, 5u), vec3<u32>(5u, 6u, 5u),
+    vec3<u32>(5u, 5u, 6u), vec3<u32>(6u, 6u, 6u), vec3<u32>(10u, 10u, 10u), vec3<u32>(9u, 9u, 9u),
+    vec3<u32>(8u, 8u, 8u), vec3<u32>(4u, 4u, 4u),
+);
+
+// モードを 0 から 13 で返す、予約されたものは 14
+fn bc6h_read_mode() -> u32 {
+    if ((block_data[0] & 3u) < 2u) {
+        return read_bits(2u);
+    }
+    var mode = 14u;
+    switch (read_bits(5u)) {
+        case 0x02u: { mode = 2u; }
+        case 0x06u: { mode = 3u; }
+        case 0x0Au: { mode = 4u; }
+        case 0x0Eu: { mode = 5u; }
+        case 0x12u: { mode = 6u; }
+        case 0x16u: { mode = 7u; }
+        case 0x1Au: { mode = 8u; }
+        case 0x1Eu: { mode = 9u; }
+        case 0x03u: { mode = 10u; }
+        case 0x07u: { mode = 11u; }
+        case 0x0Bu: { mode = 12u; }
+        case 0x0Fu: { mode = 13u; }
+        default: {}
+    }
+    return mode;
+}
+
+// bc6h_bits の channel
+const R: u32 = 0u;
+const G: u32 = 1u;
+const B: u32 = 2u;
+
+// モードごとの端点のビットの並び、コメントは DirectX のドキュメントの表記
+fn bc6h_read_endpoints(mode: u32) {
+    switch (mode) {
+        case 0u: {
+            // gy[4], by[4], bz[4], rw[9:0], gw[9:0], bw[9:0], rx[4:0], gz[4], gy[3:0], gx[4:0], bz[0], gz[3:0],
+            // bx[4:0], bz[1], by[3:0], ry[4:0], bz[2], rz[4:0], bz[3]
+            bc6h_bits(2u, G, 4u, 1u); bc6h_bits(2u, B, 4u, 1u); bc6h_bits(3u, B, 4u, 1u);
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 5u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(2u, G, 0u, 4u);
+            bc6h_bits(1u, G, 0u, 5u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, G, 0u, 4u);
+            bc6h_bits(1u, B, 0u, 5u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 5u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(3u, R, 0u, 5u); bc6h_bits(3u, B, 3u, 1u);
+        }
+        case 1u: {
+            // gy[5], gz[4], gz[5], rw[6:0], bz[0], bz[1], by[4], gw[6:0], by[5], bz[2], gy[4], bw[6:0], bz[3], bz[5], bz[4],
+            // rx[5:0], gy[3:0], gx[5:0], gz[3:0], bx[5:0], by[3:0], ry[5:0], rz[5:0]
+            bc6h_bits(2u, G, 5u, 1u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(3u, G, 5u, 1u);
+            bc6h_bits(0u, R, 0u, 7u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 4u, 1u);
+            bc6h_bits(0u, G, 0u, 7u); bc6h_bits(2u, B, 5u, 1u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(2u, G, 4u, 1u);
+            bc6h_bits(0u, B, 0u, 7u); bc6h_bits(3u, B, 3u, 1u); bc6h_bits(3u, B, 5u, 1u); bc6h_bits(3u, B, 4u, 1u);
+            bc6h_bits(1u, R, 0u, 6u); bc6h_bits(2u, G, 0u, 4u); bc6h_bits(1u, G, 0u, 6u); bc6h_bits(3u, G, 0u, 4u);
+            bc6h_bits(1u, B, 0u, 6u); bc6h_bits(2u, B, 0u, 4u); bc6h_bits(2u, R, 0u, 6u); bc6h_bits(3u, R, 0u, 6u);
+        }
+        case 2u: {
+            // rw[9:0], gw[9:0], bw[9:0], rx[4:0], rw[10], gy[3:0], gx[3:0], gw[10], bz[0], gz[3:0], bx[3:0], bw[10],
+            // bz[1], by[3:0], ry[4:0], bz[2], rz[4:0], bz[3]
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 5u); bc6h_bits(0u, R, 10u, 1u); bc6h_bits(2u, G, 0u, 4u);
+            bc6h_bits(1u, G, 0u, 4u); bc6h_bits(0u, G, 10u, 1u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, G, 0u, 4u);
+            bc6h_bits(1u, B, 0u, 4u); bc6h_bits(0u, B, 10u, 1u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 5u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(3u, R, 0u, 5u); bc6h_bits(3u, B, 3u, 1u);
+        }
+        case 3u: {
+            // rw[9:0], gw[9:0], bw[9:0], rx[3:0], rw[10], gz[4], gy[3:0], gx[4:0], gw[10], gz[3:0], bx[3:0], bw[10],
+            // bz[1], by[3:0], ry[3:0], bz[0], bz[2], rz[3:0], gy[4], bz[3]
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 4u); bc6h_bits(0u, R, 10u, 1u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(2u, G, 0u, 4u);
+            bc6h_bits(1u, G, 0u, 5u); bc6h_bits(0u, G, 10u, 1u); bc6h_bits(3u, G, 0u, 4u);
+            bc6h_bits(1u, B, 0u, 4u); bc6h_bits(0u, B, 10u, 1u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 4u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(3u, R, 0u, 4u);
+            bc6h_bits(2u, G, 4u, 1u); bc6h_bits(3u, B, 3u, 1u);
+        }
+        case 4u: {
+            // rw[9:0], gw[9:0], bw[9:0], rx[3:0], rw[10], by[4], gy[3:0], gx[3:0], gw[10], bz[0], gz[3:0], bx[4:0], bw[10],
+            // by[3:0], ry[3:0], bz[1], bz[2], rz[3:0], bz[4], bz[3]
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 4u); bc6h_bits(0u, R, 10u, 1u); bc6h_bits(2u, B, 4u, 1u); bc6h_bits(2u, G, 0u, 4u);
+            bc6h_bits(1u, G, 0u, 4u); bc6h_bits(0u, G, 10u, 1u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, G, 0u, 4u);
+            bc6h_bits(1u, B, 0u, 5u); bc6h_bits(0u, B, 10u, 1u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 4u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(3u, R, 0u, 4u);
+            bc6h_bits(3u, B, 4u, 1u); bc6h_bits(3u, B, 3u, 1u);
+        }
+        case 5u: {
+            // rw[8:0], by[4], gw[8:0], gy[4], bw[8:0], bz[4], rx[4:0], gz[4], gy[3:0], gx[4:0], bz[0], gz[3:0],
+            // bx[4:0], bz[1], by[3:0], ry[4:0], bz[2], rz[4:0], bz[3]
+            bc6h_bits(0u, R, 0u, 9u); bc6h_bits(2u, B, 4u, 1u); bc6h_bits(0u, G, 0u, 9u); bc6h_bits(2u, G, 4u, 1u);
+            bc6h_bits(0u, B, 0u, 9u); bc6h_bits(3u, B, 4u, 1u);
+            bc6h_bits(1u, R, 0u, 5u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(2u, G, 0u, 4u);
+            bc6h_bits(1u, G, 0u, 5u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, G, 0u, 4u);
+            bc6h_bits(1u, B, 0u, 5u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 5u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(3u, R, 0u, 5u); bc6h_bits(3u, B, 3u, 1u);
+        }
+        case 6u: {
+            // rw[7:0], gz[4], by[4], gw[7:0], bz[2], gy[4], bw[7:0], bz[3], bz[4], rx[5:0], gy[3:0], gx[4:0], bz[0],
+            // gz[3:0], bx[4:0], bz[1], by[3:0], ry[5:0], rz[5:0]
+            bc6h_bits(0u, R, 0u, 8u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(2u, B, 4u, 1u);
+            bc6h_bits(0u, G, 0u, 8u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(2u, G, 4u, 1u);
+            bc6h_bits(0u, B, 0u, 8u); bc6h_bits(3u, B, 3u, 1u); bc6h_bits(3u, B, 4u, 1u);
+            bc6h_bits(1u, R, 0u, 6u); bc6h_bits(2u, G, 0u, 4u); bc6h_bits(1u, G, 0u, 5u); bc6h_bits(3u, B, 0u, 1u);
+            bc6h_bits(3u, G, 0u, 4u); bc6h_bits(1u, B, 0u, 5u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 6u); bc6h_bits(3u, R, 0u, 6u);
+        }
+        case 7u: {
+            // rw[7:0], bz[0], by[4], gw[7:0], gy[5], gy[4], bw[7:0], gz[5], bz[4], rx[4:0], gz[4], gy[3:0], gx[5:0],
+            // gz[3:0], bx[4:0], bz[1], by[3:0], ry[4:0], bz[2], rz[4:0], bz[3]
+            bc6h_bits(0u, R, 0u, 8u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(2u, B, 4u, 1u);
+            bc6h_bits(0u, G, 0u, 8u); bc6h_bits(2u, G, 5u, 1u); bc6h_bits(2u, G, 4u, 1u);
+            bc6h_bits(0u, B, 0u, 8u); bc6h_bits(3u, G, 5u, 1u); bc6h_bits(3u, B, 4u, 1u);
+            bc6h_bits(1u, R, 0u, 5u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(2u, G, 0u, 4u); bc6h_bits(1u, G, 0u, 6u);
+            bc6h_bits(3u, G, 0u, 4u); bc6h_bits(1u, B, 0u, 5u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 5u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(3u, R, 0u, 5u); bc6h_bits(3u, B, 3u, 1u);
+        }
+        case 8u: {
+            // rw[7:0], bz[1], by[4], gw[7:0], by[5], gy[4], bw[7:0], bz[5], bz[4], rx[4:0], gz[4], gy[3:0], gx[4:0],
+            // bz[0], gz[3:0], bx[5:0], by[3:0], ry[4:0], bz[2], rz[4:0], bz[3]
+            bc6h_bits(0u, R, 0u, 8u); bc6h_bits(3u, B, 1u, 1u); bc6h_bits(2u, B, 4u, 1u);
+            bc6h_bits(0u, G, 0u, 8u); bc6h_bits(2u, B, 5u, 1u); bc6h_bits(2u, G, 4u, 1u);
+            bc6h_bits(0u, B, 0u, 8u); bc6h_bits(3u, B, 5u, 1u); bc6h_bits(3u, B, 4u, 1u);
+            bc6h_bits(1u, R, 0u, 5u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(2u, G, 0u, 4u); bc6h_bits(1u, G, 0u, 5u);
+            bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, G, 0u, 4u); bc6h_bits(1u, B, 0u, 6u); bc6h_bits(2u, B, 0u, 4u);
+            bc6h_bits(2u, R, 0u, 5u); bc6h_bits(3u, B, 2u, 1u); bc6h_bits(3u, R, 0u, 5u); bc6h_bits(3u, B, 3u, 1u);
+        }
+        case 9u: {
+            // rw[5:0], gz[4], bz[0], bz[1], by[4], gw[5:0], gy[5], by[5], bz[2], gy[4], bw[5:0], gz[5], bz[3], bz[5], bz[4],
+            // rx[5:0], gy[3:0], gx[5:0], gz[3:0], bx[5:0], by[3:0], ry[5:0], rz[5:0]
+            bc6h_bits(0u, R, 0u, 6u); bc6h_bits(3u, G, 4u, 1u); bc6h_bits(3u, B, 0u, 1u); bc6h_bits(3u, B, 1u, 1u);
+            bc6h_bits(2u, B, 4u, 1u); bc6h_bits(0u, G, 0u, 6u); bc6h_bits(2u, G, 5u, 1u); bc6h_bits(2u, B, 5u, 1u);
+            bc6h_bits(3u, B, 2u, 1u); bc6h_bits(2u, G, 4u, 1u); bc6h_bits(0u, B, 0u, 6u); bc6h_bits(3u, G, 5u, 1u);
+            bc6h_bits(3u, B, 3u, 1u); bc6h_bits(3u, B, 5u, 1u); bc6h_bits(3u, B, 4u, 1u);
+            bc6h_bits(1u, R, 0u, 6u); bc6h_bits(2u, G, 0u, 4u); bc6h_bits(1u, G, 0u, 6u); bc6h_bits(3u, G, 0u, 4u);
+            bc6h_bits(1u, B, 0u, 6u); bc6h_bits(2u, B, 0u, 4u); bc6h_bits(2u, R, 0u, 6u); bc6h_bits(3u, R, 0u, 6u);
+        }
+        case 10u: {
+            // rw[9:0], gw[9:0], bw[9:0], rx[9:0], gx[9:0], bx[9:0]
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 10u); bc6h_bits(1u, G, 0u, 10u); bc6h_bits(1u, B, 0u, 10u);
+        }
+        case 11u: {
+            // rw[9:0], gw[9:0], bw[9:0], rx[8:0], rw[10], gx[8:0], gw[10], bx[8:0], bw[10]
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 9u); bc6h_bits(0u, R, 10u, 1u);
+            bc6h_bits(1u, G, 0u, 9u); bc6h_bits(0u, G, 10u, 1u);
+            bc6h_bits(1u, B, 0u, 9u); bc6h_bits(0u, B, 10u, 1u);
+        }
+        case 12u: {
+            // rw[9:0], gw[9:0], bw[9:0], rx[7:0], rw[10:11], gx[7:0], gw[10:11], bx[7:0], bw[10:11]
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 8u); bc6h_reversed_bits(0u, R, 10u, 2u);
+            bc6h_bits(1u, G, 0u, 8u); bc6h_reversed_bits(0u, G, 10u, 2u);
+            bc6h_bits(1u, B, 0u, 8u); bc6h_reversed_bits(0u, B, 10u, 2u);
+        }
+        default: {
+            // rw[9:0], gw[9:0], bw[9:0], rx[3:0], rw[15:10], gx[3:0], gw[15:10], bx[3:0], bw[15:10]
+            bc6h_bits(0u, R, 0u, 10u); bc6h_bits(0u, G, 0u, 10u); bc6h_bits(0u, B, 0u, 10u);
+            bc6h_bits(1u, R, 0u, 4u); bc6h_reversed_bits(0u, R, 10u, 6u);
+            bc6h_bits(1u, G, 0u, 4u); bc6h_reversed_bits(0u, G, 10u, 6u);
+            bc6h_bits(1u, B, 0u, 4u); bc6h_reversed_bits(0u, B, 10u, 6u);
+        }
+    }
+}
+
+fn bc6h_sign_extend(v: vec3<u32>, bits: vec3<u32>) -> vec3<u32> {
+    let shift = vec3<u32>(32u) - bits;
+    return bitcast<vec3<u32>>(bitcast<vec3<i32>>(v << shift) >> shift);
+}
+
+fn bc6h_unquantize(v: u32, bits: u32) -> u32 {
+    if (bits >= 15u) {
+        return v;
+    }
+    if (v == 0u) {
+        return 0u;
+    }
+    if (v == (1u << bits) - 1u) {
+        return 0xFFFFu;
+    }
+    return ((v << 16u) + 0x8000u) >> bits;
+}
+
+fn decode_bc6h_block() {
+    bit_offset = 0u;
+    let mode = bc6h_read_mode();
+    if (mode == 14u) {
+        for (var i = 0u; i < 16u; i++) {
+            pixels[i] = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        }
+        return;
+    }
+
+    for (var e = 0u; e < 4u; e++) {
+        bc6h_endpoints[e] = vec3<u32>(0u);
+    }
+    bc6h_read_endpoints(mode);
+    // モード 10 以降は region が一つで、 partition が無い
+    let subsets = select(2u, 1u, mode >= 10u);
+    var partition_index = 0u;
+    if (subsets == 2u) {
+        partition_index = read_bits(5u);
+    }
+    let endpoint_count = subsets * 2u;
+
+    // 一つ目以外の端点は一つ目からの差分として符号付きで格納されている
+    let endpoint_bits = bc6h_endpoint_bits[mode];
+    if (bc6h_transformed[mode] != 0u) {
+        let mask = vec3<u32>((1u << endpoint_bits) - 1u);
+        for (var e = 1u; e < endpoint_count; e++) {
+            let delta = bc6h_sign_extend(bc6h_endpoints[e], bc6h_delta_bits[mode]);
+            bc6h_endpoints[e] = (bc6h_endpoints[0] + delta) & mask;
+        }
+    }
+    for (var e = 0u; e < endpoint_count; e++) {
+        bc6h_endpoints[e] = vec3<u32>(
+            bc6h_unquantize(bc6h_endpoints[e].x, endpoint_bits),
+            bc6h_unquantize(bc6h_endpoints[e].y, endpoint_bits),
+            bc6h_unquantize(bc6h_endpoints[e].z, endpoint_bits),
+        );
+    }
+
+    // partition と anchor は BC7 の 2 subset のものの先頭 32 個と同じ
+    let index_bits = select(3u, 4u, subsets == 1u);
+    for (var i = 0u; i < 16u; i++) {
+        let index = read_bits(select(index_bits, index_bits - 1u, bc7_is_anchor(subsets, partition_index, i)));
+        let weight = bc7_weight(index_bits, index);
+        let subset = bc7_subset_of(subsets, partition_index, i);
+        let e0 = bc6h_endpoints[subset * 2u];
+        let e1 = bc6h_endpoints[subset * 2u + 1u];
+        let interpolated = ((64u - weight) * e0 + weight * e1 + 32u) >> vec3<u32>(6u);
+        // 31 / 64 倍すると符号なしの half のビットになる
+        let half_bits = (interpolated * 31u) >> vec3<u32>(6u);
+        pixels[i] = vec4<f32>(
+            unpack2x16float(half_bits.x).x,
+            unpack2x16float(half_bits.y).x,
+            unpack2x16float(half_bits.z).x,
+            1.0,
+        );
+    }
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
+    let dist_size = textureDimensions(DistTex);
+    let blocks_x = (dist_size.x + 3u) / 4u;
+    let blocks_y = (dist_size.y + 3u) / 4u;
+    if (param.x >= blocks_x || param.y >= blocks_y) {
+        return;
+    }
+    let block_index = param.y * blocks_x + param.x;
+    for (var w = 0u; w < BLOCK_WORDS; w++) {
+        block_data[w] = Blocks[block_index * BLOCK_WORDS + w];
+    }
+
+    if (FORMAT == BC1) {
+        decode_color_block(0u, true);
+    } else if (FORMAT == BC2) {
+        decode_color_block(2u, false);
+        for (var i = 0u; i < 16u; i++) {
+            pixels[i].a = f32(get_bits(i * 4u, 4u)) / 15.0;
+        }
+    } else if (FORMAT == BC3) {
+        decode_color_block(2u, false);
+        decode_single_channel_block(0u);
+        for (var i = 0u; i < 16u; i++) {
+            pixels[i].a = channel_values[i];
+        }
+    } else if (FORMAT == BC4) {
+        decode_single_channel_block(0u);
+        for (var i = 0u; i < 16u; i++) {
+            pixels[i] = vec4<f32>(channel_values[i], 0.0, 0.0, 1.0);
+        }
+    } else if (FORMAT == BC5) {
+        decode_single_channel_block(0u);
+        for (var i = 0u; i < 16u; i++) {
+            pixels[i] = vec4<f32>(channel_values[i], 0.0, 0.0, 1.0);
+        }
+        decode_single_channel_block(2u);
+        for (var i = 0u; i < 16u; i++) {
+            pixels[i].g = channel_values[i];
+        }
+    } else if (FORMAT == BC7) {
+        decode_bc7_block();
+    } else if (FORMAT == BC6H) {
+        decode_bc6h_block();
+    }
+
+    for (var i = 0u; i < 16u; i++) {
+        let pos = param.xy * 4u + vec2<u32>(i % 4u, i / 4u);
+        if (pos.x < dist_size.x && pos.y < dist_size.y) {
+            textureStore(DistTex, vec2<i32>(pos), pixels[i]);
+        }
+    }
+}
+"#;
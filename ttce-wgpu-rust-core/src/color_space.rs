@@ -0,0 +1,274 @@
+use std::sync::Arc;
+
+use crate::{
+    compute_shader::AsTypeStr,
+    render_texture::TTRenderTexture,
+    tex_trans_core_engine::{
+        RequestFormat, TTCEWgpuError, TTRtDimension, TTRtRequestDescriptor,
+        TexTransCoreEngineContext, TexTransCoreEngineDevice,
+    },
+};
+
+/// テクスチャーの中身がどの色空間で表されているか。 TTRenderTexture ごとに持つ印で、書き換えても中身は変換されない。
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum TTColorSpace {
+    /// Rec.709 の原色のリニア
+    #[default]
+    Linear = 0,
+    /// sRGB のガンマがかかったもの、 Rgba8Unorm の場合はサンプリング時に Rgba8UnormSrgb の view でデコードされる
+    Srgb = 1,
+    /// ACES AP1 の原色のリニア
+    AcesCg = 2,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct ColorSpacePipelineKey {
+    format: wgpu::TextureFormat,
+    from: TTColorSpace,
+    to: TTColorSpace,
+}
+
+#[derive(Debug)]
+pub(crate) struct ColorSpacePipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+const COLOR_SPACE_WORK_GROUP_SIZE: u32 = 8;
+
+impl TexTransCoreEngineDevice {
+    // フォーマットと色空間の組み合わせが多いため、使われたときに生成する
+    fn get_or_create_color_space_pipeline(
+        &self,
+        key: ColorSpacePipelineKey,
+    ) -> Arc<ColorSpacePipeline> {
+        let mut pipelines = self
+            .color_space_pipelines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.create_color_space_pipeline(key)))
+            .clone()
+    }
+
+    fn create_color_space_pipeline(&self, key: ColorSpacePipelineKey) -> ColorSpacePipeline {
+        let wgsl_str = COLOR_SPACE_CONVERTER_TEMPLATE
+            .replace("$$$FORMAT$$$", key.format.as_type_str())
+            .replace("$$$FROM$$$", &format!("{}u", key.from as u32))
+            .replace("$$$TO$$$", &format!("{}u", key.to as u32));
+
+        let cs_module = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("color space converter shader module"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
+            });
+
+        // エンコードされたままの値を読むため、 textureLoad のみの unfilterable として明示する
+        let bind_group_layout =
+            self.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("color space converter bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::StorageTexture {
+                                access: wgpu::StorageTextureAccess::WriteOnly,
+                                format: key.format,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("color space converter pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = self
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("color space converter compute pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &cs_module,
+                entry_point: Some("CSMain"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: self.pipeline_cache(),
+            });
+
+        ColorSpacePipeline {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+}
+
+impl TexTransCoreEngineContext<'_> {
+    /// render_texture のすべてのミップの RGB を現在の色空間から to に変換し、色空間の印も to にする。 A はそのまま残る。
+    /// Byte のフォーマットはリニアにすると暗部の精度が落ちるため、リニアで扱う場合は Half や Float を推奨する。
+    pub fn convert_color_space(
+        &mut self,
+        render_texture: &mut TTRenderTexture,
+        to: TTColorSpace,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        let (format, channel) = render_texture
+            .tt_format()
+            .ok_or(TTCEWgpuError::UnsupportedFormat)?;
+        if !render_texture.is_single_layer_2d() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch);
+        }
+        let from = render_texture.color_space();
+        if from == to {
+            return Ok(());
+        }
+
+        let engine = self.engine;
+        let pipeline = engine.get_or_create_color_space_pipeline(ColorSpacePipelineKey {
+            format: render_texture.format(),
+            from,
+            to,
+        });
+
+        // 同じテクスチャーを読みながら書き込むことはできないため、一度コピーしたものから読む
+        let source = self.get_temporary_render_texture(&TTRtRequestDescriptor {
+            width: render_texture.width(),
+            height: render_texture.height(),
+            format: RequestFormat::Manual(format, channel),
+            mip_level_count: render_texture.mip_level_count(),
+            dimension: TTRtDimension::D2,
+            depth_or_array_layers: 1,
+        });
+        self.copy_texture(&source, render_texture)?;
+
+        for mip_level in 0..render_texture.mip_level_count() {
+            let mip_view_desc = wgpu::TextureViewDescriptor {
+                base_mip_level: mip_level,
+                mip_level_count: Some(1),
+                ..Default::default()
+            };
+            let src_view = source.create_view(&mip_view_desc);
+            let dist_view = render_texture.create_view(&mip_view_desc);
+
+            let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("color space converter bind group"),
+                layout: &pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&src_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&dist_view),
+                    },
+                ],
+            });
+
+            let mip_size = render_texture.mip_size(mip_level);
+            {
+                let encoder = self.get_command_encoder_as_mut();
+                let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+
+                compute_pass.set_pipeline(&pipeline.pipeline);
+                compute_pass.set_bind_group(0, &bind_group, &[]);
+                compute_pass.dispatch_workgroups(
+                    mip_size.width.div_ceil(COLOR_SPACE_WORK_GROUP_SIZE),
+                    mip_size.height.div_ceil(COLOR_SPACE_WORK_GROUP_SIZE),
+                    1,
+                );
+            }
+            self.check_command_stack();
+        }
+
+        render_texture.set_color_space(to);
+        Ok(())
+    }
+}
+
+// 行列は D65 と ACES の白色点の間を Bradford で順応させたもの
+pub const COLOR_SPACE_CONVERTER_TEMPLATE: &str = r#"
+@group(0) @binding(0)
+var SrcTex: texture_2d<f32>;
+@group(0) @binding(1)
+var DistTex: texture_storage_2d<$$$FORMAT$$$,write>;
+
+const FROM: u32 = $$$FROM$$$;
+const TO: u32 = $$$TO$$$;
+
+const LINEAR: u32 = 0u;
+const SRGB: u32 = 1u;
+const ACESCG: u32 = 2u;
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let v = max(c, vec3<f32>(0.0));
+    let low = v / 12.92;
+    let high = pow((v + 0.055) / 1.055, vec3<f32>(2.4));
+    return select(high, low, v <= vec3<f32>(0.04045));
+}
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let v = max(c, vec3<f32>(0.0));
+    let low = v * 12.92;
+    let high = 1.055 * pow(v, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return select(high, low, v <= vec3<f32>(0.0031308));
+}
+fn rec709_to_ap1(c: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        dot(c, vec3<f32>(0.6130974024, 0.3395231462, 0.0473794514)),
+        dot(c, vec3<f32>(0.0701937225, 0.9163538791, 0.0134523985)),
+        dot(c, vec3<f32>(0.0206155929, 0.1095697729, 0.8698146342)),
+    );
+}
+fn ap1_to_rec709(c: vec3<f32>) -> vec3<f32> {
+    return vec3<f32>(
+        dot(c, vec3<f32>(1.7050509927, -0.6217921207, -0.0832588720)),
+        dot(c, vec3<f32>(-0.1302564175, 1.1408047366, -0.0105483191)),
+        dot(c, vec3<f32>(-0.0240033568, -0.1289689761, 1.1529723329)),
+    );
+}
+
+fn to_linear(c: vec3<f32>) -> vec3<f32> {
+    if (FROM == SRGB) {
+        return srgb_to_linear(c);
+    }
+    if (FROM == ACESCG) {
+        return ap1_to_rec709(c);
+    }
+    return c;
+}
+fn from_linear(c: vec3<f32>) -> vec3<f32> {
+    if (TO == SRGB) {
+        return linear_to_srgb(c);
+    }
+    if (TO == ACESCG) {
+        return rec709_to_ap1(c);
+    }
+    return c;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
+    let pos = param.xy;
+    if (any(pos >= textureDimensions(DistTex))) {
+        return;
+    }
+    let col = textureLoad(SrcTex, pos, 0);
+    textureStore(DistTex, pos, vec4<f32>(from_linear(to_linear(col.rgb)), col.a));
+}
+"#;
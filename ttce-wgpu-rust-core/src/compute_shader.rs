@@ -1,19 +1,24 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Read;
 use std::ops::Deref;
 use std::path::PathBuf;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use naga::TypeInner::Image;
-use naga::{ImageClass, ImageDimension, Module, StorageFormat};
-use wgpu::util::DeviceExt;
+use naga::{Handle, ImageClass, ImageDimension, Module, StorageFormat};
 use wgpu::{ComputePipeline, ShaderModule};
 
-use crate::render_texture::TTRenderTexture;
+use crate::block_compression::BlockCompressorKey;
+use crate::color_space::TTColorSpace;
+use crate::constants_buffer::ConstantsBufferSlice;
+use crate::render_texture::{ConvertTextureFormat, TTRenderTexture};
+use crate::sampler::TTSampler;
+use crate::shader_cache::{IncludedFileStamp, ShaderCacheKey};
 use crate::storage_buffer::TTStorageBuffer;
 use crate::tex_trans_core_engine::{
-    TTCEWgpuError, TexTransCoreEngineContext, TexTransCoreEngineDevice,
+    TTCEWgpuError, TTCEWgpuErrorRecord, TexTransCoreEngineContext, TexTransCoreEngineDevice,
 };
 use crate::{debug_log, TexTransCoreTextureFormat};
 
@@ -23,13 +28,94 @@ pub struct TTComputeShader {
     pub(crate) module: ShaderModule,
 
     pub(crate) pipeline: ComputePipeline,
-    pub(crate) binding_map: HashMap<String, u32>,
-    pub(crate) binding_type: HashMap<u32, TTBindingType>,
+    pub(crate) binding_map: HashMap<String, TTBindingPoint>,
+    pub(crate) binding_type: HashMap<TTBindingPoint, TTBindingType>,
     pub(crate) work_group_size: WorkGroupSize,
+    /// group の順の bind group layout 、 dispatch のたびに作り直さないように登録時に持っておく。
+    pub(crate) bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    /// レイアウトに含まれるバインディング、シェーダーから使われていないものは含まれず、 set しても bind されない。
+    pub(crate) used_bindings: HashSet<TTBindingPoint>,
+    /// push constant にした cbuffer のバインディングと、その大きさ
+    pub(crate) push_constants: Option<(TTBindingPoint, u32)>,
 }
+impl TTComputeShader {
+    pub(crate) fn new(
+        module: ShaderModule,
+        pipeline: ComputePipeline,
+        binding_map: HashMap<String, TTBindingPoint>,
+        binding_type: HashMap<TTBindingPoint, TTBindingType>,
+        work_group_size: WorkGroupSize,
+        layout: TTComputeShaderLayout,
+    ) -> Self {
+        TTComputeShader {
+            module,
+            pipeline,
+            binding_map,
+            binding_type,
+            work_group_size,
+            bind_group_layouts: layout.bind_group_layouts,
+            used_bindings: layout.used_bindings,
+            push_constants: layout.push_constants,
+        }
+    }
+}
+
+/// TTComputeShader のパイプラインのレイアウト
+pub(crate) struct TTComputeShaderLayout {
+    pub(crate) bind_group_layouts: Vec<wgpu::BindGroupLayout>,
+    pub(crate) used_bindings: HashSet<TTBindingPoint>,
+    pub(crate) push_constants: Option<(TTBindingPoint, u32)>,
+}
+impl TTComputeShaderLayout {
+    /// layout: None で作ったパイプラインのもの。
+    /// 自動生成されたレイアウトには使われているバインディングの group までしか無いため、すべてのバインディングが使われているシェーダーにのみ使うように。
+    pub(crate) fn from_auto_layout(
+        pipeline: &ComputePipeline,
+        binding_type: &HashMap<TTBindingPoint, TTBindingType>,
+    ) -> Self {
+        let bind_group_count = binding_type.keys().map(|p| p.group + 1).max().unwrap_or(0);
+        TTComputeShaderLayout {
+            bind_group_layouts: (0..bind_group_count)
+                .map(|group| pipeline.get_bind_group_layout(group))
+                .collect(),
+            used_bindings: binding_type.keys().copied().collect(),
+            push_constants: None,
+        }
+    }
+}
+
+/// TTComputeShaderID が指すもの。内部のシェーダーは ID のみを先に割り当て、パイプラインは初めて使われるときに作る。
+#[derive(Debug)]
+pub(crate) enum TTComputeShaderEntry {
+    Registered(Arc<TTComputeShader>),
+    FormatConverter(ConvertTextureFormat),
+    BlockCompressor(BlockCompressorKey),
+}
+
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Default)]
 pub struct TTComputeShaderID(u32);
 
+/// HLSL の register の space が group 、番号が binding になる。
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Default)]
+pub struct TTBindingPoint {
+    pub group: u32,
+    pub binding: u32,
+}
+impl TTBindingPoint {
+    pub fn new(group: u32, binding: u32) -> Self {
+        TTBindingPoint { group, binding }
+    }
+}
+
+/// bind group の再利用の状況、 C# 側から取得するためのもの。
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TTBindGroupStatistics {
+    pub created_count: u64,
+    pub reused_count: u64,
+}
+
 impl TTComputeShaderID {
     pub fn from(id: u32) -> TTComputeShaderID {
         TTComputeShaderID(id)
@@ -48,10 +134,11 @@ impl TexTransCoreEngineDevice {
         &mut self,
         hlsl_file_path: &str,
         hlsl_source_code: Option<&str>,
-    ) -> Result<TTComputeShaderID, Box<dyn std::error::Error>> {
+    ) -> Result<TTComputeShaderID, TTCEWgpuErrorRecord> {
         let binding = PathBuf::from(hlsl_file_path);
         let Some(file_name_os_str) = binding.file_name() else {
-            todo!()
+            return Err(TTCEWgpuError::InvalidArgument
+                .with_message(format!("hlsl path has not file name : {}", hlsl_file_path)));
         };
         let operator_name: String = file_name_os_str.to_string_lossy().into();
 
@@ -60,31 +147,47 @@ impl TexTransCoreEngineDevice {
         if let Some(hlsl_str) = hlsl_source_code {
             hlsl_string.push_str(hlsl_str);
         } else {
-            let hlsl_file_result = File::open(hlsl_file_path);
-            if let Err(er) = hlsl_file_result?.read_to_string(&mut hlsl_string) {
-                return Err(Box::new(er));
+            let read_result =
+                File::open(hlsl_file_path).and_then(|mut f| f.read_to_string(&mut hlsl_string));
+            if let Err(er) = read_result {
+                return Err(
+                    TTCEWgpuError::FileIO.with_message(format!("{} : {}", hlsl_file_path, er))
+                );
             }
         };
 
-        let spv = self.dx_compiler().compile_hlsl(
-            hlsl_file_path,
-            hlsl_string.as_str(),
-            "CSMain",
-            "cs_6_0",
-            &["-spirv", "-HV 2018"],
-            // &["-spirv", "-HV 2018","-O0"],
-            &[],
-        )?;
+        let spv =
+            self.compile_hlsl_to_spirv(hlsl_file_path, hlsl_string.as_str(), &operator_name)?;
 
         let mut naga_ir =
-            naga::front::spv::parse_u8_slice(&spv, &naga::front::spv::Options::default())?;
+            naga::front::spv::parse_u8_slice(&spv, &naga::front::spv::Options::default()).map_err(
+                |e| TTCEWgpuError::CompileError.with_message(format!("{} : {}", operator_name, e)),
+            )?;
 
         fix_storage_texture_format(&mut naga_ir, self.default_texture_format());
         clamp_work_group_size(&mut naga_ir);
 
         let wg_size = get_work_group_size(&naga_ir);
         let bind_map = HashMap::from_iter(get_bindings(&naga_ir));
-        let bind_type = HashMap::from_iter(get_binding_types(&naga_ir));
+        let sampled_images = get_sampled_images(&naga_ir);
+        let bind_type = HashMap::from_iter(get_binding_types(&naga_ir, &sampled_images));
+
+        let compile_error = |message: String| {
+            TTCEWgpuError::CompileError.with_message(format!("{} : {}", operator_name, message))
+        };
+        let module_info = naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::all(),
+        )
+        .validate(&naga_ir)
+        .map_err(|e| compile_error(e.to_string()))?;
+        let entry_point_info = module_info.get_entry_point(0);
+
+        // cbuffer を dynamic offset で bind するため、レイアウトは自動生成させずに明示する
+        let push_constants = self.assign_push_constants(&mut naga_ir, entry_point_info);
+        let group_entries =
+            get_bind_group_layout_entries(&naga_ir, entry_point_info, &sampled_images)
+                .map_err(compile_error)?;
 
         // let mut validator = naga::valid::Validator::new(
         //     naga::valid::ValidationFlags::empty(),
@@ -103,6 +206,8 @@ impl TexTransCoreEngineDevice {
         // debug_log(hlsl_string.as_str());
         // debug_log(format!("{:?}", naga_ir).as_str());
 
+        self.device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let (pipeline_layout, layout) = self.create_pipeline_layout(&group_entries, push_constants);
         let cs_module = self
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -114,112 +219,428 @@ impl TexTransCoreEngineDevice {
             self.device
                 .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
                     label: Some((String::from("compute pipeline with ") + &operator_name).as_str()),
-                    layout: None,
+                    layout: Some(&pipeline_layout),
                     module: &cs_module,
                     entry_point: Some("CSMain"),
                     compilation_options: wgpu::PipelineCompilationOptions::default(),
-                    cache: None,
+                    cache: self.pipeline_cache(),
                 });
+        if let Some(err) = crate::get_tokio_runtime().block_on(self.device.pop_error_scope()) {
+            return Err(
+                TTCEWgpuError::CompileError.with_message(format!("{} : {}", operator_name, err))
+            );
+        }
 
         let id = TTComputeShaderID::from(self.compute_shader.len() as u32);
 
-        self.compute_shader.push(TTComputeShader {
-            module: cs_module,
-            pipeline: compute_pipeline,
-            binding_map: bind_map,
-            binding_type: bind_type,
-            work_group_size: wg_size,
-        });
+        self.compute_shader
+            .push(TTComputeShaderEntry::Registered(Arc::new(
+                TTComputeShader::new(
+                    cs_module,
+                    compute_pipeline,
+                    bind_map,
+                    bind_type,
+                    wg_size,
+                    layout,
+                ),
+            )));
 
         Ok(id)
     }
 }
 
+impl TexTransCoreEngineDevice {
+    // 一つのエントリーポイントで使える push constant は一つのみのため、 group と binding が最も小さい cbuffer を push constant にする
+    fn assign_push_constants(
+        &self,
+        naga_ir: &mut Module,
+        entry_point_info: &naga::valid::FunctionInfo,
+    ) -> Option<(TTBindingPoint, u32)> {
+        if !self
+            .device
+            .features()
+            .contains(wgpu::Features::PUSH_CONSTANTS)
+        {
+            return None;
+        }
+        let max_push_constant_size = self.device.limits().max_push_constant_size;
+
+        let gctx = naga_ir.to_ctx();
+        let (handle, bind_point, size) = naga_ir
+            .global_variables
+            .iter()
+            .filter(|(handle, gv)| {
+                gv.space == naga::AddressSpace::Uniform && !entry_point_info[*handle].is_empty()
+            })
+            .filter_map(|(handle, gv)| {
+                let binding = gv.binding.as_ref()?;
+                let size = naga_ir.types[gv.ty]
+                    .inner
+                    .size(gctx)
+                    .next_multiple_of(wgpu::PUSH_CONSTANT_ALIGNMENT);
+                Some((
+                    handle,
+                    TTBindingPoint::new(binding.group, binding.binding),
+                    size,
+                ))
+            })
+            .min_by_key(|(_, bind_point, _)| (bind_point.group, bind_point.binding))?;
+        if size > max_push_constant_size {
+            return None;
+        }
+
+        let gv = &mut naga_ir.global_variables[handle];
+        gv.space = naga::AddressSpace::PushConstant;
+        gv.binding = None;
+        Some((bind_point, size))
+    }
+
+    // 使われていない group も空の bind group layout として含めるため、 bind group は宣言されている最大の group まで set する
+    fn create_pipeline_layout(
+        &self,
+        group_entries: &[Vec<wgpu::BindGroupLayoutEntry>],
+        push_constants: Option<(TTBindingPoint, u32)>,
+    ) -> (wgpu::PipelineLayout, TTComputeShaderLayout) {
+        let bind_group_layouts: Vec<_> = group_entries
+            .iter()
+            .map(|entries| {
+                self.device
+                    .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                        label: Some("compute shader bind group layout"),
+                        entries,
+                    })
+            })
+            .collect();
+        let push_constant_ranges: Vec<_> = push_constants
+            .iter()
+            .map(|(_, size)| wgpu::PushConstantRange {
+                stages: wgpu::ShaderStages::COMPUTE,
+                range: 0..*size,
+            })
+            .collect();
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("compute shader pipeline layout"),
+                bind_group_layouts: &bind_group_layouts.iter().collect::<Vec<_>>(),
+                push_constant_ranges: &push_constant_ranges,
+            });
+        let used_bindings = group_entries
+            .iter()
+            .enumerate()
+            .flat_map(|(group, entries)| {
+                entries
+                    .iter()
+                    .map(move |e| TTBindingPoint::new(group as u32, e.binding))
+            })
+            .collect();
+
+        (
+            pipeline_layout,
+            TTComputeShaderLayout {
+                bind_group_layouts,
+                used_bindings,
+                push_constants,
+            },
+        )
+    }
+}
+
+const HLSL_ENTRY_POINT: &str = "CSMain";
+const HLSL_TARGET_PROFILE: &str = "cs_6_0";
+const DXC_ARGS: &[&str] = &["-spirv", "-HV 2018"];
+// const DXC_ARGS: &[&str] = &["-spirv", "-HV 2018","-O0"];
+const HLSL_DEFINES: &[(&str, Option<&str>)] = &[];
+
+impl TexTransCoreEngineDevice {
+    fn compile_hlsl_to_spirv(
+        &self,
+        hlsl_file_path: &str,
+        hlsl_source: &str,
+        operator_name: &str,
+    ) -> Result<Vec<u8>, TTCEWgpuErrorRecord> {
+        let cache_key = self.shader_cache.as_ref().map(|_| {
+            ShaderCacheKey::new(
+                hlsl_file_path,
+                hlsl_source,
+                HLSL_ENTRY_POINT,
+                HLSL_TARGET_PROFILE,
+                DXC_ARGS,
+                HLSL_DEFINES,
+                &format!("{:?}", self.default_texture_format()),
+            )
+        });
+        if let (Some(cache), Some(key)) = (&self.shader_cache, &cache_key) {
+            if let Some(spirv) = cache.load(key) {
+                return Ok(spirv);
+            }
+        }
+
+        // dxcompiler が読み込めなくてもデバイスは作られるため、キャッシュに無い HLSL を登録するときにエラーにする
+        let Some(dx_compiler) = self.dx_compiler() else {
+            return Err(TTCEWgpuError::CompileError.with_message(format!(
+                "{} : DirectX Shader Compiler is not loaded",
+                operator_name
+            )));
+        };
+        let output = dx_compiler
+            .compile_hlsl(
+                hlsl_file_path,
+                hlsl_source,
+                HLSL_ENTRY_POINT,
+                HLSL_TARGET_PROFILE,
+                DXC_ARGS,
+                HLSL_DEFINES,
+            )
+            .map_err(|e| {
+                TTCEWgpuError::CompileError.with_message(format!("{} : {}", operator_name, e))
+            })?;
+
+        if let (Some(cache), Some(key)) = (&self.shader_cache, &cache_key) {
+            let included_files: Option<Vec<_>> = output
+                .included_files
+                .iter()
+                .map(|p| IncludedFileStamp::from_path(p))
+                .collect();
+            // include したファイルの状態が取れない場合はキャッシュの正しさを保証できないので保存しない
+            if let Some(included_files) = included_files {
+                if let Err(e) = cache.store(key, &included_files, &output.spirv) {
+                    debug_log(&format!(
+                        "shader cache store failed : {} : {}",
+                        operator_name, e
+                    ));
+                }
+            }
+        }
+
+        Ok(output.spirv)
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct WorkGroupSize {
     pub x: u32,
     pub y: u32,
     pub z: u32,
 }
-pub struct TTComputeHandler<'ctx, 'rf, 'cs> {
+/// dispatch_indirect が読む x y z の u32 三つの大きさ
+const INDIRECT_ARGS_SIZE: u64 = 12;
+
+#[derive(Debug)]
+struct BoundConstantsBuffer {
+    data: Vec<u8>,
+    slice: ConstantsBufferSlice,
+}
+
+pub struct TTComputeHandler<'ctx, 'rf> {
     ctx: &'rf mut TexTransCoreEngineContext<'ctx>,
-    compute_shader: &'cs TTComputeShader,
+    compute_shader: Arc<TTComputeShader>,
+
+    bind_tex_view: HashMap<TTBindingPoint, (wgpu::Texture, TTColorSpace, wgpu::TextureView)>,
+    bind_constants_buffer: HashMap<TTBindingPoint, BoundConstantsBuffer>,
+    bind_storage_buffer: HashMap<TTBindingPoint, Arc<wgpu::Buffer>>,
+    bind_sampler: HashMap<TTBindingPoint, wgpu::Sampler>,
+    /// push constant にした cbuffer の値、 push constant が無い場合は空
+    push_constants_data: Vec<u8>,
 
-    bind_tex_view: HashMap<u32, wgpu::TextureView>,
-    bind_constants_buffer: HashMap<u32, wgpu::Buffer>,
-    bind_storage_buffer: HashMap<u32, Arc<wgpu::Buffer>>,
+    /// group ごとの前回の dispatch で作った bind group 、その group のバインディングが変更されると None になる。
+    bind_groups: Vec<Option<wgpu::BindGroup>>,
 }
-impl TTComputeHandler<'_, '_, '_> {
-    pub fn get_bind_index(&mut self, name: &str) -> Option<u32> {
+impl TTComputeHandler<'_, '_> {
+    pub fn get_bind_index(&mut self, name: &str) -> Option<TTBindingPoint> {
         self.compute_shader.binding_map.get(name).copied()
     }
 
+    /// デバイスに PUSH_CONSTANTS がある場合、一つ目の cbuffer は push constant になり、その大きさを超えるものは SizeMismatch になる。
     pub fn upload_constants_buffer(
         &mut self,
-        bind_index: u32,
+        bind_point: TTBindingPoint,
         buffer_data_span: &[u8],
     ) -> Result<(), TTCEWgpuError> {
-        let Some(bind_type) = self.compute_shader.binding_type.get(&bind_index) else {
+        let Some(bind_type) = self.compute_shader.binding_type.get(&bind_point) else {
             return Err(TTCEWgpuError::BindingNotFound);
         };
         if *bind_type != TTBindingType::ConstantsBuffer {
             return Err(TTCEWgpuError::BindingIsNotConstantsBuffer);
         }
 
-        if self.bind_constants_buffer.contains_key(&bind_index) {
-            let _ = self.bind_constants_buffer.remove(&bind_index).unwrap();
+        // push constant は dispatch のたびに送るため、チャンクにも bind group にも関わらない
+        if let Some((push_constants_point, _)) = self.compute_shader.push_constants {
+            if push_constants_point == bind_point {
+                if buffer_data_span.len() > self.push_constants_data.len() {
+                    return Err(TTCEWgpuError::SizeMismatch);
+                }
+                self.push_constants_data.fill(0);
+                self.push_constants_data[..buffer_data_span.len()]
+                    .copy_from_slice(buffer_data_span);
+                return Ok(());
+            }
+        }
+        if !self.is_used_binding(bind_point) {
+            return Ok(());
         }
 
-        let label = format!("{}-storage buffer", bind_index);
-        let buffer_desc = wgpu::util::BufferInitDescriptor {
-            label: Some(label.as_str()),
-            usage: wgpu::BufferUsages::UNIFORM,
-            contents: buffer_data_span,
-        };
-        let buffer = self.ctx.engine.device.create_buffer_init(&buffer_desc);
-        self.bind_constants_buffer.insert(bind_index, buffer);
+        // 同じ値であれば書き込みなおさない、送信済みの場合は dispatch で書き込みなおされる
+        if let Some(bound) = self.bind_constants_buffer.get(&bind_point) {
+            if bound.data == buffer_data_span {
+                return Ok(());
+            }
+        }
+
+        let slice = self.ctx.allocate_constants_buffer(buffer_data_span);
+        // オフセットは dispatch で渡すため、同じチャンクに同じ大きさで書き込まれた場合は bind group を作り直さない
+        let is_same_binding = self
+            .bind_constants_buffer
+            .get(&bind_point)
+            .is_some_and(|bound| bound.slice.is_same_binding(&slice));
+        self.bind_constants_buffer.insert(
+            bind_point,
+            BoundConstantsBuffer {
+                data: buffer_data_span.to_vec(),
+                slice,
+            },
+        );
+        if !is_same_binding {
+            self.invalidate_bind_group(bind_point);
+        }
 
         Ok(())
     }
     pub fn set_storage_buffer(
         &mut self,
-        bind_index: u32,
+        bind_point: TTBindingPoint,
         buffer: &TTStorageBuffer,
     ) -> Result<(), TTCEWgpuError> {
-        let Some(bind_type) = self.compute_shader.binding_type.get(&bind_index) else {
+        let Some(bind_type) = self.compute_shader.binding_type.get(&bind_point) else {
             return Err(TTCEWgpuError::BindingNotFound);
         };
         if *bind_type != TTBindingType::StorageBuffer {
             return Err(TTCEWgpuError::BindingIsNotStorageBuffer);
         }
+        if !self.is_used_binding(bind_point) {
+            return Ok(());
+        }
 
-        if self.bind_storage_buffer.contains_key(&bind_index) {
-            let _ = self.bind_storage_buffer.remove(&bind_index).unwrap();
+        if let Some(bound) = self.bind_storage_buffer.get(&bind_point) {
+            if Arc::ptr_eq(bound, &buffer.buffer) {
+                return Ok(());
+            }
         }
 
         self.bind_storage_buffer
-            .insert(bind_index, buffer.buffer.clone());
+            .insert(bind_point, buffer.buffer.clone());
+        self.invalidate_bind_group(bind_point);
 
         Ok(())
     }
 
     pub fn set_render_texture(
         &mut self,
-        bind_index: u32,
+        bind_point: TTBindingPoint,
         render_texture: &TTRenderTexture,
     ) -> Result<(), TTCEWgpuError> {
-        let Some(bind_type) = self.compute_shader.binding_type.get(&bind_index) else {
+        let Some(bind_type) = self.compute_shader.binding_type.get(&bind_point) else {
             return Err(TTCEWgpuError::BindingNotFound);
         };
-        if *bind_type != TTBindingType::RWRenderTexture {
-            return Err(TTCEWgpuError::BindingIsNotRWTexture);
+        // 同じテクスチャーと色空間であれば view も同じものになるため、 view も bind group も作り直さない
+        let is_same_texture =
+            self.bind_tex_view
+                .get(&bind_point)
+                .is_some_and(|(bound, bound_color_space, _)| {
+                    *bound == render_texture.texture
+                        && *bound_color_space == render_texture.color_space
+                });
+        if is_same_texture {
+            return Ok(());
+        }
+        let tex_view = match *bind_type {
+            TTBindingType::RWRenderTexture(view_dimension) => {
+                if !is_bindable_texture(render_texture, view_dimension) {
+                    return Err(TTCEWgpuError::TextureDimensionMismatch);
+                }
+                // storage texture にはミップ一つの view しか使えないため mip 0 を使う
+                render_texture
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor {
+                        dimension: Some(view_dimension),
+                        mip_level_count: Some(1),
+                        ..Default::default()
+                    })
+            }
+            TTBindingType::Texture {
+                view_dimension,
+                filterable,
+            } => {
+                if !is_bindable_texture(render_texture, view_dimension) {
+                    return Err(TTCEWgpuError::TextureDimensionMismatch);
+                }
+                // サンプラーで読まれるものは filterable として登録されているため、
+                // FLOAT32_FILTERABLE が無い場合の 32bit float はそこに渡せず、 Load でのみ読まれるものにしか渡せない
+                let sample_type = render_texture
+                    .format()
+                    .sample_type(None, Some(self.ctx.engine.device.features()));
+                match sample_type {
+                    Some(wgpu::TextureSampleType::Float { filterable: true }) => {}
+                    Some(wgpu::TextureSampleType::Float { filterable: false }) if !filterable => {}
+                    Some(wgpu::TextureSampleType::Float { .. }) => {
+                        return Err(TTCEWgpuError::TextureIsNotFilterable)
+                    }
+                    _ => return Err(TTCEWgpuError::UnsupportedFormat),
+                }
+                // sRGB の印がついている場合は、サンプリングでデコードされたリニアな値が得られる view にする
+                let view_format = match render_texture.color_space {
+                    TTColorSpace::Srgb => Some(render_texture.format().add_srgb_suffix()),
+                    _ => None,
+                };
+                // サンプラーでミップを補間できるように、すべてのミップを含めた view にする
+                render_texture
+                    .texture
+                    .create_view(&wgpu::TextureViewDescriptor {
+                        format: view_format,
+                        dimension: Some(view_dimension),
+                        ..Default::default()
+                    })
+            }
+            _ => return Err(TTCEWgpuError::BindingIsNotTexture),
+        };
+        if !self.is_used_binding(bind_point) {
+            return Ok(());
+        }
+
+        self.bind_tex_view.insert(
+            bind_point,
+            (
+                render_texture.texture.clone(),
+                render_texture.color_space,
+                tex_view,
+            ),
+        );
+        self.invalidate_bind_group(bind_point);
+        Ok(())
+    }
+
+    pub fn set_sampler(
+        &mut self,
+        bind_point: TTBindingPoint,
+        sampler: &TTSampler,
+    ) -> Result<(), TTCEWgpuError> {
+        let Some(bind_type) = self.compute_shader.binding_type.get(&bind_point) else {
+            return Err(TTCEWgpuError::BindingNotFound);
+        };
+        if *bind_type != TTBindingType::Sampler {
+            return Err(TTCEWgpuError::BindingIsNotSampler);
+        }
+        if !self.is_used_binding(bind_point) {
+            return Ok(());
         }
 
-        let tex_view = render_texture
-            .texture
-            .create_view(&wgpu::TextureViewDescriptor::default());
+        if self.bind_sampler.get(&bind_point) == Some(&sampler.sampler) {
+            return Ok(());
+        }
 
-        self.bind_tex_view.insert(bind_index, tex_view);
+        self.bind_sampler
+            .insert(bind_point, sampler.sampler.clone());
+        self.invalidate_bind_group(bind_point);
         Ok(())
     }
 
@@ -227,50 +648,203 @@ impl TTComputeHandler<'_, '_, '_> {
         self.compute_shader.work_group_size
     }
 
-    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) {
-        let tex_entries = self.bind_tex_view.iter().map(|t| wgpu::BindGroupEntry {
-            binding: *t.0,
-            resource: wgpu::BindingResource::TextureView(t.1),
-        });
-        let constants_buffer_entries =
-            self.bind_constants_buffer
-                .iter()
-                .map(|b| wgpu::BindGroupEntry {
-                    binding: *b.0,
-                    resource: b.1.as_entire_binding(),
-                });
-        let storage_buffer_entries =
-            self.bind_storage_buffer
-                .iter()
-                .map(|ab| wgpu::BindGroupEntry {
-                    binding: *ab.0,
-                    resource: ab.1.as_ref().as_entire_binding(),
-                });
+    pub fn dispatch(&mut self, x: u32, y: u32, z: u32) -> Result<(), TTCEWgpuError> {
+        self.dispatch_with(|compute_pass| compute_pass.dispatch_workgroups(x, y, z))
+    }
+
+    /// render_texture の全体を覆うように、ワークグループ数を切り上げて dispatch する。
+    /// 端のワークグループははみ出すため、シェーダー側で範囲外を書き込まないようにすること。
+    pub fn dispatch_for_texture(
+        &mut self,
+        render_texture: &TTRenderTexture,
+    ) -> Result<(), TTCEWgpuError> {
+        let wg_size = self.compute_shader.work_group_size;
+        self.dispatch(
+            render_texture.width().div_ceil(wg_size.x),
+            render_texture.height().div_ceil(wg_size.y),
+            render_texture.depth_or_array_layers().div_ceil(wg_size.z),
+        )
+    }
+
+    /// indirect_buffer の indirect_offset から u32 の x y z を読んで dispatch する。
+    /// GPU 側で書き込んだ値をそのまま使えるため、ワークグループ数を知るためにダウンロードする必要がない。
+    pub fn dispatch_indirect(
+        &mut self,
+        indirect_buffer: &TTStorageBuffer,
+        indirect_offset: u64,
+    ) -> Result<(), TTCEWgpuError> {
+        let buffer = indirect_buffer.buffer.as_ref();
+        if !buffer.usage().contains(wgpu::BufferUsages::INDIRECT) {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
+        if indirect_offset % 4 != 0
+            || indirect_offset
+                .checked_add(INDIRECT_ARGS_SIZE)
+                .is_none_or(|end| end > buffer.size())
+        {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
+
+        self.dispatch_with(|compute_pass| {
+            compute_pass.dispatch_workgroups_indirect(buffer, indirect_offset)
+        })
+    }
+
+    fn dispatch_with(
+        &mut self,
+        dispatch: impl FnOnce(&mut wgpu::ComputePass),
+    ) -> Result<(), TTCEWgpuError> {
+        self.ctx.check_device_lost()?;
+        self.reallocate_expired_constants_buffer();
+        for group in 0..self.bind_groups.len() {
+            if self.bind_groups[group].is_some() {
+                self.ctx
+                    .engine
+                    .bind_group_reused_count
+                    .fetch_add(1, Ordering::Relaxed);
+                continue;
+            }
+            let bind_group = self.create_bind_group(group as u32);
+            self.bind_groups[group] = Some(bind_group);
+            self.ctx
+                .engine
+                .bind_group_created_count
+                .fetch_add(1, Ordering::Relaxed);
+        }
+        let dynamic_offsets: Vec<_> = (0..self.bind_groups.len())
+            .map(|group| self.dynamic_offsets(group as u32))
+            .collect();
+        let bind_groups = &self.bind_groups;
+        let push_constants_data = &self.push_constants_data;
+
+        {
+            let encoder = self.ctx.get_command_encoder_as_mut();
+            let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+
+            compute_pass.set_pipeline(&self.compute_shader.pipeline);
+            for (group, bind_group) in bind_groups.iter().enumerate() {
+                compute_pass.set_bind_group(
+                    group as u32,
+                    bind_group.as_ref(),
+                    &dynamic_offsets[group],
+                );
+            }
+            if !push_constants_data.is_empty() {
+                compute_pass.set_push_constants(0, push_constants_data);
+            }
+            dispatch(&mut compute_pass);
+        }
+        self.ctx.check_command_stack();
+        Ok(())
+    }
+
+    // send_command の後はその前に書き込んだ領域が上書きされうるため、新しい領域に書き込みなおす
+    fn reallocate_expired_constants_buffer(&mut self) {
+        let generation = self.ctx.constants_buffer_allocator.generation();
+        let mut expired_points = Vec::new();
+        for (bind_point, bound) in self.bind_constants_buffer.iter_mut() {
+            if bound.slice.generation == generation {
+                continue;
+            }
+            let slice = self.ctx.allocate_constants_buffer(&bound.data);
+            if !bound.slice.is_same_binding(&slice) {
+                expired_points.push(*bind_point);
+            }
+            bound.slice = slice;
+        }
+        for bind_point in expired_points {
+            self.invalidate_bind_group(bind_point);
+        }
+    }
+
+    // レイアウトの dynamic offset の順番と同じく binding の順に並べる
+    fn dynamic_offsets(&self, group: u32) -> Vec<u32> {
+        let mut bound: Vec<_> = self
+            .bind_constants_buffer
+            .iter()
+            .filter(|b| b.0.group == group)
+            .collect();
+        bound.sort_by_key(|b| b.0.binding);
+        bound.iter().map(|b| b.1.slice.offset as u32).collect()
+    }
+
+    // 使われていないバインディングはレイアウトに無いため、 bind group に含めると作成に失敗する
+    fn is_used_binding(&self, bind_point: TTBindingPoint) -> bool {
+        self.compute_shader.used_bindings.contains(&bind_point)
+    }
+
+    fn invalidate_bind_group(&mut self, bind_point: TTBindingPoint) {
+        if let Some(bind_group) = self.bind_groups.get_mut(bind_point.group as usize) {
+            *bind_group = None;
+        }
+    }
+
+    fn create_bind_group(&self, group: u32) -> wgpu::BindGroup {
+        let tex_entries = self
+            .bind_tex_view
+            .iter()
+            .filter(|t| t.0.group == group)
+            .map(|t| wgpu::BindGroupEntry {
+                binding: t.0.binding,
+                resource: wgpu::BindingResource::TextureView(&t.1 .2),
+            });
+        let constants_buffer_entries = self
+            .bind_constants_buffer
+            .iter()
+            .filter(|b| b.0.group == group)
+            .map(|b| wgpu::BindGroupEntry {
+                binding: b.0.binding,
+                resource: b.1.slice.as_dynamic_binding(),
+            });
+        let storage_buffer_entries = self
+            .bind_storage_buffer
+            .iter()
+            .filter(|ab| ab.0.group == group)
+            .map(|ab| wgpu::BindGroupEntry {
+                binding: ab.0.binding,
+                resource: ab.1.as_ref().as_entire_binding(),
+            });
+        let sampler_entries = self
+            .bind_sampler
+            .iter()
+            .filter(|s| s.0.group == group)
+            .map(|s| wgpu::BindGroupEntry {
+                binding: s.0.binding,
+                resource: wgpu::BindingResource::Sampler(s.1),
+            });
 
         let entries: Vec<_> = tex_entries
             .chain(constants_buffer_entries)
             .chain(storage_buffer_entries)
+            .chain(sampler_entries)
             .collect();
 
-        let bind_group = self
-            .ctx
+        self.ctx
             .engine
             .device
             .create_bind_group(&wgpu::BindGroupDescriptor {
                 label: Some("dispatch compte from handler"),
-                layout: &self.compute_shader.pipeline.get_bind_group_layout(0),
+                layout: &self.compute_shader.bind_group_layouts[group as usize],
                 entries: &entries,
-            });
-
-        {
-            let encoder = self.ctx.get_command_encoder_as_mut();
-            let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+            })
+    }
+}
 
-            compute_pass.set_pipeline(&self.compute_shader.pipeline);
-            compute_pass.set_bind_group(0, &bind_group, &[]);
-            compute_pass.dispatch_workgroups(x, y, z);
+// Texture2D と RWTexture2D には配列でない 2D 、 *2DArray には 2D (レイヤー数は問わない) 、 *3D には 3D のみを渡せる。
+fn is_bindable_texture(
+    render_texture: &TTRenderTexture,
+    view_dimension: wgpu::TextureViewDimension,
+) -> bool {
+    match view_dimension {
+        wgpu::TextureViewDimension::D2 => {
+            render_texture.dimension() == wgpu::TextureDimension::D2
+                && render_texture.depth_or_array_layers() == 1
         }
-        self.ctx.check_command_stack();
+        wgpu::TextureViewDimension::D2Array => {
+            render_texture.dimension() == wgpu::TextureDimension::D2
+        }
+        wgpu::TextureViewDimension::D3 => render_texture.dimension() == wgpu::TextureDimension::D3,
+        _ => false,
     }
 }
 
@@ -278,10 +852,19 @@ impl<'ctx> TexTransCoreEngineContext<'ctx> {
     pub fn get_compute_handler<'rf>(
         &'rf mut self,
         id: &TTComputeShaderID,
-    ) -> Result<TTComputeHandler<'ctx, 'rf, 'ctx>, &str> {
-        let Some(compute_shader) = self.engine.compute_shader.get(*id.deref() as usize) else {
-            return Err("un registered id");
+    ) -> Result<TTComputeHandler<'ctx, 'rf>, TTCEWgpuError> {
+        let compute_shader = match self.engine.compute_shader.get(*id.deref() as usize) {
+            Some(TTComputeShaderEntry::Registered(compute_shader)) => compute_shader.clone(),
+            Some(TTComputeShaderEntry::FormatConverter(key)) => {
+                self.engine.get_or_create_format_converter(*key)
+            }
+            Some(TTComputeShaderEntry::BlockCompressor(key)) => {
+                self.engine.get_or_create_block_compressor(*key)
+            }
+            None => return Err(TTCEWgpuError::UnregisteredComputeShader),
         };
+        let push_constants_size = compute_shader.push_constants.map_or(0, |(_, size)| size);
+        let bind_group_count = compute_shader.bind_group_layouts.len();
 
         Ok(TTComputeHandler {
             ctx: self,
@@ -289,6 +872,10 @@ impl<'ctx> TexTransCoreEngineContext<'ctx> {
             bind_tex_view: HashMap::new(),
             bind_constants_buffer: HashMap::new(),
             bind_storage_buffer: HashMap::new(),
+            bind_sampler: HashMap::new(),
+            push_constants_data: vec![0; push_constants_size as usize],
+
+            bind_groups: vec![None; bind_group_count],
         })
     }
 }
@@ -307,7 +894,7 @@ fn fix_storage_texture_format(naga_ir: &mut Module, tt_format: TexTransCoreTextu
         else {
             continue;
         };
-        if ad || id != ImageDimension::D2 {
+        if id != ImageDimension::D2 && id != ImageDimension::D3 {
             continue;
         }
 
@@ -344,7 +931,7 @@ fn fix_storage_texture_format(naga_ir: &mut Module, tt_format: TexTransCoreTextu
     }
 }
 
-fn get_bindings(naga_ir: &Module) -> Vec<(String, u32)> {
+fn get_bindings(naga_ir: &Module) -> Vec<(String, TTBindingPoint)> {
     naga_ir
         .global_variables
         .iter()
@@ -354,19 +941,19 @@ fn get_bindings(naga_ir: &Module) -> Vec<(String, u32)> {
             if gv.name.is_none() || gv.binding.is_none() {
                 return None;
             }
-            if gv.binding.as_ref()?.group != 0 {
-                debug_log("not supported binding group is not 0");
-                return None;
-            }
 
+            let binding = gv.binding.as_ref().unwrap();
             Some((
                 String::from(gv.name.as_ref().unwrap().as_str()),
-                gv.binding.as_ref().unwrap().binding,
+                TTBindingPoint::new(binding.group, binding.binding),
             ))
         })
         .collect()
 }
-fn get_binding_types(naga_ir: &Module) -> Vec<(u32, TTBindingType)> {
+fn get_binding_types(
+    naga_ir: &Module,
+    sampled_images: &HashSet<Handle<naga::GlobalVariable>>,
+) -> Vec<(TTBindingPoint, TTBindingType)> {
     naga_ir
         .global_variables
         .iter()
@@ -376,10 +963,6 @@ fn get_binding_types(naga_ir: &Module) -> Vec<(u32, TTBindingType)> {
             if gv.name.is_none() || gv.binding.is_none() {
                 return None;
             }
-            if gv.binding.as_ref()?.group != 0 {
-                debug_log("not supported binding group is not 0");
-                return None;
-            }
 
             let bind_type = match naga_ir.types[gv.ty].inner {
                 naga::TypeInner::Struct { .. } => match gv.space {
@@ -387,19 +970,189 @@ fn get_binding_types(naga_ir: &Module) -> Vec<(u32, TTBindingType)> {
                     naga::AddressSpace::Storage { .. } => Some(TTBindingType::StorageBuffer),
                     _ => None,
                 },
-                naga::TypeInner::Image { .. } => Some(TTBindingType::RWRenderTexture),
+                naga::TypeInner::Image {
+                    dim,
+                    arrayed,
+                    class,
+                } => {
+                    let Some(view_dimension) = to_view_dimension(dim, arrayed) else {
+                        debug_log("not supported texture dimension, only 2D, 2D array and 3D");
+                        return None;
+                    };
+                    match class {
+                        ImageClass::Storage { .. } => {
+                            Some(TTBindingType::RWRenderTexture(view_dimension))
+                        }
+                        ImageClass::Sampled { .. } => Some(TTBindingType::Texture {
+                            view_dimension,
+                            filterable: sampled_images.contains(&gv_h.0),
+                        }),
+                        ImageClass::Depth { .. } => {
+                            debug_log("not supported depth texture");
+                            None
+                        }
+                    }
+                }
+                naga::TypeInner::Sampler { comparison: false } => Some(TTBindingType::Sampler),
                 _ => None,
             }?;
 
-            Some((gv.binding.as_ref().unwrap().binding, bind_type))
+            let binding = gv.binding.as_ref().unwrap();
+            Some((
+                TTBindingPoint::new(binding.group, binding.binding),
+                bind_type,
+            ))
         })
         .collect()
 }
+fn to_view_dimension(dim: ImageDimension, arrayed: bool) -> Option<wgpu::TextureViewDimension> {
+    match (dim, arrayed) {
+        (ImageDimension::D2, false) => Some(wgpu::TextureViewDimension::D2),
+        (ImageDimension::D2, true) => Some(wgpu::TextureViewDimension::D2Array),
+        (ImageDimension::D3, _) => Some(wgpu::TextureViewDimension::D3),
+        _ => None,
+    }
+}
+// 自動生成と同じくエントリーポイントから使われているバインディングのみを含め、使われていない group は空のレイアウトにする
+fn get_bind_group_layout_entries(
+    naga_ir: &Module,
+    entry_point_info: &naga::valid::FunctionInfo,
+    sampled_images: &HashSet<Handle<naga::GlobalVariable>>,
+) -> Result<Vec<Vec<wgpu::BindGroupLayoutEntry>>, String> {
+    let mut group_entries: Vec<Vec<wgpu::BindGroupLayoutEntry>> = Vec::new();
+    for (handle, gv) in naga_ir.global_variables.iter() {
+        let Some(binding) = gv.binding.as_ref() else {
+            continue;
+        };
+        let group = binding.group as usize;
+        if group_entries.len() <= group {
+            group_entries.resize_with(group + 1, Vec::new);
+        }
+        if entry_point_info[handle].is_empty() {
+            continue;
+        }
+
+        let ty = to_binding_type(naga_ir, gv, sampled_images.contains(&handle))
+            .ok_or_else(|| format!("unsupported binding : {:?}", gv.name))?;
+        group_entries[group].push(wgpu::BindGroupLayoutEntry {
+            binding: binding.binding,
+            visibility: wgpu::ShaderStages::COMPUTE,
+            ty,
+            count: None,
+        });
+    }
+    Ok(group_entries)
+}
+// Load でのみ読まれるテクスチャーは、フィルタリングできないフォーマットも渡せるように filterable にしない
+fn to_binding_type(
+    naga_ir: &Module,
+    gv: &naga::GlobalVariable,
+    is_sampled: bool,
+) -> Option<wgpu::BindingType> {
+    match gv.space {
+        naga::AddressSpace::Uniform => {
+            // チャンクのどこに書き込まれたかは dispatch のたびに変わるため、オフセットは set_bind_group で渡す
+            return Some(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: true,
+                min_binding_size: None,
+            });
+        }
+        naga::AddressSpace::Storage { access } => {
+            return Some(wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage {
+                    read_only: !access.contains(naga::StorageAccess::STORE),
+                },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            })
+        }
+        _ => {}
+    }
+
+    match naga_ir.types[gv.ty].inner {
+        Image {
+            dim,
+            arrayed,
+            class,
+        } => {
+            let view_dimension = to_view_dimension(dim, arrayed)?;
+            match class {
+                ImageClass::Sampled { kind, multi: false } => {
+                    let sample_type = match kind {
+                        naga::ScalarKind::Float => wgpu::TextureSampleType::Float {
+                            filterable: is_sampled,
+                        },
+                        naga::ScalarKind::Sint => wgpu::TextureSampleType::Sint,
+                        naga::ScalarKind::Uint => wgpu::TextureSampleType::Uint,
+                        _ => return None,
+                    };
+                    Some(wgpu::BindingType::Texture {
+                        sample_type,
+                        view_dimension,
+                        multisampled: false,
+                    })
+                }
+                ImageClass::Storage { format, access } => {
+                    let access = if access
+                        .contains(naga::StorageAccess::LOAD | naga::StorageAccess::STORE)
+                    {
+                        wgpu::StorageTextureAccess::ReadWrite
+                    } else if access.contains(naga::StorageAccess::STORE) {
+                        wgpu::StorageTextureAccess::WriteOnly
+                    } else {
+                        wgpu::StorageTextureAccess::ReadOnly
+                    };
+                    Some(wgpu::BindingType::StorageTexture {
+                        access,
+                        format: TTRenderTexture::from_naga_storage_texture_format(format)?,
+                        view_dimension,
+                    })
+                }
+                _ => None,
+            }
+        }
+        naga::TypeInner::Sampler { comparison: false } => Some(wgpu::BindingType::Sampler(
+            wgpu::SamplerBindingType::Filtering,
+        )),
+        _ => None,
+    }
+}
+
+// SampleLevel などでサンプラーと共に読まれるテクスチャー、 DXC は関数をインライン化するため直接 GlobalVariable を参照している
+fn get_sampled_images(naga_ir: &Module) -> HashSet<Handle<naga::GlobalVariable>> {
+    let functions = naga_ir
+        .functions
+        .iter()
+        .map(|(_, f)| f)
+        .chain(naga_ir.entry_points.iter().map(|e| &e.function));
+    let mut sampled_images = HashSet::new();
+    for function in functions {
+        for (_, expression) in function.expressions.iter() {
+            let naga::Expression::ImageSample { image, .. } = expression else {
+                continue;
+            };
+            if let naga::Expression::GlobalVariable(handle) = function.expressions[*image] {
+                sampled_images.insert(handle);
+            }
+        }
+    }
+    sampled_images
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub(crate) enum TTBindingType {
     ConstantsBuffer,
     StorageBuffer,
-    RWRenderTexture,
+    /// シェーダー側で宣言されている次元
+    RWRenderTexture(wgpu::TextureViewDimension),
+    /// 読み取り専用の Texture2D など、サンプラーで読むことができる
+    Texture {
+        view_dimension: wgpu::TextureViewDimension,
+        /// サンプラーと共に読まれていて、フィルタリングできるフォーマットのみを受け付ける
+        filterable: bool,
+    },
+    Sampler,
 }
 
 fn get_work_group_size(naga_ir: &Module) -> WorkGroupSize {
@@ -425,16 +1178,32 @@ pub trait AsTypeStr {
 impl AsTypeStr for wgpu::TextureFormat {
     fn as_type_str(&self) -> &'static str {
         match self {
+            wgpu::TextureFormat::R8Unorm => WGSL_R8UNORM,
+            wgpu::TextureFormat::Rg8Unorm => WGSL_RG8UNORM,
             wgpu::TextureFormat::Rgba8Unorm => WGSL_RGBA8UNORM,
+            wgpu::TextureFormat::R16Unorm => WGSL_R16UNORM,
+            wgpu::TextureFormat::Rg16Unorm => WGSL_RG16UNORM,
             wgpu::TextureFormat::Rgba16Unorm => WGSL_RGBA16UNORM,
+            wgpu::TextureFormat::R16Float => WGSL_R16FLOAT,
+            wgpu::TextureFormat::Rg16Float => WGSL_RG16FLOAT,
             wgpu::TextureFormat::Rgba16Float => WGSL_RGBA16FLOAT,
+            wgpu::TextureFormat::R32Float => WGSL_R32FLOAT,
+            wgpu::TextureFormat::Rg32Float => WGSL_RG32FLOAT,
             wgpu::TextureFormat::Rgba32Float => WGSL_RGBA32FLOAT,
             _ => panic!(),
         }
     }
 }
 
+const WGSL_R8UNORM: &str = "r8unorm";
+const WGSL_RG8UNORM: &str = "rg8unorm";
 const WGSL_RGBA8UNORM: &str = "rgba8unorm";
+const WGSL_R16UNORM: &str = "r16unorm";
+const WGSL_RG16UNORM: &str = "rg16unorm";
 const WGSL_RGBA16UNORM: &str = "rgba16unorm";
+const WGSL_R16FLOAT: &str = "r16float";
+const WGSL_RG16FLOAT: &str = "rg16float";
 const WGSL_RGBA16FLOAT: &str = "rgba16float";
+const WGSL_R32FLOAT: &str = "r32float";
+const WGSL_RG32FLOAT: &str = "rg32float";
 const WGSL_RGBA32FLOAT: &str = "rgba32float";
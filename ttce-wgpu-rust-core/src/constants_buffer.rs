@@ -0,0 +1,162 @@
+use wgpu::util::DeviceExt;
+
+use crate::tex_trans_core_engine::TexTransCoreEngineContext;
+
+/// 一つのチャンクの大きさ、 max_uniform_buffer_binding_size の既定値と同じ
+pub const CONSTANTS_BUFFER_CHUNK_SIZE: u64 = 64 * 1024;
+
+/// cbuffer の定数をコンテキストごとのチャンクに詰めて書き込むためのもの。
+/// queue.write_buffer は次の submit の command より先に実行されるため、 submit されるまでは同じ領域を使いまわさず、 send_command のたびに先頭に戻る。
+#[derive(Debug)]
+pub(crate) struct ConstantsBufferAllocator {
+    chunks: Vec<wgpu::Buffer>,
+    current_chunk: usize,
+    cursor: u64,
+    generation: u64,
+
+    buffer_created_count: u64,
+    suballocated_count: u64,
+}
+
+/// generation が現在のものと異なる場合は、送信済みで上書きされうる領域を指している。
+#[derive(Debug, Clone)]
+pub(crate) struct ConstantsBufferSlice {
+    pub(crate) buffer: wgpu::Buffer,
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+    pub(crate) generation: u64,
+}
+impl ConstantsBufferSlice {
+    pub(crate) fn as_binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: self.offset,
+            size: wgpu::BufferSize::new(self.size),
+        })
+    }
+    /// has_dynamic_offset のレイアウト用、 offset は set_bind_group で渡すように。
+    pub(crate) fn as_dynamic_binding(&self) -> wgpu::BindingResource<'_> {
+        wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+            buffer: &self.buffer,
+            offset: 0,
+            size: wgpu::BufferSize::new(self.size),
+        })
+    }
+    /// dynamic offset で bind する場合に、同じ bind group のまま使えるか
+    pub(crate) fn is_same_binding(&self, other: &ConstantsBufferSlice) -> bool {
+        self.buffer == other.buffer && self.size == other.size
+    }
+}
+
+/// ConstantsBufferAllocator の状態、 C# 側から取得するためのもの。
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TTConstantsBufferStatistics {
+    pub chunk_count: u32,
+    pub buffer_created_count: u64,
+    pub suballocated_count: u64,
+}
+
+impl ConstantsBufferAllocator {
+    pub(crate) fn new() -> Self {
+        ConstantsBufferAllocator {
+            chunks: Vec::new(),
+            current_chunk: 0,
+            cursor: 0,
+            generation: 0,
+
+            buffer_created_count: 0,
+            suballocated_count: 0,
+        }
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// 積まれている command が submit された後に呼ぶように。
+    pub(crate) fn reset(&mut self) {
+        self.current_chunk = 0;
+        self.cursor = 0;
+        self.generation += 1;
+    }
+
+    pub(crate) fn statistics(&self) -> TTConstantsBufferStatistics {
+        TTConstantsBufferStatistics {
+            chunk_count: self.chunks.len() as u32,
+            buffer_created_count: self.buffer_created_count,
+            suballocated_count: self.suballocated_count,
+        }
+    }
+
+    fn allocate(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &[u8],
+    ) -> ConstantsBufferSlice {
+        // write_buffer のサイズは 4 の倍数でないといけないため、末尾を 0 で埋める
+        let size = (data.len() as u64)
+            .next_multiple_of(wgpu::COPY_BUFFER_ALIGNMENT)
+            .max(wgpu::COPY_BUFFER_ALIGNMENT);
+        let mut padded_data = data.to_vec();
+        padded_data.resize(size as usize, 0);
+
+        // チャンクに収まらない大きさのものは専用のバッファーを作る
+        if size > CONSTANTS_BUFFER_CHUNK_SIZE {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("large constants buffer"),
+                usage: wgpu::BufferUsages::UNIFORM,
+                contents: &padded_data,
+            });
+            self.buffer_created_count += 1;
+            return ConstantsBufferSlice {
+                buffer,
+                offset: 0,
+                size,
+                generation: self.generation,
+            };
+        }
+
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as u64;
+        let mut offset = self.cursor.next_multiple_of(alignment);
+        if offset + size > CONSTANTS_BUFFER_CHUNK_SIZE {
+            self.current_chunk += 1;
+            offset = 0;
+        }
+        if self.current_chunk == self.chunks.len() {
+            self.chunks
+                .push(device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("constants buffer chunk"),
+                    size: CONSTANTS_BUFFER_CHUNK_SIZE,
+                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }));
+            self.buffer_created_count += 1;
+        }
+        self.cursor = offset + size;
+
+        let buffer = self.chunks[self.current_chunk].clone();
+        queue.write_buffer(&buffer, offset, &padded_data);
+        self.suballocated_count += 1;
+
+        ConstantsBufferSlice {
+            buffer,
+            offset,
+            size,
+            generation: self.generation,
+        }
+    }
+}
+
+impl TexTransCoreEngineContext<'_> {
+    pub(crate) fn allocate_constants_buffer(&mut self, data: &[u8]) -> ConstantsBufferSlice {
+        let engine = self.engine;
+        self.constants_buffer_allocator
+            .allocate(&engine.device, &engine.queue, data)
+    }
+
+    pub fn constants_buffer_statistics(&self) -> TTConstantsBufferStatistics {
+        self.constants_buffer_allocator.statistics()
+    }
+}
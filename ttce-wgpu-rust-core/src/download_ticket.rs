@@ -0,0 +1,153 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+use crate::{
+    render_texture::DownloadLayout,
+    tex_trans_core_engine::{poll_until, TTCEWgpuError},
+};
+
+/// 既定の保持しておく read back buffer の合計サイズの上限
+pub const DEFAULT_STAGING_BUFFER_POOL_BUDGET: u64 = 256 * 1024 * 1024;
+
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TTDownloadTicketState {
+    Pending = 0,
+    Ready = 1,
+    Failed = 2,
+}
+impl TTDownloadTicketState {
+    fn from_u32(value: u32) -> Self {
+        match value {
+            0 => TTDownloadTicketState::Pending,
+            1 => TTDownloadTicketState::Ready,
+            _ => TTDownloadTicketState::Failed,
+        }
+    }
+}
+
+pub type DownloadCompletedCallback = Box<dyn FnOnce(TTDownloadTicketState) + Send>;
+
+/// 読み戻し用の MAP_READ なバッファーを、ダウンロードが読み終わった後に再利用するためのもの。
+/// 同じ大きさのものを使いまわすだけのため、ブロック圧縮の出力先の STORAGE なバッファーにも使っている。
+#[derive(Debug)]
+pub(crate) struct StagingBufferPool {
+    budget: u64,
+    idle: HashMap<u64, Vec<wgpu::Buffer>>,
+    idle_byte: u64,
+}
+impl StagingBufferPool {
+    pub(crate) fn new(budget: u64) -> Self {
+        StagingBufferPool {
+            budget,
+            idle: HashMap::new(),
+            idle_byte: 0,
+        }
+    }
+    pub(crate) fn take(&mut self, size: u64) -> Option<wgpu::Buffer> {
+        let buffer = self.idle.get_mut(&size)?.pop()?;
+        self.idle_byte -= size;
+        Some(buffer)
+    }
+    pub(crate) fn recycle(&mut self, buffer: wgpu::Buffer) {
+        let size = buffer.size();
+        if self.idle_byte + size > self.budget {
+            return;
+        }
+        self.idle_byte += size;
+        self.idle.entry(size).or_default().push(buffer);
+    }
+}
+
+/// 非同期のダウンロードの要求。 GPU 側のコピーが終わると Ready になり、 read で読み出すことができる。
+/// 状態は poll か wait 、またはデバイスの poll を行わないと進まない。
+#[derive(Debug)]
+pub struct TTDownloadTicket {
+    device: wgpu::Device,
+    buffer: wgpu::Buffer,
+    layout: DownloadLayout,
+    state: Arc<AtomicU32>,
+    pool: Weak<Mutex<StagingBufferPool>>,
+}
+impl TTDownloadTicket {
+    /// on_completed は Ready か Failed になったときに、 poll を行ったスレッドで呼ばれる。
+    pub(crate) fn new(
+        device: wgpu::Device,
+        buffer: wgpu::Buffer,
+        layout: DownloadLayout,
+        pool: Weak<Mutex<StagingBufferPool>>,
+        on_completed: Option<DownloadCompletedCallback>,
+    ) -> Self {
+        let state = Arc::new(AtomicU32::new(TTDownloadTicketState::Pending as u32));
+        let state_flag = state.clone();
+        buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let completed_state = if result.is_ok() {
+                    TTDownloadTicketState::Ready
+                } else {
+                    TTDownloadTicketState::Failed
+                };
+                state_flag.store(completed_state as u32, Ordering::Release);
+                if let Some(on_completed) = on_completed {
+                    on_completed(completed_state);
+                }
+            });
+
+        TTDownloadTicket {
+            device,
+            buffer,
+            layout,
+            state,
+            pool,
+        }
+    }
+
+    pub fn state(&self) -> TTDownloadTicketState {
+        TTDownloadTicketState::from_u32(self.state.load(Ordering::Acquire))
+    }
+    /// ブロックせずに状態を進める。
+    pub fn poll(&self) -> TTDownloadTicketState {
+        let _ = self.device.poll(wgpu::Maintain::Poll);
+        self.state()
+    }
+    /// 完了するまでブロックする。 DEVICE_WAIT_TIMEOUT を過ぎた場合は DeviceTimeout になる。
+    pub fn wait(&self) -> Result<TTDownloadTicketState, TTCEWgpuError> {
+        poll_until(&self.device, || {
+            self.state() != TTDownloadTicketState::Pending
+        })?;
+        Ok(self.state())
+    }
+
+    /// read に渡すべき長さ、パディングは含まない
+    pub fn data_size(&self) -> u64 {
+        self.layout.data_size()
+    }
+    pub fn read(&self, dist: &mut [u8]) -> Result<(), TTCEWgpuError> {
+        match self.state() {
+            TTDownloadTicketState::Pending => Err(TTCEWgpuError::DownloadNotReady),
+            TTDownloadTicketState::Failed => Err(TTCEWgpuError::MappingFailure),
+            TTDownloadTicketState::Ready => self
+                .layout
+                .copy_to_packed(&self.buffer.slice(..).get_mapped_range(), dist),
+        }
+    }
+}
+impl Drop for TTDownloadTicket {
+    fn drop(&mut self) {
+        // 完了していないものはマップが終わっていないので再利用しない
+        if self.state() != TTDownloadTicketState::Ready {
+            return;
+        }
+        self.buffer.unmap();
+        if let Some(pool) = self.pool.upgrade() {
+            let mut pool = pool.lock().unwrap_or_else(|e| e.into_inner());
+            pool.recycle(self.buffer.clone());
+        }
+    }
+}
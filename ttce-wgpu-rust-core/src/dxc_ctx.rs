@@ -18,11 +18,16 @@ pub struct DirectXCompilerContext {
 impl DirectXCompilerContext {
     pub fn new() -> Result<Self, DirectXCompilerContextError> {
         let lib_path_name = libloading::library_filename("dxcompiler");
-        let current_dir = env::current_dir().unwrap();
+        let Ok(current_dir) = env::current_dir() else {
+            return Err(DirectXCompilerContextError::LibraryNotFound);
+        };
         let Some(dll_path) = find_reclusive(current_dir, lib_path_name.as_os_str()) else {
             return Err(DirectXCompilerContextError::LibraryNotFound);
         };
-
+        Self::from_library_path(dll_path)
+    }
+    /// カレントディレクトリから探さずに、 dll_path の dxcompiler を読み込む。
+    pub fn from_library_path(dll_path: PathBuf) -> Result<Self, DirectXCompilerContextError> {
         let Ok(dxc) = Dxc::new(Some(dll_path)) else {
             return Err(DirectXCompilerContextError::LibraryNotFound);
         };
@@ -55,19 +60,22 @@ impl DirectXCompilerContext {
         target_profile: &str,
         args: &[&str],
         defines: &[(&str, Option<&str>)],
-    ) -> Result<Vec<u8>, DirectXCompilerCompilingError> {
+    ) -> Result<DirectXCompilerOutput, DirectXCompilerCompilingError> {
         let blob = match self.dxc_lib.create_blob_with_encoding_from_str(shader_text) {
             Ok(blob) => blob,
             Err(error) => return Err(DirectXCompilerCompilingError::HassleError(error)),
         };
 
+        let mut include_handler = TTCEDefaultIncludeHandler {
+            included_files: Vec::new(),
+        };
         let result = self.dxc_compiler.compile(
             &blob,
             source_name,
             entry_point,
             target_profile,
             args,
-            Some(&mut TTCEDefaultIncludeHandler {}),
+            Some(&mut include_handler),
             defines,
         );
 
@@ -77,7 +85,10 @@ impl DirectXCompilerContext {
                     Ok(op_result) => op_result,
                     Err(e) => return Err(DirectXCompilerCompilingError::HassleError(e)),
                 };
-                return Ok(op_result.to_vec());
+                return Ok(DirectXCompilerOutput {
+                    spirv: op_result.to_vec(),
+                    included_files: include_handler.included_files,
+                });
             }
             Err((compile_result, _)) => {
                 let compile_error = match compile_result.get_error_buffer() {
@@ -121,6 +132,12 @@ fn find_reclusive(dir: PathBuf, target_file_name: &OsStr) -> Option<PathBuf> {
     return None;
 }
 
+pub struct DirectXCompilerOutput {
+    pub spirv: Vec<u8>,
+    /// #include で読み込まれたファイルのパス
+    pub included_files: Vec<String>,
+}
+
 #[derive(Debug)]
 pub enum DirectXCompilerContextError {
     LibraryNotFound,
@@ -146,12 +163,17 @@ impl Debug for DirectXCompilerContext {
     }
 }
 
-struct TTCEDefaultIncludeHandler {}
+struct TTCEDefaultIncludeHandler {
+    included_files: Vec<String>,
+}
 
 impl DxcIncludeHandler for TTCEDefaultIncludeHandler {
     fn load_source(&mut self, filename: String) -> Option<String> {
-        match fs::read_to_string(filename) {
-            Ok(file_contents) => return Some(file_contents),
+        match fs::read_to_string(&filename) {
+            Ok(file_contents) => {
+                self.included_files.push(filename);
+                return Some(file_contents);
+            }
             Err(_) => None,
         }
     }
@@ -0,0 +1,270 @@
+use std::sync::Arc;
+
+use crate::{
+    compute_shader::AsTypeStr,
+    render_texture::{TTRenderTexture, TTTextureRegion},
+    tex_trans_core_engine::{TTCEWgpuError, TexTransCoreEngineContext, TexTransCoreEngineDevice},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct FillPipelineKey {
+    format: wgpu::TextureFormat,
+    is_3d: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct FillPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+const FILL_WORK_GROUP_SIZE: u32 = 8;
+
+impl TexTransCoreEngineDevice {
+    fn get_or_create_fill_pipeline(&self, key: FillPipelineKey) -> Arc<FillPipeline> {
+        let mut pipelines = self
+            .fill_pipelines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.create_fill_pipeline(key)))
+            .clone()
+    }
+
+    fn create_fill_pipeline(&self, key: FillPipelineKey) -> FillPipeline {
+        let (view_dimension, texture_type, position_expr) = if key.is_3d {
+            (
+                wgpu::TextureViewDimension::D3,
+                "texture_storage_3d",
+                "Params.origin.xyz + param",
+            )
+        } else {
+            (
+                wgpu::TextureViewDimension::D2,
+                "texture_storage_2d",
+                "Params.origin.xy + param.xy",
+            )
+        };
+        let wgsl_str = FILL_TEMPLATE
+            .replace("$$$FORMAT$$$", key.format.as_type_str())
+            .replace("$$$TEXTURE_TYPE$$$", texture_type)
+            .replace("$$$POSITION$$$", position_expr);
+
+        let cs_module = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("fill shader module"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
+            });
+
+        let bind_group_layout =
+            self.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("fill bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Buffer {
+                                ty: wgpu::BufferBindingType::Uniform,
+                                has_dynamic_offset: false,
+                                min_binding_size: None,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::StorageTexture {
+                                access: wgpu::StorageTextureAccess::WriteOnly,
+                                format: key.format,
+                                view_dimension,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("fill pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = self
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("fill compute pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &cs_module,
+                entry_point: Some("CSMain"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: self.pipeline_cache(),
+            });
+
+        FillPipeline {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+}
+
+impl TexTransCoreEngineContext<'_> {
+    /// すべてのミップとレイヤーを color で塗りつぶす。
+    /// color はテクスチャーのフォーマットにそのまま書き込まれ、色空間の変換や存在しないチャンネルの扱いは行わない。
+    pub fn clear_render_texture(
+        &mut self,
+        render_texture: &TTRenderTexture,
+        color: [f32; 4],
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        render_texture
+            .tt_format()
+            .ok_or(TTCEWgpuError::UnsupportedFormat)?;
+
+        if render_texture.dimension() != wgpu::TextureDimension::D3 {
+            let [r, g, b, a] = color.map(|v| v as f64);
+            self.clear_render_texture_2d(render_texture, wgpu::Color { r, g, b, a });
+            return Ok(());
+        }
+
+        // 3D テクスチャーは render attachment にできないため、シェーダーで奥行きごと塗る
+        for mip_level in 0..render_texture.mip_level_count() {
+            let mip_size = render_texture.mip_size(mip_level);
+            self.fill_impl(
+                render_texture,
+                mip_level,
+                [0, 0, 0],
+                [
+                    mip_size.width,
+                    mip_size.height,
+                    mip_size.depth_or_array_layers,
+                ],
+                color,
+            );
+        }
+        Ok(())
+    }
+
+    /// region の範囲のみを color で塗りつぶし、範囲外はそのまま残す。 3D の場合は region.layer が奥行きの位置になる。
+    /// color の扱いは clear_render_texture と同じ。
+    pub fn fill_rect(
+        &mut self,
+        render_texture: &TTRenderTexture,
+        region: TTTextureRegion,
+        color: [f32; 4],
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        render_texture
+            .tt_format()
+            .ok_or(TTCEWgpuError::UnsupportedFormat)?;
+        render_texture.check_region(&region)?;
+
+        self.fill_impl(
+            render_texture,
+            region.mip_level,
+            [region.x, region.y, region.layer],
+            [region.width, region.height, 1],
+            color,
+        );
+        Ok(())
+    }
+
+    fn fill_impl(
+        &mut self,
+        render_texture: &TTRenderTexture,
+        mip_level: u32,
+        origin: [u32; 3],
+        size: [u32; 3],
+        color: [f32; 4],
+    ) {
+        let engine = self.engine;
+        let is_3d = render_texture.dimension() == wgpu::TextureDimension::D3;
+        let fill_pipeline = engine.get_or_create_fill_pipeline(FillPipelineKey {
+            format: render_texture.format(),
+            is_3d,
+        });
+
+        // 2D の場合の origin[2] はレイヤーで、 view の側で選んでおく
+        let view = render_texture.create_view(&wgpu::TextureViewDescriptor {
+            dimension: Some(if is_3d {
+                wgpu::TextureViewDimension::D3
+            } else {
+                wgpu::TextureViewDimension::D2
+            }),
+            base_mip_level: mip_level,
+            mip_level_count: Some(1),
+            base_array_layer: if is_3d { 0 } else { origin[2] },
+            array_layer_count: Some(1),
+            ..Default::default()
+        });
+        let origin_z = if is_3d { origin[2] } else { 0 };
+
+        // WGSL の Params と同じく vec4 を 3 つ並べる
+        let mut params = Vec::with_capacity(48);
+        params.extend(color.iter().flat_map(|v| v.to_le_bytes()));
+        params.extend(
+            [origin[0], origin[1], origin_z, 0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes()),
+        );
+        params.extend(
+            [size[0], size[1], size[2], 0]
+                .iter()
+                .flat_map(|v| v.to_le_bytes()),
+        );
+        // 呼ばれるたびにバッファーを作らないように、コンテキストのチャンクから切り出す
+        let params_slice = self.allocate_constants_buffer(&params);
+
+        let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("fill bind group"),
+            layout: &fill_pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: params_slice.as_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+            ],
+        });
+
+        {
+            let encoder = self.get_command_encoder_as_mut();
+            let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+
+            compute_pass.set_pipeline(&fill_pipeline.pipeline);
+            compute_pass.set_bind_group(0, &bind_group, &[]);
+            compute_pass.dispatch_workgroups(
+                size[0].div_ceil(FILL_WORK_GROUP_SIZE),
+                size[1].div_ceil(FILL_WORK_GROUP_SIZE),
+                size[2],
+            );
+        }
+        self.check_command_stack();
+    }
+}
+
+pub const FILL_TEMPLATE: &str = r#"
+struct FillParams {
+    color: vec4<f32>,
+    origin: vec4<u32>,
+    size: vec4<u32>,
+}
+
+@group(0) @binding(0)
+var<uniform> Params: FillParams;
+@group(0) @binding(1)
+var DistTex: $$$TEXTURE_TYPE$$$<$$$FORMAT$$$,write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
+    if (any(param >= Params.size.xyz)) {
+        return;
+    }
+    textureStore(DistTex, $$$POSITION$$$, Params.color);
+}
+"#;
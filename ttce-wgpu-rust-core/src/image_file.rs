@@ -0,0 +1,349 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use ddsfile::{D3DFormat, Dds, DxgiFormat};
+use image::{DynamicImage, ExtendedColorType, ImageError, ImageFormat, ImageReader};
+
+use crate::{
+    color_space::TTColorSpace,
+    render_texture::TTRenderTexture,
+    tex_trans_core_engine::{
+        RequestFormat, TTCEWgpuError, TTCEWgpuErrorRecord, TTRtDimension, TTRtRequestDescriptor,
+        TexTransCoreEngineContext,
+    },
+    TTChannelMapping, TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+/// save_image_file で書き出すファイルの形式。
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TTImageFileFormat {
+    /// Byte のものは 8bit 、それ以外は 16bit で書き出される
+    Png = 0,
+    /// Float の RGBA で書き出される
+    Exr = 1,
+}
+
+fn image_error(path: &Path, error: ImageError) -> TTCEWgpuErrorRecord {
+    let kind = match error {
+        ImageError::Unsupported(_) => TTCEWgpuError::UnsupportedFormat,
+        _ => TTCEWgpuError::FileIO,
+    };
+    kind.with_message(format!("{} : {}", path.display(), error))
+}
+
+/// 読み込んだ画像を、ビット深度に合うフォーマットとチャンネルに詰めなおしたもの。
+fn normalize_image(
+    image: DynamicImage,
+) -> (
+    DynamicImage,
+    TexTransCoreTextureFormat,
+    TexTransCoreTextureChannel,
+) {
+    use TexTransCoreTextureChannel as C;
+    use TexTransCoreTextureFormat as F;
+    // RGB のフォーマットは無いため、 A を 1 で埋めて RGBA にする
+    match image {
+        DynamicImage::ImageLuma8(_) => (image, F::Byte, C::R),
+        DynamicImage::ImageLumaA8(_) => (image, F::Byte, C::RG),
+        DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => (
+            DynamicImage::ImageRgba8(image.into_rgba8()),
+            F::Byte,
+            C::RGBA,
+        ),
+        DynamicImage::ImageLuma16(_) => (image, F::UShort, C::R),
+        DynamicImage::ImageLumaA16(_) => (image, F::UShort, C::RG),
+        DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) => (
+            DynamicImage::ImageRgba16(image.into_rgba16()),
+            F::UShort,
+            C::RGBA,
+        ),
+        _ => (
+            DynamicImage::ImageRgba32F(image.into_rgba32f()),
+            F::Float,
+            C::RGBA,
+        ),
+    }
+}
+
+/// DDS のピクセルフォーマットと、それをそのまま詰めたときの TTCE のフォーマット。 BGRA のものは RGBA に並び替える必要がある。
+struct DdsPixelLayout {
+    format: TexTransCoreTextureFormat,
+    channel: TexTransCoreTextureChannel,
+    color_space: TTColorSpace,
+    is_bgra: bool,
+}
+
+fn dds_pixel_layout(dds: &Dds) -> Option<DdsPixelLayout> {
+    use TexTransCoreTextureChannel as C;
+    use TexTransCoreTextureFormat as F;
+    let layout = |format, channel| DdsPixelLayout {
+        format,
+        channel,
+        color_space: TTColorSpace::Linear,
+        is_bgra: false,
+    };
+
+    if let Some(dxgi_format) = dds.get_dxgi_format() {
+        return Some(match dxgi_format {
+            DxgiFormat::R8_UNorm => layout(F::Byte, C::R),
+            DxgiFormat::R8G8_UNorm => layout(F::Byte, C::RG),
+            DxgiFormat::R8G8B8A8_UNorm => layout(F::Byte, C::RGBA),
+            DxgiFormat::R8G8B8A8_UNorm_sRGB => DdsPixelLayout {
+                color_space: TTColorSpace::Srgb,
+                ..layout(F::Byte, C::RGBA)
+            },
+            DxgiFormat::B8G8R8A8_UNorm => DdsPixelLayout {
+                is_bgra: true,
+                ..layout(F::Byte, C::RGBA)
+            },
+            DxgiFormat::B8G8R8A8_UNorm_sRGB => DdsPixelLayout {
+                color_space: TTColorSpace::Srgb,
+                is_bgra: true,
+                ..layout(F::Byte, C::RGBA)
+            },
+            DxgiFormat::R16_UNorm => layout(F::UShort, C::R),
+            DxgiFormat::R16G16_UNorm => layout(F::UShort, C::RG),
+            DxgiFormat::R16G16B16A16_UNorm => layout(F::UShort, C::RGBA),
+            DxgiFormat::R16_Float => layout(F::Half, C::R),
+            DxgiFormat::R16G16_Float => layout(F::Half, C::RG),
+            DxgiFormat::R16G16B16A16_Float => layout(F::Half, C::RGBA),
+            DxgiFormat::R32_Float => layout(F::Float, C::R),
+            DxgiFormat::R32G32_Float => layout(F::Float, C::RG),
+            DxgiFormat::R32G32B32A32_Float => layout(F::Float, C::RGBA),
+            _ => return None,
+        });
+    }
+
+    // D3DFormat の名前はビットの並びが上位から書かれているため、メモリ上では逆順になる
+    Some(match dds.get_d3d_format()? {
+        D3DFormat::L8 => layout(F::Byte, C::R),
+        D3DFormat::A8L8 => layout(F::Byte, C::RG),
+        D3DFormat::A8B8G8R8 => layout(F::Byte, C::RGBA),
+        D3DFormat::A8R8G8B8 => DdsPixelLayout {
+            is_bgra: true,
+            ..layout(F::Byte, C::RGBA)
+        },
+        D3DFormat::L16 => layout(F::UShort, C::R),
+        D3DFormat::G16R16 => layout(F::UShort, C::RG),
+        D3DFormat::A16B16G16R16 => layout(F::UShort, C::RGBA),
+        D3DFormat::R16F => layout(F::Half, C::R),
+        D3DFormat::G16R16F => layout(F::Half, C::RG),
+        D3DFormat::A16B16G16R16F => layout(F::Half, C::RGBA),
+        D3DFormat::R32F => layout(F::Float, C::R),
+        D3DFormat::G32R32F => layout(F::Float, C::RG),
+        D3DFormat::A32B32G32R32F => layout(F::Float, C::RGBA),
+        _ => return None,
+    })
+}
+
+impl TexTransCoreEngineContext<'_> {
+    /// PNG, TGA, EXR, DDS のファイルを読み込み、 TTRenderTexture に書き込んだものを返す。
+    /// フォーマットはファイルのビット深度から 8bit は Byte 、 16bit は UShort 、浮動小数点は Float (DDS は Half も) が選ばれ、 RGB のものは A を 1 とした RGBA になる。
+    /// 色空間の印は EXR はリニア、 PNG と TGA は sRGB 、 DDS はフォーマットが _sRGB のもののみ sRGB になる。
+    /// DDS はミップと 2D 配列をそのまま読み込むが、非圧縮のフォーマットのみに対応している。
+    pub fn load_image_file(&mut self, path: &Path) -> Result<TTRenderTexture, TTCEWgpuErrorRecord> {
+        self.check_device_lost()?;
+        let is_dds = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("dds"));
+        if is_dds {
+            return self.load_dds_file(path);
+        }
+
+        let reader = ImageReader::open(path)
+            .and_then(|r| r.with_guessed_format())
+            .map_err(|e| {
+                TTCEWgpuError::FileIO.with_message(format!("{} : {}", path.display(), e))
+            })?;
+        let color_space = match reader.format() {
+            Some(ImageFormat::OpenExr) => TTColorSpace::Linear,
+            _ => TTColorSpace::Srgb,
+        };
+        let image = reader.decode().map_err(|e| image_error(path, e))?;
+        let (image, format, channel) = normalize_image(image);
+
+        let desc = TTRtRequestDescriptor {
+            width: image.width(),
+            height: image.height(),
+            format: RequestFormat::Manual(format, channel),
+            mip_level_count: 1,
+            dimension: TTRtDimension::D2,
+            depth_or_array_layers: 1,
+        };
+        desc.validate(&self.engine.device.limits())?;
+
+        let mut render_texture = self.get_render_texture_with(&desc);
+        self.upload_texture(&render_texture, image.as_bytes(), format)?;
+        render_texture.set_color_space(color_space);
+        Ok(render_texture)
+    }
+
+    fn load_dds_file(&mut self, path: &Path) -> Result<TTRenderTexture, TTCEWgpuErrorRecord> {
+        let file_io_error = |e: &dyn std::fmt::Display| {
+            TTCEWgpuError::FileIO.with_message(format!("{} : {}", path.display(), e))
+        };
+        let file = File::open(path).map_err(|e| file_io_error(&e))?;
+        let dds = Dds::read(BufReader::new(file)).map_err(|e| file_io_error(&e))?;
+
+        let layout = dds_pixel_layout(&dds).ok_or_else(|| {
+            TTCEWgpuError::UnsupportedFormat.with_message(format!(
+                "{} : unsupported dds format {:?} {:?}",
+                path.display(),
+                dds.get_dxgi_format(),
+                dds.get_d3d_format()
+            ))
+        })?;
+        if dds.get_depth() > 1 {
+            return Err(TTCEWgpuError::TextureDimensionMismatch
+                .with_message(format!("{} : volume dds is not supported", path.display())));
+        }
+
+        let desc = TTRtRequestDescriptor {
+            width: dds.get_width(),
+            height: dds.get_height(),
+            format: RequestFormat::Manual(layout.format, layout.channel),
+            mip_level_count: dds.get_num_mipmap_levels().max(1),
+            dimension: TTRtDimension::D2,
+            depth_or_array_layers: dds.get_num_array_layers().max(1),
+        };
+        desc.validate(&self.engine.device.limits())?;
+
+        let mut render_texture = self.get_render_texture_with(&desc);
+        let pixel_par_byte = TTRenderTexture::to_wgpu_texture_format(layout.format, layout.channel)
+            .block_copy_size(None)
+            .ok_or(TTCEWgpuError::UnsupportedFormat)? as usize;
+
+        for layer in 0..desc.depth_or_array_layers {
+            // 一つのレイヤーのデータには、すべてのミップが大きい順に詰まっている
+            let mut layer_data = dds.get_data(layer).map_err(|e| file_io_error(&e))?;
+            for mip_level in 0..desc.mip_level_count {
+                let mip_size = render_texture.mip_size(mip_level);
+                let mip_data_size =
+                    mip_size.width as usize * mip_size.height as usize * pixel_par_byte;
+                if layer_data.len() < mip_data_size {
+                    return Err(TTCEWgpuError::SizeMismatch
+                        .with_message(format!("{} : dds data is too short", path.display())));
+                }
+                let (mip_data, rest) = layer_data.split_at(mip_data_size);
+                layer_data = rest;
+
+                if layout.is_bgra {
+                    let mut rgba = mip_data.to_vec();
+                    rgba.chunks_exact_mut(4).for_each(|p| p.swap(0, 2));
+                    self.upload_texture_layer(
+                        &render_texture,
+                        mip_level,
+                        layer,
+                        &rgba,
+                        layout.format,
+                    )?;
+                } else {
+                    self.upload_texture_layer(
+                        &render_texture,
+                        mip_level,
+                        layer,
+                        mip_data,
+                        layout.format,
+                    )?;
+                }
+            }
+        }
+
+        render_texture.set_color_space(layout.color_space);
+        Ok(render_texture)
+    }
+
+    /// render_texture の mip 0 を file_format で書き出す。配列でない 2D のもののみ書き出せる。
+    /// 色空間の変換は行わないため、 PNG に sRGB で書き出したい場合は先に convert_color_space で変換しておくように。
+    /// EXR は R や RG のものも RGBA に広げて書き出す。
+    /// PNG の RG は LA として書くと G がアルファになってしまうため、 B を 0 、 A を 1 で埋めた RGBA に広げて書き出す。
+    pub async fn save_image_file(
+        &mut self,
+        render_texture: &TTRenderTexture,
+        path: &Path,
+        file_format: TTImageFileFormat,
+    ) -> Result<(), TTCEWgpuErrorRecord> {
+        self.check_device_lost()?;
+        let (format, channel) = render_texture
+            .tt_format()
+            .ok_or(TTCEWgpuError::UnsupportedFormat)?;
+        if !render_texture.is_single_layer_2d() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch.into());
+        }
+        let width = render_texture.width();
+        let height = render_texture.height();
+
+        let (data, color_type, image_format) = match file_format {
+            TTImageFileFormat::Png => {
+                let download_format = match format {
+                    TexTransCoreTextureFormat::Byte => TexTransCoreTextureFormat::Byte,
+                    _ => TexTransCoreTextureFormat::UShort,
+                };
+                // Default のマッピングで RG から広げると B は 0 、 A は 1 になる
+                let expanded = if channel == TexTransCoreTextureChannel::RG {
+                    let desc = TTRtRequestDescriptor {
+                        width,
+                        height,
+                        format: RequestFormat::Manual(format, TexTransCoreTextureChannel::RGBA),
+                        mip_level_count: 1,
+                        dimension: TTRtDimension::D2,
+                        depth_or_array_layers: 1,
+                    };
+                    let rgba = self.get_temporary_render_texture(&desc);
+                    self.convert_texture(&rgba, render_texture, TTChannelMapping::Default)?;
+                    Some(rgba)
+                } else {
+                    None
+                };
+                // RG は RGBA に広げているため、 R 以外はすべて RGBA になる
+                let color_type = match (download_format, channel) {
+                    (TexTransCoreTextureFormat::Byte, TexTransCoreTextureChannel::R) => {
+                        ExtendedColorType::L8
+                    }
+                    (TexTransCoreTextureFormat::Byte, _) => ExtendedColorType::Rgba8,
+                    (_, TexTransCoreTextureChannel::R) => ExtendedColorType::L16,
+                    (_, _) => ExtendedColorType::Rgba16,
+                };
+                let mut data =
+                    vec![0; (width * height) as usize * color_type.bits_per_pixel() as usize / 8];
+                self.download_texture(
+                    expanded.as_ref().unwrap_or(render_texture),
+                    Some(download_format),
+                    &mut data,
+                )
+                .await?;
+                (data, color_type, ImageFormat::Png)
+            }
+            TTImageFileFormat::Exr => {
+                let mut data = vec![0; (width * height) as usize * 16];
+                let desc = TTRtRequestDescriptor {
+                    width,
+                    height,
+                    format: RequestFormat::Manual(
+                        TexTransCoreTextureFormat::Float,
+                        TexTransCoreTextureChannel::RGBA,
+                    ),
+                    mip_level_count: 1,
+                    dimension: TTRtDimension::D2,
+                    depth_or_array_layers: 1,
+                };
+                if channel == TexTransCoreTextureChannel::RGBA {
+                    self.download_texture(
+                        render_texture,
+                        Some(TexTransCoreTextureFormat::Float),
+                        &mut data,
+                    )
+                    .await?;
+                } else {
+                    let rgba = self.get_temporary_render_texture(&desc);
+                    self.convert_texture(&rgba, render_texture, TTChannelMapping::Default)?;
+                    self.download_texture(&rgba, None, &mut data).await?;
+                }
+                (data, ExtendedColorType::Rgba32F, ImageFormat::OpenExr)
+            }
+        };
+
+        image::save_buffer_with_format(path, &data, width, height, color_type, image_format)
+            .map_err(|e| image_error(path, e))
+    }
+}
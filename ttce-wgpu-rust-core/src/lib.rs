@@ -1,32 +1,67 @@
-mod compute_shader;
-mod dxc_ctx;
-mod render_texture;
-mod storage_buffer;
-mod tex_trans_core_engine;
-
-use std::{ffi::c_void, ops::Deref, sync::Mutex};
-
-use compute_shader::{TTComputeHandler, TTComputeShaderID};
+pub mod adapter;
+pub mod blit;
+pub mod block_compression;
+pub mod block_decompression;
+pub mod color_space;
+pub mod compute_shader;
+pub mod constants_buffer;
+pub mod download_ticket;
+pub mod dxc_ctx;
+pub mod fill;
+pub mod image_file;
+pub mod mipmap;
+pub mod pipeline_cache;
+pub mod render_texture;
+pub mod render_texture_pool;
+pub mod sampler;
+pub mod shader_cache;
+pub mod storage_buffer;
+pub mod tex_trans_core_engine;
+
+use std::{
+    any::Any,
+    cell::RefCell,
+    ffi::c_void,
+    ops::Deref,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::Mutex,
+};
+
+use blit::TTBlitFilter;
+use block_compression::{TTBlockCompressionFormat, TTBlockCompressionQuality};
+use block_decompression::TTBlockDecompressionPath;
+use color_space::TTColorSpace;
+use compute_shader::{TTBindGroupStatistics, TTBindingPoint, TTComputeHandler, TTComputeShaderID};
+use constants_buffer::TTConstantsBufferStatistics;
+use download_ticket::{DownloadCompletedCallback, TTDownloadTicket, TTDownloadTicketState};
 use dxc_ctx::DirectXCompilerContext;
+use image_file::TTImageFileFormat;
+use mipmap::MipmapFilter;
 use once_cell::sync::OnceCell;
-use render_texture::TTRenderTexture;
+use render_texture::{TTRenderTexture, TTTextureRegion};
+use render_texture_pool::RenderTexturePoolStatistics;
+use sampler::{TTSampler, TTSamplerDescriptor};
+use shader_cache::ShaderCache;
 use storage_buffer::TTStorageBuffer;
-use tex_trans_core_engine::{TexTransCoreEngineContext, TexTransCoreEngineDevice};
-use wgpu::{Backends, DeviceType};
+use tex_trans_core_engine::{
+    RequestFormat, TTCEWgpuError, TTCEWgpuErrorRecord, TTRtDimension, TTRtRequestDescriptor,
+    TexTransCoreEngineContext, TexTransCoreEngineDevice,
+};
+use wgpu::DeviceType;
 
 static DEBUG_LOG: Mutex<Option<unsafe extern "C" fn(*const u16, i32) -> ()>> = Mutex::new(None);
 #[no_mangle]
 pub extern "C" fn set_debug_log_pointer(
     debug_log_fn_ptr: unsafe extern "C" fn(*const u16, i32) -> (),
 ) {
-    *DEBUG_LOG.lock().unwrap() = if debug_log_fn_ptr as usize == 0 {
+    *DEBUG_LOG.lock().unwrap_or_else(|e| e.into_inner()) = if debug_log_fn_ptr as usize == 0 {
         None
     } else {
         Some(debug_log_fn_ptr)
     };
 }
 pub fn debug_log(str: &str) {
-    let ptr = DEBUG_LOG.lock().unwrap();
+    let ptr = DEBUG_LOG.lock().unwrap_or_else(|e| e.into_inner());
     let Some(fn_ptr) = *ptr else {
         return;
     };
@@ -47,125 +82,428 @@ fn get_tokio_runtime() -> &'static tokio::runtime::Runtime {
     TOKIO_RUNTIME.get_or_init(create_tokio_runtime)
 }
 
+// Error
+
+/// extern "C" な関数すべての戻り値。 Ok 以外の場合は get_last_error_kind と get_last_error_message で詳細を得られる。
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum TTCEWgpuStatus {
+    Ok = 0,
+    Error = 1,
+    Panic = 2,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<TTCEWgpuErrorRecord>> = const { RefCell::new(None) };
+}
+fn set_last_error(error: TTCEWgpuErrorRecord) {
+    debug_log(error.to_string().as_str());
+    LAST_ERROR.with_borrow_mut(|e| *e = Some(error));
+}
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(str) = payload.downcast_ref::<&str>() {
+        str.to_string()
+    } else if let Some(string) = payload.downcast_ref::<String>() {
+        string.clone()
+    } else {
+        String::from("unknown panic payload")
+    }
+}
+
+/// FFI の境界。 panic を C# 側に伝搬させず、エラーは last error に記録して TTCEWgpuStatus に変換する。
+fn ffi_boundary(f: impl FnOnce() -> Result<(), TTCEWgpuErrorRecord>) -> TTCEWgpuStatus {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => TTCEWgpuStatus::Ok,
+        Ok(Err(error)) => {
+            set_last_error(error);
+            TTCEWgpuStatus::Error
+        }
+        Err(payload) => {
+            set_last_error(TTCEWgpuError::Panic.with_message(panic_message(payload.as_ref())));
+            TTCEWgpuStatus::Panic
+        }
+    }
+}
+
+unsafe fn ref_from_ptr<'a, T>(ptr: *const c_void) -> Result<&'a T, TTCEWgpuError> {
+    (ptr as *const T).as_ref().ok_or(TTCEWgpuError::NullHandle)
+}
+unsafe fn mut_from_ptr<'a, T>(ptr: *mut c_void) -> Result<&'a mut T, TTCEWgpuError> {
+    (ptr as *mut T).as_mut().ok_or(TTCEWgpuError::NullHandle)
+}
+unsafe fn drop_from_ptr<T>(ptr: *mut c_void) -> Result<(), TTCEWgpuError> {
+    if ptr.is_null() {
+        return Err(TTCEWgpuError::NullHandle);
+    }
+    let _ = Box::from_raw(ptr as *mut T);
+    Ok(())
+}
+unsafe fn write_to_ptr<T>(ptr: *mut T, value: T) -> Result<(), TTCEWgpuError> {
+    if ptr.is_null() {
+        return Err(TTCEWgpuError::InvalidArgument);
+    }
+    ptr.write(value);
+    Ok(())
+}
+unsafe fn write_handle_to_ptr<T>(ptr: *mut *mut c_void, value: T) -> Result<(), TTCEWgpuError> {
+    if ptr.is_null() {
+        return Err(TTCEWgpuError::InvalidArgument);
+    }
+    ptr.write(Box::into_raw(Box::new(value)) as *mut c_void);
+    Ok(())
+}
+unsafe fn slice_from_ptr<'a, T>(ptr: *const T, len: i32) -> Result<&'a [T], TTCEWgpuError> {
+    if ptr.is_null() || len < 0 {
+        return Err(TTCEWgpuError::InvalidArgument);
+    }
+    Ok(std::slice::from_raw_parts(ptr, len as usize))
+}
+unsafe fn slice_from_ptr_mut<'a, T>(ptr: *mut T, len: i32) -> Result<&'a mut [T], TTCEWgpuError> {
+    if ptr.is_null() || len < 0 {
+        return Err(TTCEWgpuError::InvalidArgument);
+    }
+    Ok(std::slice::from_raw_parts_mut(ptr, len as usize))
+}
+unsafe fn string_from_utf16(ptr: *const u16, len: i32) -> Result<String, TTCEWgpuError> {
+    String::from_utf16(slice_from_ptr(ptr, len)?).map_err(|_| TTCEWgpuError::InvalidUtf16)
+}
+
+/// # Safety
+/// out_kind は TTCEWgpuError を書き込めるポインターでないといけない。
+/// このスレッドで最後に起きたエラーの種類を得る。エラーが記録されていない場合は false が返る。
+#[no_mangle]
+pub unsafe extern "C" fn get_last_error_kind(out_kind: *mut TTCEWgpuError) -> bool {
+    LAST_ERROR.with_borrow(|e| match (e, out_kind.is_null()) {
+        (Some(error), false) => {
+            out_kind.write(error.kind);
+            true
+        }
+        _ => false,
+    })
+}
+
+/// # Safety
+/// buffer は buffer_len 分の u16 を書き込める配列の先頭のポインターか、 null pointer でないといけない。
+/// このスレッドで最後に起きたエラーのメッセージを UTF16 (C# string) で書き込み、メッセージ全体の長さを返す。
+/// 戻り値が buffer_len より大きい場合は切り詰められているので、長さを確認してから再度呼ぶように。
+#[no_mangle]
+pub unsafe extern "C" fn get_last_error_message(buffer: *mut u16, buffer_len: i32) -> i32 {
+    LAST_ERROR.with_borrow(|e| {
+        let Some(error) = e else {
+            return 0;
+        };
+        let utf_16_str: Vec<_> = error.message.encode_utf16().collect();
+
+        if !buffer.is_null() && buffer_len > 0 {
+            let write_len = utf_16_str.len().min(buffer_len as usize);
+            std::slice::from_raw_parts_mut(buffer, write_len)
+                .copy_from_slice(&utf_16_str[..write_len]);
+        }
+        utf_16_str.len() as i32
+    })
+}
+
+/// このスレッドで記録されているエラーを消去する。
+#[no_mangle]
+pub extern "C" fn clear_last_error() {
+    LAST_ERROR.with_borrow_mut(|e| *e = None);
+}
+
 // TexTransCoreEngine
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(u32)]
 pub enum RequestDevicePreference {
     Auto,
     DiscreteGPU,
     IntegratedGPUOrCPU,
+    /// lavapipe や llvmpipe などの CPU で動作するアダプターのみを要求する。見つからない場合は GPU にフォールバックせずに失敗する。
+    SoftwareFallback,
 }
 
+// dxcompiler が無くても HLSL 以外は使えるため、デバイスの生成は失敗させずに HLSL の登録で CompileError にする
+// パイプラインキャッシュはシェーダーの登録より前に読み込まないといけないため、生成と同時に読み込む
+unsafe fn create_engine_device(
+    (device, queue): (wgpu::Device, wgpu::Queue),
+    pipeline_cache_path: *const u16,
+    pipeline_cache_path_str_len: i32,
+) -> Result<TexTransCoreEngineDevice, TTCEWgpuErrorRecord> {
+    let pipeline_cache_path = if pipeline_cache_path.is_null() {
+        None
+    } else {
+        Some(string_from_utf16(
+            pipeline_cache_path,
+            pipeline_cache_path_str_len,
+        )?)
+    };
+    let dxc_ctx = DirectXCompilerContext::new()
+        .inspect_err(|e| debug_log(&format!("DirectCompilerContext creation failed : {:?}", e)))
+        .ok();
+
+    let mut engine = TexTransCoreEngineDevice::new(device, queue, dxc_ctx);
+    if let Some(path) = pipeline_cache_path {
+        engine.load_pipeline_cache_from_file(std::path::Path::new(&path))?;
+    }
+    Ok(engine)
+}
+
+/// # Safety
+/// out_tex_trans_core_engine_ptr はポインターを書き込める場所のポインターでないといけない。
+/// pipeline_cache_path は save_pipeline_cache で保存したファイルか、存在しないファイルのパスを UTF16 (C# string) で渡すように。
+/// null pointer の場合はパイプラインキャッシュを使用しない。バックエンドが対応していない場合も使用されない。
 /// TexTransCoreEngineDevice を生成し、ポインターを得ることができる。
 #[no_mangle]
-pub extern "C" fn create_tex_trans_core_engine_device(
+pub unsafe extern "C" fn create_tex_trans_core_engine_device(
     preference: RequestDevicePreference,
-) -> *mut c_void {
-    let (device, queue) = get_tokio_runtime()
-        .block_on(async move {
-            let instance = wgpu::Instance::default();
-            let adapter = match preference {
-                RequestDevicePreference::Auto => instance
-                    .enumerate_adapters(Backends::DX12 | Backends::VULKAN | Backends::METAL)// OpenGL 系を排除する
-                    .into_iter()
-                    .find(|_| true),// 何かもっといい手段ないのか？
-                // Some(
-                //     instance
-                //         .request_adapter(&wgpu::RequestAdapterOptions::default())
-                //         .await
-                //         .expect("adapter request failed when ttce device creation"),
-                // ),
-                RequestDevicePreference::IntegratedGPUOrCPU => instance
-                    .enumerate_adapters(Backends::all())
-                    .into_iter()
-                    .find(|a| {
-                        let device_type = a.get_info().device_type;
-                        device_type == DeviceType::IntegratedGpu || device_type == DeviceType::Cpu
-                    }),
-                RequestDevicePreference::DiscreteGPU => instance
-                    .enumerate_adapters(Backends::all())
-                    .into_iter()
-                    .find(|a| {
-                        let device_type = a.get_info().device_type;
-                        device_type == DeviceType::DiscreteGpu
-                    }),
-            };
-            let adapter = if let Some(adapter) = adapter {
-                adapter
-            } else {
-                let request_adapter_option = wgpu::RequestAdapterOptions::default();
-                instance
-                    .request_adapter(&request_adapter_option)
-                    .await
-                    .expect("fallback adapter request failed when ttce device creation")
-            };
-
-            debug_log(&format!("Adapter : \n{:?}", adapter.get_info()));
-
-            let device_feature = wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
-                    | wgpu::Features::TEXTURE_FORMAT_16BIT_NORM,
-                required_limits: wgpu::Limits {
-                    max_storage_textures_per_shader_stage: 8,
-                    max_bind_groups: 1,
-                    ..Default::default()
-                },
-                ..Default::default()
-            };
-
-            adapter.request_device(&device_feature, None).await
-        })
-        .unwrap();
-
-    let dxc_ctx = DirectXCompilerContext::new().expect("DirectCompilerContext creation failed");
-
-    let ttce = tex_trans_core_engine::TexTransCoreEngineDevice::new(device, queue, dxc_ctx);
-
-    Box::into_raw(Box::new(ttce)) as *mut c_void
+    pipeline_cache_path: *const u16,
+    pipeline_cache_path_str_len: i32,
+    out_tex_trans_core_engine_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let ttce = create_engine_device(
+            adapter::request_device_with_preference(preference)?,
+            pipeline_cache_path,
+            pipeline_cache_path_str_len,
+        )?;
+
+        write_handle_to_ptr(out_tex_trans_core_engine_ptr, ttce)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// out_tex_trans_core_engine_ptr はポインターを書き込める場所のポインターでないといけない。
+/// adapter_index は get_adapter_count と get_adapter_info で確認した index を使うように。
+/// pipeline_cache_path は create_tex_trans_core_engine_device と同じ。
+/// 指定したアダプターから TexTransCoreEngineDevice を生成し、ポインターを得ることができる。
+#[no_mangle]
+pub unsafe extern "C" fn create_device_from_adapter_index(
+    adapter_index: u32,
+    pipeline_cache_path: *const u16,
+    pipeline_cache_path_str_len: i32,
+    out_tex_trans_core_engine_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let adapter = adapter::get_adapter(adapter_index)?;
+        let ttce = create_engine_device(
+            adapter::request_device_from_adapter(&adapter)?,
+            pipeline_cache_path,
+            pipeline_cache_path_str_len,
+        )?;
+
+        write_handle_to_ptr(out_tex_trans_core_engine_ptr, ttce)?;
+        Ok(())
+    })
+}
+
+// Adapter
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum AdapterDeviceType {
+    Other,
+    IntegratedGpu,
+    DiscreteGpu,
+    VirtualGpu,
+    Cpu,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum AdapterBackend {
+    Empty,
+    Vulkan,
+    Metal,
+    Dx12,
+    Gl,
+    BrowserWebGpu,
+}
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u32)]
+pub enum AdapterInfoString {
+    Name,
+    Driver,
+    DriverInfo,
+}
+#[repr(C)]
+pub struct AdapterInfo {
+    pub vendor: u32,
+    pub device: u32,
+    pub device_type: AdapterDeviceType,
+    pub backend: AdapterBackend,
+    /// TexTransCoreEngineDevice が要求する機能と制限を満たしているか。
+    pub is_supported: bool,
+    pub features: u64,
+
+    pub max_texture_dimension_2d: u32,
+    pub max_storage_textures_per_shader_stage: u32,
+    pub max_bind_groups: u32,
+    pub max_storage_buffer_binding_size: u32,
+    pub max_buffer_size: u64,
+    pub max_compute_workgroup_size_x: u32,
+    pub max_compute_workgroup_size_y: u32,
+    pub max_compute_workgroup_size_z: u32,
+    pub max_compute_invocations_per_workgroup: u32,
+    pub max_compute_workgroups_per_dimension: u32,
+}
+impl AdapterInfo {
+    fn from(adapter: &wgpu::Adapter) -> Self {
+        let info = adapter.get_info();
+        let limits = adapter.limits();
+        AdapterInfo {
+            vendor: info.vendor,
+            device: info.device,
+            device_type: match info.device_type {
+                DeviceType::Other => AdapterDeviceType::Other,
+                DeviceType::IntegratedGpu => AdapterDeviceType::IntegratedGpu,
+                DeviceType::DiscreteGpu => AdapterDeviceType::DiscreteGpu,
+                DeviceType::VirtualGpu => AdapterDeviceType::VirtualGpu,
+                DeviceType::Cpu => AdapterDeviceType::Cpu,
+            },
+            backend: match info.backend {
+                wgpu::Backend::Empty => AdapterBackend::Empty,
+                wgpu::Backend::Vulkan => AdapterBackend::Vulkan,
+                wgpu::Backend::Metal => AdapterBackend::Metal,
+                wgpu::Backend::Dx12 => AdapterBackend::Dx12,
+                wgpu::Backend::Gl => AdapterBackend::Gl,
+                wgpu::Backend::BrowserWebGpu => AdapterBackend::BrowserWebGpu,
+            },
+            is_supported: adapter::is_supported_adapter(adapter),
+            features: adapter.features().bits(),
+
+            max_texture_dimension_2d: limits.max_texture_dimension_2d,
+            max_storage_textures_per_shader_stage: limits.max_storage_textures_per_shader_stage,
+            max_bind_groups: limits.max_bind_groups,
+            max_storage_buffer_binding_size: limits.max_storage_buffer_binding_size,
+            max_buffer_size: limits.max_buffer_size,
+            max_compute_workgroup_size_x: limits.max_compute_workgroup_size_x,
+            max_compute_workgroup_size_y: limits.max_compute_workgroup_size_y,
+            max_compute_workgroup_size_z: limits.max_compute_workgroup_size_z,
+            max_compute_invocations_per_workgroup: limits.max_compute_invocations_per_workgroup,
+            max_compute_workgroups_per_dimension: limits.max_compute_workgroups_per_dimension,
+        }
+    }
+}
+
+/// # Safety
+/// out_adapter_count は u32 を書き込めるポインターでないといけない。
+/// 使用可能なアダプターの数を得る。 index の順番は同じ環境であれば毎回同じになる。
+#[no_mangle]
+pub unsafe extern "C" fn get_adapter_count(out_adapter_count: *mut u32) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        write_to_ptr(
+            out_adapter_count,
+            adapter::enumerate_adapters().len() as u32,
+        )?;
+        Ok(())
+    })
 }
 
+/// # Safety
+/// out_adapter_info は AdapterInfo を書き込めるポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn get_adapter_info(
+    adapter_index: u32,
+    out_adapter_info: *mut AdapterInfo,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let adapter = adapter::get_adapter(adapter_index)?;
+
+        write_to_ptr(out_adapter_info, AdapterInfo::from(&adapter))?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// buffer は buffer_len 分の u16 を書き込める配列の先頭のポインターか、 null pointer でないといけない。
+/// アダプターの名前やドライバーの情報を UTF16 (C# string) で書き込み、文字列全体の長さを out_str_len に書き込む。
+/// out_str_len が buffer_len より大きい場合は切り詰められているので、長さを確認してから再度呼ぶように。
+#[no_mangle]
+pub unsafe extern "C" fn get_adapter_info_string(
+    adapter_index: u32,
+    info_string: AdapterInfoString,
+    buffer: *mut u16,
+    buffer_len: i32,
+    out_str_len: *mut i32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let info = adapter::get_adapter(adapter_index)?.get_info();
+        let str = match info_string {
+            AdapterInfoString::Name => info.name,
+            AdapterInfoString::Driver => info.driver,
+            AdapterInfoString::DriverInfo => info.driver_info,
+        };
+        let utf_16_str: Vec<_> = str.encode_utf16().collect();
+
+        if !buffer.is_null() && buffer_len > 0 {
+            let write_len = utf_16_str.len().min(buffer_len as usize);
+            slice_from_ptr_mut(buffer, write_len as i32)?.copy_from_slice(&utf_16_str[..write_len]);
+        }
+        write_to_ptr(out_str_len, utf_16_str.len() as i32)?;
+        Ok(())
+    })
+}
+
+/// # Safety
 /// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
 /// TexTransCoreEngineDevice のポインターを受け取り、そのデバイスの内部で使われるデフォルトのフォーマットを指定する。
 /// 初期化時に行うようか、 TexTransCoreEngineContext が一つもぶら下がっていないときに行うように。
 #[no_mangle]
-pub extern "C" fn set_default_texture_format(
+pub unsafe extern "C" fn set_default_texture_format(
     tex_trans_core_engine_ptr: *mut c_void,
     format: TexTransCoreTextureFormat,
-) {
-    let engine = unsafe {
-        (tex_trans_core_engine_ptr as *mut TexTransCoreEngineDevice)
-            .as_mut()
-            .unwrap()
-    };
-    engine.set_default_texture_format(format);
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = mut_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        engine.set_default_texture_format(format);
+        Ok(())
+    })
 }
 
 /// # Safety
 /// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
 /// Context や TTRenderTexture などぶら下がってる物をすべてドロップしてから呼ぶように。
 #[no_mangle]
-pub unsafe extern "C" fn drop_tex_trans_core_engine_device(tex_trans_core_engine_ptr: *mut c_void) {
-    let _ = Box::from_raw(tex_trans_core_engine_ptr as *mut TexTransCoreEngineDevice);
+pub unsafe extern "C" fn drop_tex_trans_core_engine_device(
+    tex_trans_core_engine_ptr: *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        drop_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        Ok(())
+    })
 }
 
+/// # Safety
 /// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
-/// TexTransCoreEngineDevice に内部的に使用するフォーマットコンバータを生成させる。
+/// TexTransCoreEngineDevice に内部的に使用するフォーマットコンバータを登録させる。パイプラインは初めて使われるときに生成される。
 /// set_default_texture_format と同様、処理を始める前やしていないタイミングで行うように。
 #[no_mangle]
-pub extern "C" fn register_format_convertor(tex_trans_core_engine_ptr: *mut c_void) {
-    let engine = unsafe {
-        (tex_trans_core_engine_ptr as *mut TexTransCoreEngineDevice)
-            .as_mut()
-            .unwrap()
-    };
-    engine.register_format_convertor();
+pub unsafe extern "C" fn register_format_convertor(
+    tex_trans_core_engine_ptr: *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = mut_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        engine.register_format_convertor();
+        Ok(())
+    })
 }
 
-// retune of tt_compute_shader_id
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+/// TexTransCoreEngineDevice にブロック圧縮のエンコーダーを登録させる。パイプラインは初めて使われるときに生成される。
+/// register_format_convertor の後に行うように。
+#[no_mangle]
+pub unsafe extern "C" fn register_block_compressor(
+    tex_trans_core_engine_ptr: *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = mut_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        engine.register_block_compressor();
+        Ok(())
+    })
+}
 
 /// # Safety
 /// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
 /// 任意の HLSL を UTF16 (C# string) をコンピュートシェーダーとして登録させることができ、hlsl_path_source は null pointer でもよい。
-/// 戻り値の値は result が true の時しか使用してはならない。 false の場合は何らかの理由で失敗している。ログに出力されたものを見るように。
+/// out_compute_shader_id に書き込まれる値は Ok が返った時しか使用してはならない。
 #[no_mangle]
 pub unsafe extern "C" fn register_compute_shader_from_hlsl(
     tex_trans_core_engine_ptr: *mut c_void,
@@ -173,48 +511,167 @@ pub unsafe extern "C" fn register_compute_shader_from_hlsl(
     hlsl_path_str_len: i32,
     hlsl_path_source: *const u16,
     hlsl_path_source_str_len: i32,
-) -> RegisterCSResult {
-    let engine = (tex_trans_core_engine_ptr as *mut TexTransCoreEngineDevice)
-        .as_mut()
-        .unwrap();
-
-    let hlsl_path_rust_string = String::from_utf16(std::slice::from_raw_parts(
-        hlsl_path,
-        hlsl_path_str_len as usize,
-    ))
-    .unwrap();
-
-    let source_slice_rust_string_opt = (!hlsl_path_source.is_null()).then(|| {
-        String::from_utf16(std::slice::from_raw_parts(
-            hlsl_path_source,
-            hlsl_path_source_str_len as usize,
-        ))
-        .unwrap()
-    });
-
-    let try_id = engine.register_compute_shader_from_hlsl(
-        hlsl_path_rust_string.as_str(),
-        source_slice_rust_string_opt.as_deref(),
-    );
-
-    match try_id {
-        Ok(id) => RegisterCSResult {
-            result: true,
-            compute_shader_id: *id.deref(),
-        },
-        Err(err) => {
-            debug_log(err.to_string().as_str());
-            RegisterCSResult {
-                result: false,
-                compute_shader_id: 0,
-            }
+    out_compute_shader_id: *mut u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = mut_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+
+        let hlsl_path_rust_string = string_from_utf16(hlsl_path, hlsl_path_str_len)?;
+
+        let source_slice_rust_string_opt = if hlsl_path_source.is_null() {
+            None
+        } else {
+            Some(string_from_utf16(
+                hlsl_path_source,
+                hlsl_path_source_str_len,
+            )?)
+        };
+
+        let id = engine.register_compute_shader_from_hlsl(
+            hlsl_path_rust_string.as_str(),
+            source_slice_rust_string_opt.as_deref(),
+        )?;
+
+        write_to_ptr(out_compute_shader_id, *id.deref())?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+/// HLSL のコンパイル結果 (SPIR-V) を保存するディスクキャッシュのディレクトリを UTF16 (C# string) で指定する。 cache_directory を null pointer にするとキャッシュを使用しなくなる。
+/// max_size_byte はキャッシュの合計サイズの上限で、超えた場合は古く使われていない物から削除される。
+/// register_compute_shader_from_hlsl の前に行うように。
+#[no_mangle]
+pub unsafe extern "C" fn set_shader_cache_directory(
+    tex_trans_core_engine_ptr: *mut c_void,
+    cache_directory: *const u16,
+    cache_directory_str_len: i32,
+    max_size_byte: u64,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = mut_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+
+        if cache_directory.is_null() {
+            engine.set_shader_cache(None);
+            return Ok(());
         }
-    }
+        let directory = string_from_utf16(cache_directory, cache_directory_str_len)?;
+        let shader_cache = ShaderCache::new(directory.as_str(), max_size_byte)
+            .map_err(|e| TTCEWgpuError::FileIO.with_message(format!("{} : {}", directory, e)))?;
+
+        engine.set_shader_cache(Some(shader_cache));
+        Ok(())
+    })
 }
-#[repr(C)]
-pub struct RegisterCSResult {
-    result: bool,
-    compute_shader_id: u32,
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+/// set_shader_cache_directory で指定したディレクトリのキャッシュをすべて削除する。
+#[no_mangle]
+pub unsafe extern "C" fn clear_shader_cache(
+    tex_trans_core_engine_ptr: *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+
+        if let Some(shader_cache) = engine.shader_cache() {
+            shader_cache
+                .clear()
+                .map_err(|e| TTCEWgpuError::FileIO.with_message(e.to_string()))?;
+        }
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+/// ブロックせずに GPU の状態を進め、完了した TTDownloadTicket の callback を呼ぶ。 callback を使う場合は定期的に呼ぶように。
+#[no_mangle]
+pub unsafe extern "C" fn poll_tex_trans_core_engine_device(
+    tex_trans_core_engine_ptr: *const c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        engine.poll();
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+/// ドロップされた TTRenderTexture を再利用のために保持しておく合計サイズの上限を設定する。 0 の場合はプールしなくなる。
+#[no_mangle]
+pub unsafe extern "C" fn set_render_texture_pool_budget(
+    tex_trans_core_engine_ptr: *const c_void,
+    budget_byte: u64,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        engine.set_render_texture_pool_budget(budget_byte);
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+/// 再利用のために保持している TTRenderTexture をすべて破棄する。
+#[no_mangle]
+pub unsafe extern "C" fn clear_render_texture_pool(
+    tex_trans_core_engine_ptr: *const c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        engine.clear_render_texture_pool();
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn get_render_texture_pool_statistics(
+    tex_trans_core_engine_ptr: *const c_void,
+    out_statistics: *mut RenderTexturePoolStatistics,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        write_to_ptr(out_statistics, engine.render_texture_pool_statistics())?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn get_bind_group_statistics(
+    tex_trans_core_engine_ptr: *const c_void,
+    out_statistics: *mut TTBindGroupStatistics,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        write_to_ptr(out_statistics, engine.bind_group_statistics())?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tex_trans_core_engine_ptr は TexTransCoreEngineDevice のポインターでないといけない。
+/// デバイスの生成時に読み込んだキャッシュの内容を file_path に保存する。キャッシュが無い場合は何もしない。
+/// ドロップする前に行うように。
+#[no_mangle]
+pub unsafe extern "C" fn save_pipeline_cache(
+    tex_trans_core_engine_ptr: *const c_void,
+    file_path: *const u16,
+    file_path_str_len: i32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        let path = string_from_utf16(file_path, file_path_str_len)?;
+
+        engine.save_pipeline_cache_to_file(std::path::Path::new(&path))?;
+        Ok(())
+    })
 }
 
 // TexTransCoreEngineContext
@@ -224,23 +681,48 @@ pub struct RegisterCSResult {
 /// TexTransCoreEngineContext を生成し、それのポインターを得ることができる。
 /// 処理が始まる前に行うべきことを行ってから作ることを推奨。
 #[no_mangle]
-pub unsafe extern "C" fn get_ttce_context(tex_trans_core_engine_ptr: *const c_void) -> *mut c_void {
-    let engine = (tex_trans_core_engine_ptr as *const TexTransCoreEngineDevice)
-        .as_ref()
-        .unwrap();
+pub unsafe extern "C" fn get_ttce_context(
+    tex_trans_core_engine_ptr: *const c_void,
+    out_ttce_context_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine = ref_from_ptr::<TexTransCoreEngineDevice>(tex_trans_core_engine_ptr)?;
+        if engine.is_device_lost() {
+            return Err(TTCEWgpuError::DeviceLost.into());
+        }
 
-    Box::into_raw(Box::from(engine.create_ctx())) as *mut c_void
+        write_handle_to_ptr(out_ttce_context_ptr, engine.create_ctx())?;
+        Ok(())
+    })
 }
 
 /// # Safety
 /// ttce_context_ptr は TexTransCoreEngineContext でないといけない。
 #[no_mangle]
-pub unsafe extern "C" fn drop_ttce_context(ttce_context_ptr: *mut c_void) {
-    let _ = Box::from_raw(ttce_context_ptr as *mut TexTransCoreEngineContext);
+pub unsafe extern "C" fn drop_ttce_context(ttce_context_ptr: *mut c_void) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        drop_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn get_constants_buffer_statistics(
+    ttce_context_ptr: *const c_void,
+    out_statistics: *mut TTConstantsBufferStatistics,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = ref_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        write_to_ptr(out_statistics, engine_ctx.constants_buffer_statistics())?;
+        Ok(())
+    })
 }
 
 /// # Safety
 /// ttce_context_ptr は TexTransCoreEngineContext のポインターでないといけない。
+/// mip_level_count は 1 以上で、 1x1 までのミップの数以下でないといけない。
 /// TTRenderTexture のポインターを得る事ができる。
 #[no_mangle]
 pub unsafe extern "C" fn get_render_texture(
@@ -248,47 +730,272 @@ pub unsafe extern "C" fn get_render_texture(
     width: u32,
     height: u32,
     channel: TexTransCoreTextureChannel,
-) -> *mut c_void {
-    let engine_ctx = (ttce_context_ptr as *mut TexTransCoreEngineContext)
-        .as_mut()
-        .unwrap();
+    mip_level_count: u32,
+    out_render_texture_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    get_render_texture_with_dimension(
+        ttce_context_ptr,
+        width,
+        height,
+        1,
+        TTRtDimension::D2,
+        channel,
+        mip_level_count,
+        out_render_texture_ptr,
+    )
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext のポインターでないといけない。
+/// dimension が D2 の場合 depth_or_array_layers は 2D 配列のレイヤー数、 D3 の場合は奥行きになる。
+/// mip_level_count は 1 以上で、 1x1 までのミップの数以下でないといけない。
+/// TTRenderTexture のポインターを得る事ができる。
+#[no_mangle]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn get_render_texture_with_dimension(
+    ttce_context_ptr: *mut c_void,
+    width: u32,
+    height: u32,
+    depth_or_array_layers: u32,
+    dimension: TTRtDimension,
+    channel: TexTransCoreTextureChannel,
+    mip_level_count: u32,
+    out_render_texture_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let desc = TTRtRequestDescriptor {
+            width,
+            height,
+            format: RequestFormat::AutoWithChannel(channel),
+            mip_level_count,
+            dimension,
+            depth_or_array_layers,
+        };
+        desc.validate(&engine_ctx.engine.device.limits())?;
+
+        write_handle_to_ptr(
+            out_render_texture_ptr,
+            engine_ctx.get_render_texture_with(&desc),
+        )?;
+        Ok(())
+    })
+}
+
+/// # Safety
+///  TTRenderTexture のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn drop_render_texture(render_texture_ptr: *mut c_void) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        drop_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+///  TTRenderTexture のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn get_width(
+    render_texture_ptr: *const c_void,
+    out_width: *mut u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let from_render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        write_to_ptr(out_width, from_render_texture.width())?;
+        Ok(())
+    })
+}
+
+/// # Safety
+///  TTRenderTexture のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn get_height(
+    render_texture_ptr: *const c_void,
+    out_height: *mut u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let from_render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        write_to_ptr(out_height, from_render_texture.height())?;
+        Ok(())
+    })
+}
 
-    Box::into_raw(Box::from(
-        engine_ctx.get_render_texture(width, height, channel),
-    )) as *mut c_void
+/// # Safety
+///  TTRenderTexture のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn get_mip_level_count(
+    render_texture_ptr: *const c_void,
+    out_mip_level_count: *mut u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let from_render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        write_to_ptr(out_mip_level_count, from_render_texture.mip_level_count())?;
+        Ok(())
+    })
 }
 
 /// # Safety
 ///  TTRenderTexture のポインターでないといけない。
+/// 2D 配列の場合はレイヤー数、 3D の場合は mip 0 の奥行きが得られる。
 #[no_mangle]
-pub unsafe extern "C" fn drop_render_texture(render_texture_ptr: *mut c_void) {
-    let _ = Box::from_raw(render_texture_ptr as *mut TTRenderTexture);
+pub unsafe extern "C" fn get_depth_or_array_layers(
+    render_texture_ptr: *const c_void,
+    out_depth_or_array_layers: *mut u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let from_render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        write_to_ptr(
+            out_depth_or_array_layers,
+            from_render_texture.depth_or_array_layers(),
+        )?;
+        Ok(())
+    })
 }
 
 /// # Safety
 ///  TTRenderTexture のポインターでないといけない。
 #[no_mangle]
-pub unsafe extern "C" fn get_width(render_texture_ptr: *mut c_void) -> u32 {
-    let from_render_texture = (render_texture_ptr as *const TTRenderTexture)
-        .as_ref()
-        .unwrap();
+pub unsafe extern "C" fn get_color_space(
+    render_texture_ptr: *const c_void,
+    out_color_space: *mut TTColorSpace,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let from_render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        write_to_ptr(out_color_space, from_render_texture.color_space())?;
+        Ok(())
+    })
+}
 
-    from_render_texture.width()
+/// # Safety
+///  TTRenderTexture のポインターでないといけない。
+/// 印を付け替えるのみで中身は変換されない。
+#[no_mangle]
+pub unsafe extern "C" fn set_color_space(
+    render_texture_ptr: *mut c_void,
+    color_space: TTColorSpace,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let render_texture = mut_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        render_texture.set_color_space(color_space);
+        Ok(())
+    })
 }
 
 /// # Safety
 ///  TTRenderTexture のポインターでないといけない。
 #[no_mangle]
-pub unsafe extern "C" fn get_height(render_texture_ptr: *mut c_void) -> u32 {
-    let from_render_texture = (render_texture_ptr as *const TTRenderTexture)
-        .as_ref()
-        .unwrap();
+pub unsafe extern "C" fn get_dimension(
+    render_texture_ptr: *const c_void,
+    out_dimension: *mut TTRtDimension,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let from_render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        let dimension = match from_render_texture.dimension() {
+            wgpu::TextureDimension::D3 => TTRtDimension::D3,
+            _ => TTRtDimension::D2,
+        };
+        write_to_ptr(out_dimension, dimension)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// mip 0 から残りのすべてのミップを生成する。 gamma_aware の場合は RGB を sRGB として扱う。
+#[no_mangle]
+pub unsafe extern "C" fn generate_mipmap(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    filter: MipmapFilter,
+    gamma_aware: bool,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.generate_mipmap(render_texture, filter, gamma_aware)?;
+        Ok(())
+    })
+}
 
-    from_render_texture.height()
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// すべてのミップの RGB を現在の色空間から color_space に変換し、色空間の印も付け替える。
+#[no_mangle]
+pub unsafe extern "C" fn convert_color_space(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *mut c_void,
+    color_space: TTColorSpace,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let render_texture = mut_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.convert_color_space(render_texture, color_space)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext のポインターでないといけない。
+/// file_path は PNG, TGA, EXR, DDS のファイルのパスを UTF16 (C# string) で渡すように。
+/// 読み込んだ TTRenderTexture のポインターと、ファイルのビット深度から選ばれたチャンネルを得る事ができる。
+#[no_mangle]
+pub unsafe extern "C" fn load_image_file(
+    ttce_context_ptr: *mut c_void,
+    file_path: *const u16,
+    file_path_str_len: i32,
+    out_render_texture_ptr: *mut *mut c_void,
+    out_channel: *mut TexTransCoreTextureChannel,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let path = string_from_utf16(file_path, file_path_str_len)?;
+
+        let render_texture = engine_ctx.load_image_file(std::path::Path::new(&path))?;
+        let (_, channel) = render_texture
+            .tt_format()
+            .ok_or(TTCEWgpuError::UnsupportedFormat)?;
+        write_to_ptr(out_channel, channel)?;
+        write_handle_to_ptr(out_render_texture_ptr, render_texture)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// render_texture の mip 0 を file_format で file_path に書き出す。色空間の変換は行わない。
+#[no_mangle]
+pub unsafe extern "C" fn save_image_file(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    file_path: *const u16,
+    file_path_str_len: i32,
+    file_format: TTImageFileFormat,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+        let path = string_from_utf16(file_path, file_path_str_len)?;
+
+        get_tokio_runtime().block_on(engine_ctx.save_image_file(
+            render_texture,
+            std::path::Path::new(&path),
+            file_format,
+        ))?;
+        Ok(())
+    })
 }
 
 //Upload Download to render texture
-#[derive(Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 #[repr(i32)]
 pub enum TexTransCoreTextureChannel {
     R = 1,
@@ -304,27 +1011,102 @@ pub enum TexTransCoreTextureFormat {
     Half = 2,
     Float = 3,
 }
+/// チャンネル数が変わる変換でのチャンネルの扱い。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[repr(u32)]
+pub enum TTChannelMapping {
+    /// 減る場合は先頭のチャンネルを残し、 R から増やす場合は RGB に広げ、 RG から増やす場合は B を 0 、 A を 1 で埋める。
+    Default = 0,
+    /// RGBA から R への変換のみ、 RGB の輝度 (Rec.709) を R にする。
+    Luminance = 1,
+    /// RGBA から R への変換のみ、 A を R にする。
+    Alpha = 2,
+}
 
 /// # Safety
 /// 二つの TTRenderTexture のポインターでなければならない。
-/// 形式の変換や解像度のリサイズなどは一切行えないので注意。
+/// 形式の変換や解像度のリサイズなどは一切行えないので注意、それらが必要な場合は blit_texture を使うように。
 #[no_mangle]
 pub unsafe extern "C" fn copy_texture(
     ttce_context_ptr: *mut c_void,
     dist_render_texture_ptr: *const c_void,
     source_render_texture_ptr: *const c_void,
-) {
-    let engine_ctx = (ttce_context_ptr as *mut TexTransCoreEngineContext)
-        .as_mut()
-        .unwrap();
-    let dist_render_texture = (dist_render_texture_ptr as *const TTRenderTexture)
-        .as_ref()
-        .unwrap();
-    let source_render_texture = (source_render_texture_ptr as *const TTRenderTexture)
-        .as_ref()
-        .unwrap();
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let dist_render_texture = ref_from_ptr::<TTRenderTexture>(dist_render_texture_ptr)?;
+        let source_render_texture = ref_from_ptr::<TTRenderTexture>(source_render_texture_ptr)?;
+
+        engine_ctx.copy_texture(dist_render_texture, source_render_texture)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 dist_render_texture_ptr と source_render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// source_region を dist_region にコピーする。二つの領域は範囲内で同じ大きさでなければならず、形式の変換は行えない。
+#[no_mangle]
+pub unsafe extern "C" fn copy_texture_region(
+    ttce_context_ptr: *mut c_void,
+    dist_render_texture_ptr: *const c_void,
+    dist_region: TTTextureRegion,
+    source_render_texture_ptr: *const c_void,
+    source_region: TTTextureRegion,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let dist_render_texture = ref_from_ptr::<TTRenderTexture>(dist_render_texture_ptr)?;
+        let source_render_texture = ref_from_ptr::<TTRenderTexture>(source_render_texture_ptr)?;
+
+        engine_ctx.copy_texture_region(
+            dist_render_texture,
+            dist_region,
+            source_render_texture,
+            source_region,
+        )?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 dist_render_texture_ptr と source_render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// 配列でない 2D の異なるもの同士で、 mip 0 を解像度とフォーマットとチャンネルを変換しながらコピーする。
+#[no_mangle]
+pub unsafe extern "C" fn blit_texture(
+    ttce_context_ptr: *mut c_void,
+    dist_render_texture_ptr: *const c_void,
+    source_render_texture_ptr: *const c_void,
+    filter: TTBlitFilter,
+    mapping: TTChannelMapping,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let dist_render_texture = ref_from_ptr::<TTRenderTexture>(dist_render_texture_ptr)?;
+        let source_render_texture = ref_from_ptr::<TTRenderTexture>(source_render_texture_ptr)?;
+
+        engine_ctx.blit(dist_render_texture, source_render_texture, filter, mapping)?;
+        Ok(())
+    })
+}
 
-    engine_ctx.copy_texture(dist_render_texture, source_render_texture);
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 dist_render_texture_ptr と source_render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// 同じ大きさの配列でない 2D 同士で、 mip 0 をフォーマットとチャンネルを変換しながらコピーする。
+#[no_mangle]
+pub unsafe extern "C" fn convert_texture(
+    ttce_context_ptr: *mut c_void,
+    dist_render_texture_ptr: *const c_void,
+    source_render_texture_ptr: *const c_void,
+    mapping: TTChannelMapping,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let dist_render_texture = ref_from_ptr::<TTRenderTexture>(dist_render_texture_ptr)?;
+        let source_render_texture = ref_from_ptr::<TTRenderTexture>(source_render_texture_ptr)?;
+
+        engine_ctx.convert_texture(dist_render_texture, source_render_texture, mapping)?;
+        Ok(())
+    })
 }
 
 /// # Safety
@@ -339,16 +1121,122 @@ pub unsafe extern "C" fn upload_texture(
     data: *const u8,
     data_len: i32,
     format: TexTransCoreTextureFormat,
-) {
-    let engine_ctx = (ttce_context_ptr as *mut TexTransCoreEngineContext)
-        .as_mut()
-        .unwrap();
-    let data_slice = std::slice::from_raw_parts(data, data_len as usize);
-    let render_texture = (render_texture_ptr as *const TTRenderTexture)
-        .as_ref()
-        .unwrap();
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr(data, data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.upload_texture(render_texture, data_slice, format)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// upload_texture と同じだが、 data_len は mip_level のミップの解像度と合うようにしなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn upload_texture_mip(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    mip_level: u32,
+    data: *const u8,
+    data_len: i32,
+    format: TexTransCoreTextureFormat,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr(data, data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.upload_texture_mip(render_texture, mip_level, data_slice, format)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// upload_texture と同じだが、 2D 配列の場合は layer 番目のレイヤー、 3D の場合は奥行き layer の位置の一枚に書き込む。
+/// data_len は mip_level のミップの一枚分の解像度と合うようにしなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn upload_texture_layer(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    mip_level: u32,
+    layer: u32,
+    data: *const u8,
+    data_len: i32,
+    format: TexTransCoreTextureFormat,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr(data, data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.upload_texture_layer(render_texture, mip_level, layer, data_slice, format)?;
+        Ok(())
+    })
+}
 
-    engine_ctx.upload_texture(render_texture, data_slice, format);
+/// # Safety
+/// upload_texture と同じだが、 region の矩形のみに書き込む。 data_len は region の大きさと合うようにしなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn upload_texture_region(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    region: TTTextureRegion,
+    data: *const u8,
+    data_len: i32,
+    format: TexTransCoreTextureFormat,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr(data, data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.upload_texture_region(render_texture, region, data_slice, format)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// すべてのミップとレイヤーを r g b a の値で塗りつぶす、値はフォーマットにそのまま書き込まれる。
+#[no_mangle]
+pub unsafe extern "C" fn clear_render_texture(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.clear_render_texture(render_texture, [r, g, b, a])?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// clear_render_texture と同じだが、 region の矩形のみを塗りつぶす。
+#[no_mangle]
+pub unsafe extern "C" fn fill_rect(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    region: TTTextureRegion,
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.fill_rect(render_texture, region, [r, g, b, a])?;
+        Ok(())
+    })
 }
 
 /// # Safety
@@ -363,47 +1251,300 @@ pub unsafe extern "C" fn download_texture(
     write_data_len: i32,
     format: TexTransCoreTextureFormat,
     render_texture_ptr: *const c_void,
-) {
-    let engine_ctx = (ttce_context_ptr as *mut TexTransCoreEngineContext)
-        .as_mut()
-        .unwrap();
-    let data_slice = std::slice::from_raw_parts_mut(write_data, write_data_len as usize);
-    let render_texture = (render_texture_ptr as *const TTRenderTexture)
-        .as_ref()
-        .unwrap();
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr_mut(write_data, write_data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        get_tokio_runtime().block_on(engine_ctx.download_texture(
+            render_texture,
+            Some(format),
+            data_slice,
+        ))?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// download_texture と同じだが、 write_data_len は mip_level のミップの解像度と合うようにしなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn download_texture_mip(
+    ttce_context_ptr: *mut c_void,
+    write_data: *mut u8,
+    write_data_len: i32,
+    format: TexTransCoreTextureFormat,
+    render_texture_ptr: *const c_void,
+    mip_level: u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr_mut(write_data, write_data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        get_tokio_runtime().block_on(engine_ctx.download_texture_mip(
+            render_texture,
+            mip_level,
+            Some(format),
+            data_slice,
+        ))?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// download_texture と同じだが、 upload_texture_layer と同じく一枚分を読み出す。
+#[no_mangle]
+pub unsafe extern "C" fn download_texture_layer(
+    ttce_context_ptr: *mut c_void,
+    write_data: *mut u8,
+    write_data_len: i32,
+    format: TexTransCoreTextureFormat,
+    render_texture_ptr: *const c_void,
+    mip_level: u32,
+    layer: u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr_mut(write_data, write_data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        get_tokio_runtime().block_on(engine_ctx.download_texture_layer(
+            render_texture,
+            mip_level,
+            layer,
+            Some(format),
+            data_slice,
+        ))?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// download_texture と同じだが、 region の矩形のみを読み出す。 write_data_len は region の大きさと合うようにしなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn download_texture_region(
+    ttce_context_ptr: *mut c_void,
+    write_data: *mut u8,
+    write_data_len: i32,
+    format: TexTransCoreTextureFormat,
+    render_texture_ptr: *const c_void,
+    region: TTTextureRegion,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr_mut(write_data, write_data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        get_tokio_runtime().block_on(engine_ctx.download_texture_region(
+            render_texture,
+            region,
+            Some(format),
+            data_slice,
+        ))?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext
+/// render_texture_ptr は TTRenderTexture
+/// write_data は 配列の先頭 のポインター
+/// write_data_len は format でブロック圧縮したときの長さと一致しなければならない。
+#[no_mangle]
+pub unsafe extern "C" fn encode_block_compression(
+    ttce_context_ptr: *mut c_void,
+    write_data: *mut u8,
+    write_data_len: i32,
+    format: TTBlockCompressionFormat,
+    quality: TTBlockCompressionQuality,
+    render_texture_ptr: *const c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr_mut(write_data, write_data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        get_tokio_runtime().block_on(engine_ctx.encode_block_compression(
+            render_texture,
+            format,
+            quality,
+            data_slice,
+        ))?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext
+/// render_texture_ptr は TTRenderTexture
+/// data は 配列の先頭 のポインター
+/// data_len は書き込み先の解像度を format でブロック圧縮したときの長さと一致しなければならない。
+/// BC6H も含めてどの path でもデコードでき、 Hardware を TEXTURE_COMPRESSION_BC が無いデバイスで指定した場合のみ UnsupportedFormat になる。
+#[no_mangle]
+pub unsafe extern "C" fn upload_block_compressed_texture(
+    ttce_context_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+    data: *const u8,
+    data_len: i32,
+    format: TTBlockCompressionFormat,
+    path: TTBlockDecompressionPath,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let data_slice = slice_from_ptr(data, data_len)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        engine_ctx.upload_block_compressed_texture_with(
+            render_texture,
+            data_slice,
+            format,
+            path,
+        )?;
+        Ok(())
+    })
+}
 
-    get_tokio_runtime().block_on(async move {
-        let buffer = engine_ctx
-            .download_texture(render_texture, Some(format))
-            .await
-            .unwrap();
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext 、 render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// completed_callback は null でもよく、完了したときに user_data と状態が渡される。
+/// callback は poll_download_ticket や poll_tex_trans_core_engine_device などを呼んだスレッドで呼ばれる。
+/// 完了を待たずに TTDownloadTicket のポインターが得られ、 region の矩形のみが読み出される。
+#[no_mangle]
+pub unsafe extern "C" fn request_download_texture(
+    ttce_context_ptr: *mut c_void,
+    format: TexTransCoreTextureFormat,
+    render_texture_ptr: *const c_void,
+    region: TTTextureRegion,
+    completed_callback: unsafe extern "C" fn(*mut c_void, TTDownloadTicketState) -> (),
+    user_data: *mut c_void,
+    out_download_ticket_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        let on_completed: Option<DownloadCompletedCallback> = if completed_callback as usize == 0 {
+            None
+        } else {
+            let user_data = user_data as usize;
+            Some(Box::new(move |state| unsafe {
+                completed_callback(user_data as *mut c_void, state)
+            }))
+        };
+
+        let ticket = engine_ctx.request_download_texture(
+            render_texture,
+            region,
+            Some(format),
+            on_completed,
+        )?;
+        write_handle_to_ptr(out_download_ticket_ptr, ticket)?;
+        Ok(())
+    })
+}
 
-        let buffer_slice = buffer.slice(..);
-        let buffer_mapped = buffer_slice.get_mapped_range();
+/// # Safety
+/// download_ticket_ptr は TTDownloadTicket のポインターでないといけない。
+/// ブロックせずに状態を進めて、その状態を返す。
+#[no_mangle]
+pub unsafe extern "C" fn poll_download_ticket(
+    download_ticket_ptr: *const c_void,
+    out_state: *mut TTDownloadTicketState,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let ticket = ref_from_ptr::<TTDownloadTicket>(download_ticket_ptr)?;
+        write_to_ptr(out_state, ticket.poll())?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// download_ticket_ptr は TTDownloadTicket のポインターでないといけない。
+/// 完了するまでブロックする。 DEVICE_WAIT_TIMEOUT を過ぎた場合は DeviceTimeout になる。
+#[no_mangle]
+pub unsafe extern "C" fn wait_download_ticket(
+    download_ticket_ptr: *const c_void,
+    out_state: *mut TTDownloadTicketState,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let ticket = ref_from_ptr::<TTDownloadTicket>(download_ticket_ptr)?;
+        write_to_ptr(out_state, ticket.wait()?)?;
+        Ok(())
+    })
+}
 
-        data_slice.copy_from_slice(&buffer_mapped);
-    });
+/// # Safety
+/// download_ticket_ptr は TTDownloadTicket のポインターでないといけない。
+/// read_download_ticket に渡すべき長さが得られる。
+#[no_mangle]
+pub unsafe extern "C" fn get_download_ticket_data_size(
+    download_ticket_ptr: *const c_void,
+    out_data_size: *mut u64,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let ticket = ref_from_ptr::<TTDownloadTicket>(download_ticket_ptr)?;
+        write_to_ptr(out_data_size, ticket.data_size())?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// download_ticket_ptr は TTDownloadTicket のポインター、 write_data は 配列の先頭 のポインターでないといけない。
+/// 完了していない場合は DownloadNotReady になる。
+#[no_mangle]
+pub unsafe extern "C" fn read_download_ticket(
+    download_ticket_ptr: *const c_void,
+    write_data: *mut u8,
+    write_data_len: i32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let ticket = ref_from_ptr::<TTDownloadTicket>(download_ticket_ptr)?;
+        let data_slice = slice_from_ptr_mut(write_data, write_data_len)?;
+        ticket.read(data_slice)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// download_ticket_ptr は TTDownloadTicket のポインターでないといけない。
+/// 完了していないものをドロップした場合、その callback は Failed で呼ばれる。
+#[no_mangle]
+pub unsafe extern "C" fn drop_download_ticket(download_ticket_ptr: *mut c_void) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        drop_from_ptr::<TTDownloadTicket>(download_ticket_ptr)?;
+        Ok(())
+    })
 }
 
 /// # Safety
-/// tt_compute_handler_ptr は TTComputeHandler のポインターを割り当てるように。
+/// ttce_context_ptr は TexTransCoreEngineContext のポインターを割り当てるように。
+/// indirect が true の場合は dispatch_indirect に渡せるようになる。
 /// TTStorageBuffer への pointer が得られる。
 #[no_mangle]
 pub unsafe extern "C" fn allocate_storage_buffer(
     ttce_context_ptr: *const c_void,
     buffer_len: i32,
     downloadable: bool,
-) -> *mut c_void {
-    let engine_ctx = (ttce_context_ptr as *const TexTransCoreEngineContext)
-        .as_ref()
-        .unwrap();
+    indirect: bool,
+    out_storage_buffer_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = ref_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        if buffer_len < 0 {
+            return Err(TTCEWgpuError::InvalidArgument.into());
+        }
 
-    Box::into_raw(Box::from(
-        engine_ctx.allocate_storage_buffer(buffer_len, downloadable),
-    )) as *mut c_void
+        write_handle_to_ptr(
+            out_storage_buffer_ptr,
+            engine_ctx.allocate_storage_buffer(buffer_len, downloadable, indirect),
+        )?;
+        Ok(())
+    })
 }
 /// # Safety
-/// tt_compute_handler_ptr は TTComputeHandler のポインター、 buffer は アップロードしたい 配列の先頭の のポインターでないといけない。
+/// ttce_context_ptr は TexTransCoreEngineContext のポインター、 buffer は アップロードしたい 配列の先頭の のポインターでないといけない。
+/// indirect が true の場合は dispatch_indirect に渡せるようになる。
 /// TTStorageBuffer への pointer が得られる。
 #[no_mangle]
 pub unsafe extern "C" fn upload_storage_buffer(
@@ -411,52 +1552,85 @@ pub unsafe extern "C" fn upload_storage_buffer(
     buffer: *const u8,
     buffer_len: i32,
     downloadable: bool,
-) -> *mut c_void {
-    let engine_ctx = (ttce_context_ptr as *const TexTransCoreEngineContext)
-        .as_ref()
-        .unwrap();
-
-    let buffer = std::slice::from_raw_parts(buffer, buffer_len as usize);
+    indirect: bool,
+    out_storage_buffer_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = ref_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+
+        let buffer = slice_from_ptr(buffer, buffer_len)?;
+
+        write_handle_to_ptr(
+            out_storage_buffer_ptr,
+            engine_ctx.upload_storage_buffer(buffer, downloadable, indirect),
+        )?;
+        Ok(())
+    })
+}
+/// # Safety
+/// storage_buffer_ptr は TTStorageBuffer のポインターでないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn drop_storage_buffer(storage_buffer_ptr: *mut c_void) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        drop_from_ptr::<TTStorageBuffer>(storage_buffer_ptr)?;
+        Ok(())
+    })
+}
 
-    Box::into_raw(Box::from(
-        engine_ctx.upload_storage_buffer(buffer, downloadable),
-    )) as *mut c_void
+/// # Safety
+/// ttce_context_ptr は TexTransCoreEngineContext のポインターでないといけない。
+/// TTSampler への pointer が得られる。 anisotropy_clamp が 2 以上の場合は filter_mode が Trilinear でないといけない。
+#[no_mangle]
+pub unsafe extern "C" fn create_sampler(
+    ttce_context_ptr: *const c_void,
+    descriptor: TTSamplerDescriptor,
+    out_sampler_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = ref_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+
+        let sampler = engine_ctx.create_sampler(&descriptor).map_err(|e| {
+            e.with_message(format!("sampler descriptor is invalid : {:?}", descriptor))
+        })?;
+        write_handle_to_ptr(out_sampler_ptr, sampler)?;
+        Ok(())
+    })
 }
 /// # Safety
-/// tt_compute_handler_ptr は TTComputeHandler のポインターでないといけない。
+/// sampler_ptr は TTSampler のポインターでないといけない。
 #[no_mangle]
-pub unsafe extern "C" fn drop_storage_buffer(storage_buffer_ptr: *mut c_void) {
-    let _ = Box::from_raw(storage_buffer_ptr as *mut TTStorageBuffer);
+pub unsafe extern "C" fn drop_sampler(sampler_ptr: *mut c_void) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        drop_from_ptr::<TTSampler>(sampler_ptr)?;
+        Ok(())
+    })
 }
 
 /// # Safety
-/// tt_compute_handler_ptr は TTComputeHandler のポインターでないといけない。
+/// ttce_context_ptr は TexTransCoreEngineContext 、 storage_buffer_ptr は TTStorageBuffer のポインターでないといけない。
 #[no_mangle]
 pub unsafe extern "C" fn download_storage_buffer(
     ttce_context_ptr: *mut c_void,
     buffer: *mut u8,
     buffer_len: i32,
     storage_buffer_ptr: *const c_void,
-) {
-    let engine_ctx = (ttce_context_ptr as *mut TexTransCoreEngineContext)
-        .as_mut()
-        .unwrap();
-    let storage_buffer = (storage_buffer_ptr as *mut TTStorageBuffer)
-        .as_ref()
-        .unwrap();
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+        let storage_buffer = ref_from_ptr::<TTStorageBuffer>(storage_buffer_ptr)?;
 
-    let buffer = std::slice::from_raw_parts_mut(buffer, buffer_len as usize);
+        let buffer = slice_from_ptr_mut(buffer, buffer_len)?;
 
-    get_tokio_runtime()
-        .block_on(engine_ctx.download_storage_buffer(storage_buffer))
-        .unwrap();
+        get_tokio_runtime().block_on(engine_ctx.download_storage_buffer(storage_buffer))?;
 
-    let storage_buffer_slice = storage_buffer
-        .buffer
-        .slice(..storage_buffer.buffer.size().min(buffer_len as u64));
-    let storage_buffer_mapped = storage_buffer_slice.get_mapped_range();
+        let storage_buffer_slice = storage_buffer
+            .buffer
+            .slice(..storage_buffer.buffer.size().min(buffer_len as u64));
+        let storage_buffer_mapped = storage_buffer_slice.get_mapped_range();
 
-    buffer.copy_from_slice(&storage_buffer_mapped);
+        buffer[..storage_buffer_mapped.len()].copy_from_slice(&storage_buffer_mapped);
+        Ok(())
+    })
 }
 
 // TTComputeHandler
@@ -468,132 +1642,128 @@ pub unsafe extern "C" fn download_storage_buffer(
 pub unsafe extern "C" fn get_compute_handler(
     ttce_context_ptr: *mut c_void,
     tt_compute_shader_id: u32,
-) -> *mut c_void {
-    let engine_ctx = (ttce_context_ptr as *mut TexTransCoreEngineContext)
-        .as_mut()
-        .unwrap();
-
-    Box::into_raw(Box::from(
-        engine_ctx
-            .get_compute_handler(&TTComputeShaderID::from(tt_compute_shader_id))
-            .unwrap(),
-    )) as *mut c_void
+    out_compute_handler_ptr: *mut *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let engine_ctx = mut_from_ptr::<TexTransCoreEngineContext>(ttce_context_ptr)?;
+
+        write_handle_to_ptr(
+            out_compute_handler_ptr,
+            engine_ctx.get_compute_handler(&TTComputeShaderID::from(tt_compute_shader_id))?,
+        )?;
+        Ok(())
+    })
 }
 
 /// # Safety
 /// tt_compute_handler_ptr は TTComputeHandler のポインターでないといけない。
 #[no_mangle]
-pub unsafe extern "C" fn drop_compute_handler(tt_compute_handler_ptr: *mut c_void) {
-    let _ = Box::from_raw(tt_compute_handler_ptr as *mut TTComputeHandler);
+pub unsafe extern "C" fn drop_compute_handler(
+    tt_compute_handler_ptr: *mut c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        drop_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
+        Ok(())
+    })
 }
 
 /// # Safety
 /// tt_compute_handler_ptr は TTComputeHandler のポインターでないといけない。
+/// 見つからなかった場合は BindingNotFound になる。 HLSL の register の space と番号の組が得られる。
 #[no_mangle]
 pub unsafe extern "C" fn get_bind_index(
-    tt_compute_handler_ptr: *const c_void,
+    tt_compute_handler_ptr: *mut c_void,
     name_ptr: *const u16,
     name_ptr_len: i32,
-) -> GetBindIndexResult {
-    let compute_handler = (tt_compute_handler_ptr as *mut TTComputeHandler)
-        .as_mut()
-        .unwrap();
-
-    let name_slice = std::slice::from_raw_parts(name_ptr, name_ptr_len as usize);
-    let name_rust_string = String::from_utf16(name_slice).unwrap();
-
-    if let Some(i) = compute_handler.get_bind_index(name_rust_string.as_str()) {
-        GetBindIndexResult {
-            result: true,
-            bind_index: i,
-        }
-    } else {
-        GetBindIndexResult {
-            result: false,
-            bind_index: 0,
-        }
-    }
-}
-#[repr(C)]
-pub struct GetBindIndexResult {
-    result: bool,
-    bind_index: u32,
+    out_bind_point: *mut TTBindingPoint,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
+
+        let name_rust_string = string_from_utf16(name_ptr, name_ptr_len)?;
+
+        let Some(i) = compute_handler.get_bind_index(name_rust_string.as_str()) else {
+            return Err(TTCEWgpuError::BindingNotFound.with_message(name_rust_string));
+        };
+        write_to_ptr(out_bind_point, i)?;
+        Ok(())
+    })
 }
 /// # Safety
 /// tt_compute_handler_ptr は TTComputeHandler のポインター、render_texture_ptr は TTRenderTexture のポインターでないといけない。
-/// bind_index は get_bind_index から得た値を使うように。
+/// bind_point は get_bind_index から得た値を使うように。
 #[no_mangle]
 pub unsafe extern "C" fn set_render_texture(
     tt_compute_handler_ptr: *mut c_void,
-    bind_index: u32,
+    bind_point: TTBindingPoint,
     render_texture_ptr: *const c_void,
-) -> bool {
-    let compute_handler = (tt_compute_handler_ptr as *mut TTComputeHandler)
-        .as_mut()
-        .unwrap();
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
+
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
 
-    let render_texture = (render_texture_ptr as *const TTRenderTexture)
-        .as_ref()
-        .unwrap();
+        compute_handler.set_render_texture(bind_point, render_texture)?;
+        Ok(())
+    })
+}
 
-    let result = compute_handler.set_render_texture(bind_index, render_texture);
+/// # Safety
+/// tt_compute_handler_ptr は TTComputeHandler のポインター、 sampler_ptr は TTSampler のポインターでないといけない。
+/// bind_point は get_bind_index から得た値を使うように。
+#[no_mangle]
+pub unsafe extern "C" fn set_sampler(
+    tt_compute_handler_ptr: *mut c_void,
+    bind_point: TTBindingPoint,
+    sampler_ptr: *const c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
 
-    if let Err(e) = result {
-        debug_log(format!("{:?}", e).as_str());
-    }
+        let sampler = ref_from_ptr::<TTSampler>(sampler_ptr)?;
 
-    result.is_ok()
+        compute_handler.set_sampler(bind_point, sampler)?;
+        Ok(())
+    })
 }
 
 /// # Safety
 /// tt_compute_handler_ptr は TTComputeHandler のポインター、 buffer は アップロードしたい 配列の先頭の のポインターでないといけない。
-/// bind_index は get_bind_index から得た値を使うように。
+/// bind_point は get_bind_index から得た値を使うように。
 #[no_mangle]
 pub unsafe extern "C" fn upload_constants_buffer(
     tt_compute_handler_ptr: *mut c_void,
-    bind_index: u32,
+    bind_point: TTBindingPoint,
     buffer: *const u8,
     buffer_len: i32,
-) -> bool {
-    let compute_handler = (tt_compute_handler_ptr as *mut TTComputeHandler)
-        .as_mut()
-        .unwrap();
-
-    let buffer = std::slice::from_raw_parts(buffer, buffer_len as usize);
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
 
-    let result = compute_handler.upload_constants_buffer(bind_index, buffer);
+        let buffer = slice_from_ptr(buffer, buffer_len)?;
 
-    if let Err(e) = result {
-        debug_log(format!("{:?}", e).as_str());
-    }
-
-    result.is_ok()
+        compute_handler.upload_constants_buffer(bind_point, buffer)?;
+        Ok(())
+    })
 }
 
 /// # Safety
-/// tt_compute_handler_ptr は TTComputeHandler のポインター、 buffer は アップロードしたい 配列の先頭の のポインターでないといけない。
-/// bind_index は get_bind_index から得た値を使うように。
+/// tt_compute_handler_ptr は TTComputeHandler のポインター、 storage_buffer_ptr は TTStorageBuffer のポインターでないといけない。
+/// bind_point は get_bind_index から得た値を使うように。
 #[no_mangle]
 pub unsafe extern "C" fn set_storage_buffer(
     tt_compute_handler_ptr: *mut c_void,
-    bind_index: u32,
-    storage_buffer_ptr: *mut c_void,
-) -> bool {
-    let compute_handler = (tt_compute_handler_ptr as *mut TTComputeHandler)
-        .as_mut()
-        .unwrap();
-
-    let storage_buffer = (storage_buffer_ptr as *mut TTStorageBuffer)
-        .as_mut()
-        .unwrap();
-
-    let result = compute_handler.set_storage_buffer(bind_index, storage_buffer);
+    bind_point: TTBindingPoint,
+    storage_buffer_ptr: *const c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
 
-    if let Err(e) = result {
-        debug_log(format!("{:?}", e).as_str());
-    }
+        let storage_buffer = ref_from_ptr::<TTStorageBuffer>(storage_buffer_ptr)?;
 
-    result.is_ok()
+        compute_handler.set_storage_buffer(bind_point, storage_buffer)?;
+        Ok(())
+    })
 }
 
 #[repr(C)]
@@ -617,20 +1787,67 @@ impl WorkGroupSize {
 #[no_mangle]
 pub unsafe extern "C" fn get_work_group_size(
     tt_compute_handler_ptr: *const c_void,
-) -> WorkGroupSize {
-    let compute_handler = (tt_compute_handler_ptr as *const TTComputeHandler)
-        .as_ref()
-        .unwrap();
-    WorkGroupSize::from(compute_handler.get_work_group_size())
+    out_work_group_size: *mut WorkGroupSize,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = ref_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
+
+        write_to_ptr(
+            out_work_group_size,
+            WorkGroupSize::from(compute_handler.get_work_group_size()),
+        )?;
+        Ok(())
+    })
 }
 
 /// # Safety
 /// tt_compute_handler_ptr は TTComputeHandler のポインター
 #[no_mangle]
-pub unsafe extern "C" fn dispatch(tt_compute_handler_ptr: *mut c_void, x: u32, y: u32, z: u32) {
-    let compute_handler = (tt_compute_handler_ptr as *mut TTComputeHandler)
-        .as_mut()
-        .unwrap();
+pub unsafe extern "C" fn dispatch(
+    tt_compute_handler_ptr: *mut c_void,
+    x: u32,
+    y: u32,
+    z: u32,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
+
+        compute_handler.dispatch(x, y, z)?;
+        Ok(())
+    })
+}
+
+/// # Safety
+/// tt_compute_handler_ptr は TTComputeHandler 、 render_texture_ptr は TTRenderTexture のポインターでないといけない。
+/// render_texture のすべてのピクセルを覆うように、ワークグループ数を切り上げて dispatch する。
+#[no_mangle]
+pub unsafe extern "C" fn dispatch_for_texture(
+    tt_compute_handler_ptr: *mut c_void,
+    render_texture_ptr: *const c_void,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
+        let render_texture = ref_from_ptr::<TTRenderTexture>(render_texture_ptr)?;
+
+        compute_handler.dispatch_for_texture(render_texture)?;
+        Ok(())
+    })
+}
 
-    compute_handler.dispatch(x, y, z);
+/// # Safety
+/// tt_compute_handler_ptr は TTComputeHandler 、 storage_buffer_ptr は indirect を有効にして作られた TTStorageBuffer のポインターでないといけない。
+/// indirect_offset は 4 の倍数で、そこから u32 の x y z の三つが読まれる。
+#[no_mangle]
+pub unsafe extern "C" fn dispatch_indirect(
+    tt_compute_handler_ptr: *mut c_void,
+    storage_buffer_ptr: *const c_void,
+    indirect_offset: u64,
+) -> TTCEWgpuStatus {
+    ffi_boundary(|| {
+        let compute_handler = mut_from_ptr::<TTComputeHandler>(tt_compute_handler_ptr)?;
+        let storage_buffer = ref_from_ptr::<TTStorageBuffer>(storage_buffer_ptr)?;
+
+        compute_handler.dispatch_indirect(storage_buffer, indirect_offset)?;
+        Ok(())
+    })
 }
@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use crate::{
+    compute_shader::AsTypeStr,
+    render_texture::TTRenderTexture,
+    tex_trans_core_engine::{TTCEWgpuError, TexTransCoreEngineContext, TexTransCoreEngineDevice},
+};
+
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MipmapFilter {
+    /// 縮小元の範囲の単純な平均
+    Box = 0,
+    /// Kaiser 窓をかけた sinc 、 Box よりシャープになる
+    Kaiser = 1,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct MipmapPipelineKey {
+    format: wgpu::TextureFormat,
+    filter: MipmapFilter,
+    gamma_aware: bool,
+}
+
+#[derive(Debug)]
+pub(crate) struct MipmapPipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::ComputePipeline,
+}
+
+const MIPMAP_WORK_GROUP_SIZE: u32 = 8;
+
+impl TexTransCoreEngineDevice {
+    // フォーマットとフィルターの組み合わせが多いため、使われたときに生成する
+    fn get_or_create_mipmap_pipeline(&self, key: MipmapPipelineKey) -> Arc<MipmapPipeline> {
+        let mut pipelines = self
+            .mipmap_pipelines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.create_mipmap_pipeline(key)))
+            .clone()
+    }
+
+    fn create_mipmap_pipeline(&self, key: MipmapPipelineKey) -> MipmapPipeline {
+        let wgsl_str = MIPMAP_GENERATOR_TEMPLATE
+            .replace("$$$FORMAT$$$", key.format.as_type_str())
+            .replace("$$$FILTER$$$", &format!("{}u", key.filter as u32))
+            .replace("$$$GAMMA_AWARE$$$", &key.gamma_aware.to_string());
+
+        let cs_module = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("mipmap generator shader module"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
+            });
+
+        // R32Float などはフィルタリングできないため、 textureLoad のみで読む unfilterable として明示する
+        let bind_group_layout =
+            self.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: Some("mipmap generator bind group layout"),
+                    entries: &[
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 0,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::Texture {
+                                sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                                multisampled: false,
+                            },
+                            count: None,
+                        },
+                        wgpu::BindGroupLayoutEntry {
+                            binding: 1,
+                            visibility: wgpu::ShaderStages::COMPUTE,
+                            ty: wgpu::BindingType::StorageTexture {
+                                access: wgpu::StorageTextureAccess::WriteOnly,
+                                format: key.format,
+                                view_dimension: wgpu::TextureViewDimension::D2,
+                            },
+                            count: None,
+                        },
+                    ],
+                });
+        let pipeline_layout = self
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("mipmap generator pipeline layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = self
+            .device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some("mipmap generator compute pipeline"),
+                layout: Some(&pipeline_layout),
+                module: &cs_module,
+                entry_point: Some("CSMain"),
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                cache: self.pipeline_cache(),
+            });
+
+        MipmapPipeline {
+            bind_group_layout,
+            pipeline,
+        }
+    }
+}
+
+impl TexTransCoreEngineContext<'_> {
+    /// mip 0 から順に縮小して、残りのすべてのミップを生成する。
+    /// gamma_aware が true の場合は RGB を sRGB として扱い、リニアに変換してからフィルタリングする。
+    pub fn generate_mipmap(
+        &mut self,
+        render_texture: &TTRenderTexture,
+        filter: MipmapFilter,
+        gamma_aware: bool,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        if render_texture.tt_format().is_none() {
+            return Err(TTCEWgpuError::UnsupportedFormat);
+        }
+        if !render_texture.is_single_layer_2d() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch);
+        }
+
+        let engine = self.engine;
+        let mipmap_pipeline = engine.get_or_create_mipmap_pipeline(MipmapPipelineKey {
+            format: render_texture.format(),
+            filter,
+            gamma_aware,
+        });
+
+        for mip_level in 1..render_texture.mip_level_count() {
+            let mip_view = |mip_level| {
+                render_texture.create_view(&wgpu::TextureViewDescriptor {
+                    base_mip_level: mip_level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            };
+            let src_view = mip_view(mip_level - 1);
+            let dist_view = mip_view(mip_level);
+
+            let bind_group = engine.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("mipmap generator bind group"),
+                layout: &mipmap_pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&src_view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::TextureView(&dist_view),
+                    },
+                ],
+            });
+
+            let dist_size = render_texture.mip_size(mip_level);
+            {
+                let encoder = self.get_command_encoder_as_mut();
+                let mut compute_pass = encoder.begin_compute_pass(&Default::default());
+
+                compute_pass.set_pipeline(&mipmap_pipeline.pipeline);
+                compute_pass.set_bind_group(0, &bind_group, &[]);
+                compute_pass.dispatch_workgroups(
+                    dist_size.width.div_ceil(MIPMAP_WORK_GROUP_SIZE),
+                    dist_size.height.div_ceil(MIPMAP_WORK_GROUP_SIZE),
+                    1,
+                );
+            }
+            self.check_command_stack();
+        }
+        Ok(())
+    }
+}
+
+// 奇数の解像度の場合も縮小率から縮小元の範囲を求めるため、端のピクセルが落ちることはない。
+pub const MIPMAP_GENERATOR_TEMPLATE: &str = r#"
+@group(0) @binding(0)
+var SrcTex: texture_2d<f32>;
+@group(0) @binding(1)
+var DistTex: texture_storage_2d<$$$FORMAT$$$,write>;
+
+const FILTER: u32 = $$$FILTER$$$;
+const GAMMA_AWARE: bool = $$$GAMMA_AWARE$$$;
+
+const PI: f32 = 3.14159265358979;
+const KAISER_WIDTH: f32 = 1.5;
+const KAISER_ALPHA: f32 = 4.0;
+
+fn bessel_i0(x: f32) -> f32 {
+    var sum = 1.0;
+    var term = 1.0;
+    let q = x * x * 0.25;
+    for (var k = 1; k < 16; k++) {
+        term *= q / f32(k * k);
+        sum += term;
+    }
+    return sum;
+}
+
+// d は縮小元のピクセル単位の中心からの距離
+fn filter_weight(d: f32, scale: f32) -> f32 {
+    if (FILTER == 0u) {
+        let half_scale = scale * 0.5;
+        return max(0.0, min(d + 0.5, half_scale) - max(d - 0.5, -half_scale));
+    }
+
+    let t = d / scale;
+    if (abs(t) >= KAISER_WIDTH) {
+        return 0.0;
+    }
+    let r = t / KAISER_WIDTH;
+    let window = bessel_i0(KAISER_ALPHA * sqrt(1.0 - r * r)) / bessel_i0(KAISER_ALPHA);
+    var sinc = 1.0;
+    if (abs(t) > 0.00001) {
+        sinc = sin(PI * t) / (PI * t);
+    }
+    return sinc * window;
+}
+
+fn to_linear(col: vec4<f32>) -> vec4<f32> {
+    let c = max(col.rgb, vec3<f32>(0.0));
+    let low = c / 12.92;
+    let high = pow((c + 0.055) / 1.055, vec3<f32>(2.4));
+    return vec4<f32>(select(high, low, c <= vec3<f32>(0.04045)), col.a);
+}
+fn to_gamma(col: vec4<f32>) -> vec4<f32> {
+    let c = max(col.rgb, vec3<f32>(0.0));
+    let low = c * 12.92;
+    let high = 1.055 * pow(c, vec3<f32>(1.0 / 2.4)) - 0.055;
+    return vec4<f32>(select(high, low, c <= vec3<f32>(0.0031308)), col.a);
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
+    let dist_size = textureDimensions(DistTex);
+    if (param.x >= dist_size.x || param.y >= dist_size.y) {
+        return;
+    }
+    let src_size = textureDimensions(SrcTex);
+
+    let scale = vec2<f32>(src_size) / vec2<f32>(dist_size);
+    let center = (vec2<f32>(param.xy) + 0.5) * scale;
+    var radius = scale * 0.5;
+    if (FILTER != 0u) {
+        radius = scale * KAISER_WIDTH;
+    }
+    let begin = max(vec2<i32>(floor(center - radius)), vec2<i32>(0));
+    let end = min(vec2<i32>(ceil(center + radius)), vec2<i32>(src_size));
+
+    var sum = vec4<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var y = begin.y; y < end.y; y++) {
+        let weight_y = filter_weight(f32(y) + 0.5 - center.y, scale.y);
+        for (var x = begin.x; x < end.x; x++) {
+            let weight = weight_y * filter_weight(f32(x) + 0.5 - center.x, scale.x);
+            var col = textureLoad(SrcTex, vec2<i32>(x, y), 0);
+            if (GAMMA_AWARE) {
+                col = to_linear(col);
+            }
+            sum += col * weight;
+            weight_sum += weight;
+        }
+    }
+
+    var result = sum / weight_sum;
+    if (GAMMA_AWARE) {
+        result = to_gamma(result);
+    }
+    textureStore(DistTex, vec2<i32>(param.xy), result);
+}
+"#;
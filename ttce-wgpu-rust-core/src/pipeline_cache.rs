@@ -0,0 +1,74 @@
+use std::{fs, path::Path};
+
+use crate::{
+    debug_log,
+    tex_trans_core_engine::{TTCEWgpuError, TTCEWgpuErrorRecord, TexTransCoreEngineDevice},
+};
+
+impl TexTransCoreEngineDevice {
+    /// wgpu::PipelineCache を生成し、以降の compute pipeline の生成に使用する。
+    /// ファイルが存在しない場合や、別のアダプターやドライバーで作られたものだった場合は空のキャッシュから始まる。
+    /// バックエンドが対応していない場合は何もせず false が返る。
+    /// 登録済みのシェーダーのパイプラインにはキャッシュが使われないため、シェーダーやフォーマットコンバーターを登録した後は InvalidArgument になる。
+    ///
+    /// # Safety
+    /// path のファイルは save_pipeline_cache_to_file で保存されたものか、存在しないものでないといけない。
+    pub unsafe fn load_pipeline_cache_from_file(
+        &mut self,
+        path: &Path,
+    ) -> Result<bool, TTCEWgpuErrorRecord> {
+        if !self.compute_shader.is_empty() {
+            return Err(TTCEWgpuError::InvalidArgument
+                .with_message("pipeline cache must be loaded before registering shaders"));
+        }
+        if !self
+            .device
+            .features()
+            .contains(wgpu::Features::PIPELINE_CACHE)
+        {
+            debug_log("pipeline cache is not supported in this device");
+            return Ok(false);
+        }
+
+        let data = match fs::read(path) {
+            Ok(data) => Some(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(TTCEWgpuError::FileIO.with_message(format!(
+                    "{} : {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let pipeline_cache = self
+            .device
+            .create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: Some("ttce pipeline cache"),
+                data: data.as_deref(),
+                fallback: true,
+            });
+        self.pipeline_cache = Some(pipeline_cache);
+        Ok(true)
+    }
+
+    /// load_pipeline_cache_from_file で生成したキャッシュの内容を保存する。キャッシュが無い場合は何もせず false が返る。
+    pub fn save_pipeline_cache_to_file(&self, path: &Path) -> Result<bool, TTCEWgpuErrorRecord> {
+        let Some(data) = self.pipeline_cache.as_ref().and_then(|c| c.get_data()) else {
+            return Ok(false);
+        };
+
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, data)
+            .and_then(|_| fs::rename(&temp_path, path))
+            .map_err(|e| {
+                TTCEWgpuError::FileIO.with_message(format!("{} : {}", path.display(), e))
+            })?;
+        Ok(true)
+    }
+
+    pub(crate) fn pipeline_cache(&self) -> Option<&wgpu::PipelineCache> {
+        self.pipeline_cache.as_ref()
+    }
+}
@@ -1,32 +1,108 @@
-use std::{collections::HashMap, ops::Deref};
+use std::{
+    collections::HashMap,
+    ops::Deref,
+    sync::{Arc, Mutex, Weak},
+};
 
 use crate::{
-    compute_shader::{AsTypeStr, TTBindingType, TTComputeShader, TTComputeShaderID, WorkGroupSize},
+    color_space::TTColorSpace,
+    compute_shader::{
+        AsTypeStr, TTBindingPoint, TTBindingType, TTComputeShader, TTComputeShaderEntry,
+        TTComputeShaderID, TTComputeShaderLayout, WorkGroupSize,
+    },
+    download_ticket::{DownloadCompletedCallback, TTDownloadTicket},
+    render_texture_pool::RenderTexturePool,
     storage_buffer::TTStorageBuffer,
     tex_trans_core_engine::{
-        RequestFormat, TTRtRequestDescriptor, TexTransCoreEngineContext, TexTransCoreEngineDevice,
+        map_read_and_wait, RequestFormat, TTCEWgpuError, TTRtDimension, TTRtRequestDescriptor,
+        TexTransCoreEngineContext, TexTransCoreEngineDevice,
     },
-    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+    TTChannelMapping, TexTransCoreTextureChannel, TexTransCoreTextureFormat,
 };
 
+/// フォーマットかチャンネルのどちらか一方が異なる組み合わせ。 mapping はチャンネルが変わる場合のみ意味を持つ。
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct ConvertTextureFormat {
     pub from: wgpu::TextureFormat,
     pub to: wgpu::TextureFormat,
+    pub mapping: TTChannelMapping,
 }
 
+/// ドロップされたときにデバイスの RenderTexturePool に返却される。
+/// コンテキストから取得したものは、そのコンテキストに積まれた command が送信されるまで返却を遅らせる。
 #[derive(Debug)]
 pub struct TTRenderTexture {
     pub texture: wgpu::Texture,
+    pub(crate) pool: Weak<Mutex<RenderTexturePool>>,
+    pub(crate) pending_recycle: Weak<Mutex<Vec<wgpu::Texture>>>,
+    pub(crate) color_space: TTColorSpace,
+}
+impl Drop for TTRenderTexture {
+    fn drop(&mut self) {
+        // 未送信の command が残ったまま他のコンテキストに渡ると、そちらの queue.write_texture が先に実行されてしまう
+        if let Some(pending) = self.pending_recycle.upgrade() {
+            let mut pending = pending.lock().unwrap_or_else(|e| e.into_inner());
+            pending.push(self.texture.clone());
+            return;
+        }
+        if let Some(pool) = self.pool.upgrade() {
+            let mut pool = pool.lock().unwrap_or_else(|e| e.into_inner());
+            pool.recycle(self.texture.clone());
+        }
+    }
 }
 impl TTRenderTexture {
-    pub(crate) fn get_request_descriptor(&self) -> TTRtRequestDescriptor {
-        let format = Self::from_wgpu_texture_format(self.format()).unwrap();
-        TTRtRequestDescriptor {
-            width: self.width(),
-            height: self.height(),
-            format: RequestFormat::Manual(format.0, format.1),
+    /// mip_level のミップの解像度
+    pub fn mip_size(&self, mip_level: u32) -> wgpu::Extent3d {
+        self.size().mip_level_size(mip_level, self.dimension())
+    }
+    pub(crate) fn check_mip_level(&self, mip_level: u32) -> Result<(), TTCEWgpuError> {
+        if mip_level >= self.mip_level_count() {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
+        Ok(())
+    }
+    /// mip_level のミップのレイヤーの数、 3D の場合は奥行きもミップで縮小される。
+    pub fn layer_count(&self, mip_level: u32) -> u32 {
+        self.mip_size(mip_level).depth_or_array_layers
+    }
+    pub(crate) fn check_layer(&self, mip_level: u32, layer: u32) -> Result<(), TTCEWgpuError> {
+        self.check_mip_level(mip_level)?;
+        if layer >= self.layer_count(mip_level) {
+            return Err(TTCEWgpuError::InvalidArgument);
         }
+        Ok(())
+    }
+    /// region がこのテクスチャーの範囲に収まっているか、幅か高さが 0 の場合も InvalidArgument になる。
+    pub(crate) fn check_region(&self, region: &TTTextureRegion) -> Result<(), TTCEWgpuError> {
+        self.check_layer(region.mip_level, region.layer)?;
+        let mip_size = self.mip_size(region.mip_level);
+        let in_range = |origin: u32, length: u32, size: u32| {
+            length != 0 && origin.checked_add(length).is_some_and(|end| end <= size)
+        };
+        if !in_range(region.x, region.width, mip_size.width)
+            || !in_range(region.y, region.height, mip_size.height)
+        {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
+        Ok(())
+    }
+    /// region がミップ 0 の全体を指していて、コンバーターで直接読み書きできるかどうか。
+    fn is_whole_single_layer_2d(&self, region: &TTTextureRegion) -> bool {
+        self.is_single_layer_2d() && *region == TTTextureRegion::whole(self, 0, 0)
+    }
+    /// 配列でない 2D のテクスチャーかどうか、コンバーターなどの内部のシェーダーはこれにしか書き込めない。
+    pub(crate) fn is_single_layer_2d(&self) -> bool {
+        self.dimension() == wgpu::TextureDimension::D2 && self.depth_or_array_layers() == 1
+    }
+
+    /// プールから再利用されたものも含め、取得した時点では Linear になっている。
+    pub fn color_space(&self) -> TTColorSpace {
+        self.color_space
+    }
+    /// 印を付け替えるのみで中身は変換しない、変換する場合は convert_color_space を使うように。
+    pub fn set_color_space(&mut self, color_space: TTColorSpace) {
+        self.color_space = color_space;
     }
 
     pub fn tt_format(&self) -> Option<(TexTransCoreTextureFormat, TexTransCoreTextureChannel)> {
@@ -137,6 +213,16 @@ impl TTRenderTexture {
         }
     }
 
+    /// to_naga_storage_texture_format の逆、 TexTransCore のフォーマットで表せないものは None になる。
+    pub(crate) fn from_naga_storage_texture_format(
+        format: naga::StorageFormat,
+    ) -> Option<wgpu::TextureFormat> {
+        CONVERTIBLE_FORMAT
+            .into_iter()
+            .flat_map(|f| CONVERTIBLE_CHANNEL.map(|c| (f, c)))
+            .find(|(f, c)| Self::to_naga_storage_texture_format(*f, *c) == format)
+            .map(|(f, c)| Self::to_wgpu_texture_format(f, c))
+    }
     pub(crate) fn to_naga_storage_texture_format(
         format: TexTransCoreTextureFormat,
         channel: TexTransCoreTextureChannel,
@@ -184,6 +270,46 @@ impl TTRenderTexture {
         }
     }
 }
+/// テクスチャーの mip_level のミップの、 layer 番目のレイヤー上の矩形。 3D の場合 layer は奥行きの位置を指す。
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TTTextureRegion {
+    pub mip_level: u32,
+    pub layer: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+impl TTTextureRegion {
+    /// mip_level のミップの layer 番目のレイヤー全体
+    pub fn whole(render_texture: &TTRenderTexture, mip_level: u32, layer: u32) -> Self {
+        let mip_size = render_texture.mip_size(mip_level);
+        TTTextureRegion {
+            mip_level,
+            layer,
+            x: 0,
+            y: 0,
+            width: mip_size.width,
+            height: mip_size.height,
+        }
+    }
+    fn origin(&self) -> wgpu::Origin3d {
+        wgpu::Origin3d {
+            x: self.x,
+            y: self.y,
+            z: self.layer,
+        }
+    }
+    fn extent(&self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: 1,
+        }
+    }
+}
+
 impl Deref for TTRenderTexture {
     type Target = wgpu::Texture;
 
@@ -194,56 +320,250 @@ impl Deref for TTRenderTexture {
 
 // Upload Download Copy Util
 impl TexTransCoreEngineContext<'_> {
-    pub fn copy_texture(&mut self, dist: &TTRenderTexture, src: &TTRenderTexture) {
-        if dist.width() != src.width() {
-            panic!("Size Different!!!")
+    pub fn copy_texture(
+        &mut self,
+        dist: &TTRenderTexture,
+        src: &TTRenderTexture,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        if dist.width() != src.width() || dist.height() != src.height() {
+            return Err(TTCEWgpuError::SizeMismatch);
+        }
+        if dist.mip_level_count() != src.mip_level_count()
+            || dist.depth_or_array_layers() != src.depth_or_array_layers()
+        {
+            return Err(TTCEWgpuError::SizeMismatch);
+        }
+        if dist.dimension() != src.dimension() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch);
+        }
+        if dist.format() != src.format() {
+            return Err(TTCEWgpuError::UnsupportedFormat);
+        }
+
+        for mip_level in 0..dist.mip_level_count() {
+            self.copy_mip(dist, src, mip_level);
+        }
+        Ok(())
+    }
+
+    /// src の src_region を dist の dist_region にコピーする。
+    /// 二つの領域の幅と高さは一致していないといけず、 copy_texture と同じく形式の変換は行えない。
+    /// 同じテクスチャーの同じミップとレイヤー内でのコピーは、一時的なテクスチャーを経由する。
+    pub fn copy_texture_region(
+        &mut self,
+        dist: &TTRenderTexture,
+        dist_region: TTTextureRegion,
+        src: &TTRenderTexture,
+        src_region: TTTextureRegion,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        dist.check_region(&dist_region)?;
+        src.check_region(&src_region)?;
+        if dist_region.width != src_region.width || dist_region.height != src_region.height {
+            return Err(TTCEWgpuError::SizeMismatch);
         }
-        if dist.height() != src.height() {
-            panic!("Size Different!!!")
+        if dist.format() != src.format() {
+            return Err(TTCEWgpuError::UnsupportedFormat);
+        }
+
+        // copy_texture_to_texture は同じサブリソース内でのコピーができない
+        if dist.texture == src.texture
+            && dist_region.mip_level == src_region.mip_level
+            && dist_region.layer == src_region.layer
+        {
+            let (format, channel) = src.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
+            let temp = self.get_temporary_render_texture(&TTRtRequestDescriptor {
+                width: src_region.width,
+                height: src_region.height,
+                format: RequestFormat::Manual(format, channel),
+                mip_level_count: 1,
+                dimension: TTRtDimension::D2,
+                depth_or_array_layers: 1,
+            });
+            let temp_region = TTTextureRegion::whole(&temp, 0, 0);
+            self.copy_rect(&temp, &temp_region, src, &src_region);
+            self.copy_rect(dist, &dist_region, &temp, &temp_region);
+        } else {
+            self.copy_rect(dist, &dist_region, src, &src_region);
         }
+        Ok(())
+    }
+
+    // 同じフォーマットで、 region は範囲内かつ同じ大きさでないといけない。
+    fn copy_rect(
+        &mut self,
+        dist: &TTRenderTexture,
+        dist_region: &TTTextureRegion,
+        src: &TTRenderTexture,
+        src_region: &TTTextureRegion,
+    ) {
+        let encoder = self.get_command_encoder_as_mut();
+        encoder.copy_texture_to_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: src,
+                mip_level: src_region.mip_level,
+                origin: src_region.origin(),
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyTextureInfo {
+                texture: dist,
+                mip_level: dist_region.mip_level,
+                origin: dist_region.origin(),
+                aspect: wgpu::TextureAspect::All,
+            },
+            src_region.extent(),
+        );
+    }
+
+    // 同じフォーマットで、 src と dist の mip_level のミップの解像度とレイヤー数が同じでないといけない。
+    fn copy_mip(&mut self, dist: &TTRenderTexture, src: &TTRenderTexture, mip_level: u32) {
+        let copy_size = src.mip_size(mip_level);
+        let encoder = self.get_command_encoder_as_mut();
+
+        encoder.copy_texture_to_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: src,
+                mip_level,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyTextureInfo {
+                texture: dist,
+                mip_level,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            copy_size,
+        );
+    }
 
+    // 同じフォーマットで、 src と dist のそれぞれのミップの解像度が同じでないといけない。レイヤー一枚分だけをコピーする。
+    fn copy_layer(
+        &mut self,
+        dist: &TTRenderTexture,
+        dist_mip_level: u32,
+        dist_layer: u32,
+        src: &TTRenderTexture,
+        src_mip_level: u32,
+        src_layer: u32,
+    ) {
+        let copy_size = src.mip_size(src_mip_level);
         let encoder = self.get_command_encoder_as_mut();
 
         encoder.copy_texture_to_texture(
-            src.as_image_copy(),
-            dist.as_image_copy(),
+            wgpu::TexelCopyTextureInfo {
+                texture: src,
+                mip_level: src_mip_level,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: src_layer,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyTextureInfo {
+                texture: dist,
+                mip_level: dist_mip_level,
+                origin: wgpu::Origin3d {
+                    x: 0,
+                    y: 0,
+                    z: dist_layer,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
             wgpu::Extent3d {
-                width: dist.width(),
-                height: dist.height(),
+                width: copy_size.width,
+                height: copy_size.height,
                 depth_or_array_layers: 1,
             },
         );
     }
 
-    fn convert_to_copy(&mut self, dist: &TTRenderTexture, src: &TTRenderTexture) {
-        let converter_id = self
-            .engine
-            .converter_id
-            .get(&ConvertTextureFormat {
-                from: src.format(),
-                to: dist.format(),
-            })
-            .unwrap();
-
-        // println!("{:?}", converter_id);
-
-        let mut converter_handler = self.get_compute_handler(converter_id).unwrap();
-
-        let src_index = converter_handler.get_bind_index("SrcTex").unwrap();
-        converter_handler
-            .set_render_texture(src_index, src)
-            .unwrap();
-        let dist_index = converter_handler.get_bind_index("DistTex").unwrap();
-        converter_handler
-            .set_render_texture(dist_index, dist)
-            .unwrap();
-
-        let wg_size = converter_handler.get_work_group_size();
-        converter_handler.dispatch(
-            (dist.width() / wg_size.x).max(1),
-            (dist.height() / wg_size.y).max(1),
-            1,
-        );
+    /// src を dist のフォーマットとチャンネルに変換して書き込む。
+    /// 配列でない 2D の同じ大きさのもの同士で、 mip 0 のみが変換される。
+    pub fn convert_texture(
+        &mut self,
+        dist: &TTRenderTexture,
+        src: &TTRenderTexture,
+        mapping: TTChannelMapping,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        if dist.width() != src.width() || dist.height() != src.height() {
+            return Err(TTCEWgpuError::SizeMismatch);
+        }
+        if !dist.is_single_layer_2d() || !src.is_single_layer_2d() {
+            return Err(TTCEWgpuError::TextureDimensionMismatch);
+        }
+
+        if dist.format() == src.format() && mapping == TTChannelMapping::Default {
+            self.copy_layer(dist, 0, 0, src, 0, 0);
+            return Ok(());
+        }
+        self.convert_to_copy_with(dist, src, mapping)
+    }
+
+    fn convert_to_copy(
+        &mut self,
+        dist: &TTRenderTexture,
+        src: &TTRenderTexture,
+    ) -> Result<(), TTCEWgpuError> {
+        self.convert_to_copy_with(dist, src, TTChannelMapping::Default)
+    }
+
+    fn convert_to_copy_with(
+        &mut self,
+        dist: &TTRenderTexture,
+        src: &TTRenderTexture,
+        mapping: TTChannelMapping,
+    ) -> Result<(), TTCEWgpuError> {
+        let (src_format, _) = src.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
+        let (_, dist_channel) = dist.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
+
+        // フォーマットとチャンネルを同時に変えるコンバーターは無いため、 src のフォーマットのままチャンネルを変えてからフォーマットを変える
+        let channel_converted_format =
+            TTRenderTexture::to_wgpu_texture_format(src_format, dist_channel);
+        if src.format() != channel_converted_format && dist.format() != channel_converted_format {
+            let channel_converted = self.get_temporary_render_texture(&TTRtRequestDescriptor {
+                width: dist.width(),
+                height: dist.height(),
+                format: RequestFormat::Manual(src_format, dist_channel),
+                mip_level_count: 1,
+                dimension: TTRtDimension::D2,
+                depth_or_array_layers: 1,
+            });
+            self.dispatch_converter(&channel_converted, src, mapping)?;
+            return self.dispatch_converter(dist, &channel_converted, TTChannelMapping::Default);
+        }
+        self.dispatch_converter(dist, src, mapping)
+    }
+
+    fn dispatch_converter(
+        &mut self,
+        dist: &TTRenderTexture,
+        src: &TTRenderTexture,
+        mapping: TTChannelMapping,
+    ) -> Result<(), TTCEWgpuError> {
+        let Some(converter_id) = self.engine.converter_id.get(&ConvertTextureFormat {
+            from: src.format(),
+            to: dist.format(),
+            mapping,
+        }) else {
+            return Err(TTCEWgpuError::UnsupportedFormat);
+        };
+
+        let mut converter_handler = self.get_compute_handler(converter_id)?;
+
+        let src_index = converter_handler
+            .get_bind_index("SrcTex")
+            .ok_or(TTCEWgpuError::BindingNotFound)?;
+        converter_handler.set_render_texture(src_index, src)?;
+        let dist_index = converter_handler
+            .get_bind_index("DistTex")
+            .ok_or(TTCEWgpuError::BindingNotFound)?;
+        converter_handler.set_render_texture(dist_index, dist)?;
+
+        converter_handler.dispatch_for_texture(dist)
     }
 
     pub fn upload_texture(
@@ -251,266 +571,644 @@ impl TexTransCoreEngineContext<'_> {
         target: &TTRenderTexture,
         data: &[u8],
         data_format: TexTransCoreTextureFormat,
-    ) {
+    ) -> Result<(), TTCEWgpuError> {
+        self.upload_texture_mip(target, 0, data, data_format)
+    }
+
+    /// data の長さは mip_level のミップの解像度に合っていないといけない。
+    pub fn upload_texture_mip(
+        &mut self,
+        target: &TTRenderTexture,
+        mip_level: u32,
+        data: &[u8],
+        data_format: TexTransCoreTextureFormat,
+    ) -> Result<(), TTCEWgpuError> {
+        self.upload_texture_layer(target, mip_level, 0, data, data_format)
+    }
+
+    /// 2D 配列の場合は layer 番目のレイヤー、 3D の場合は奥行き layer の位置の一枚に書き込む。
+    /// data の長さは mip_level のミップの一枚分の解像度に合っていないといけない。
+    pub fn upload_texture_layer(
+        &mut self,
+        target: &TTRenderTexture,
+        mip_level: u32,
+        layer: u32,
+        data: &[u8],
+        data_format: TexTransCoreTextureFormat,
+    ) -> Result<(), TTCEWgpuError> {
+        target.check_layer(mip_level, layer)?;
+        self.upload_texture_region(
+            target,
+            TTTextureRegion::whole(target, mip_level, layer),
+            data,
+            data_format,
+        )
+    }
+
+    /// region の矩形のみに書き込む。 data の長さは region の 幅 * 高さ * ピクセル当たりのバイト数 と一致しないといけない。
+    pub fn upload_texture_region(
+        &mut self,
+        target: &TTRenderTexture,
+        region: TTTextureRegion,
+        data: &[u8],
+        data_format: TexTransCoreTextureFormat,
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        target.check_region(&region)?;
         let (target_format, target_channel) =
-            TTRenderTexture::from_wgpu_texture_format(target.format()).unwrap();
+            target.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
         let pixel_par_byte = TTRenderTexture::to_wgpu_texture_format(data_format, target_channel)
             .block_copy_size(None)
-            .unwrap();
+            .ok_or(TTCEWgpuError::UnsupportedFormat)?;
 
-        let data_size = target.width() * target.height() * pixel_par_byte;
+        let data_size = region.width as usize * region.height as usize * pixel_par_byte as usize;
 
-        if data.len() as u32 != data_size {
-            panic!("Data Size is Different")
+        if data.len() != data_size {
+            return Err(TTCEWgpuError::SizeMismatch);
         }
 
+        // queue.write_texture は bytes_per_row のアライメントの制約がないため、詰められたデータをそのまま渡せる
         let data_layout = wgpu::TexelCopyBufferLayout {
             offset: 0,
-            bytes_per_row: Some(target.width() * pixel_par_byte),
+            bytes_per_row: Some(region.width * pixel_par_byte),
             rows_per_image: None,
         };
-        let data_size = wgpu::Extent3d {
-            width: target.width(),
-            height: target.height(),
-            depth_or_array_layers: 1,
-        };
 
+        // queue.write_texture は積まれている command より先に実行されるため、先に投げておく
+        self.send_command();
         if data_format == target_format {
-            self.send_command();
-            self.engine
-                .queue
-                .write_texture(target.as_image_copy(), data, data_layout, data_size);
+            self.engine.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: target,
+                    mip_level: region.mip_level,
+                    origin: region.origin(),
+                    aspect: wgpu::TextureAspect::All,
+                },
+                data,
+                data_layout,
+                region.extent(),
+            );
         } else {
-            let copy_src = self.get_render_texture_with(&TTRtRequestDescriptor {
-                width: target.width(),
-                height: target.height(),
-                format: RequestFormat::Manual(data_format, target_channel),
-            });
+            let region_desc = |format| TTRtRequestDescriptor {
+                width: region.width,
+                height: region.height,
+                format: RequestFormat::Manual(format, target_channel),
+                mip_level_count: 1,
+                dimension: TTRtDimension::D2,
+                depth_or_array_layers: 1,
+            };
+            let copy_src = self.get_temporary_render_texture(&region_desc(data_format));
 
-            self.engine
-                .queue
-                .write_texture(copy_src.as_image_copy(), data, data_layout, data_size);
+            self.engine.queue.write_texture(
+                copy_src.as_image_copy(),
+                data,
+                data_layout,
+                region.extent(),
+            );
 
-            self.convert_to_copy(target, &copy_src);
+            if target.is_whole_single_layer_2d(&region) {
+                self.convert_to_copy(target, &copy_src)?;
+            } else {
+                // コンバーターは配列でない 2D の mip 0 の全体にしか書き込めないため、一度変換してからコピーする
+                let converted = self.get_temporary_render_texture(&region_desc(target_format));
+                self.convert_to_copy(&converted, &copy_src)?;
+                self.copy_rect(
+                    target,
+                    &region,
+                    &converted,
+                    &TTTextureRegion::whole(&converted, 0, 0),
+                );
+            }
         }
         self.send_command();
+        Ok(())
     }
 
+    /// dist にはパディングのない詰められた状態で書き込まれ、長さは 幅 * 高さ * ピクセル当たりのバイト数 と一致しないといけない。
     pub async fn download_texture(
         &mut self,
         target: &TTRenderTexture,
         download_format: Option<TexTransCoreTextureFormat>,
-    ) -> Option<wgpu::Buffer> {
-        let (target_format, target_channel) =
-            TTRenderTexture::from_wgpu_texture_format(target.format()).unwrap();
+        dist: &mut [u8],
+    ) -> Result<(), TTCEWgpuError> {
+        self.download_texture_mip(target, 0, download_format, dist)
+            .await
+    }
 
-        let is_format_different = if let Some(f) = download_format {
-            f != target_format
-        } else {
-            false
-        };
+    /// dist の長さは mip_level のミップの解像度に合っていないといけない。
+    pub async fn download_texture_mip(
+        &mut self,
+        target: &TTRenderTexture,
+        mip_level: u32,
+        download_format: Option<TexTransCoreTextureFormat>,
+        dist: &mut [u8],
+    ) -> Result<(), TTCEWgpuError> {
+        self.download_texture_layer(target, mip_level, 0, download_format, dist)
+            .await
+    }
 
-        let download_pixel_par_byte = if let Some(df) = download_format {
-            TTRenderTexture::to_wgpu_texture_format(df, target_channel)
-                .block_copy_size(None)
-                .unwrap()
-        } else {
-            TTRenderTexture::to_wgpu_texture_format(target_format, target_channel)
-                .block_copy_size(None)
-                .unwrap()
-        };
+    /// upload_texture_layer と同じく一枚分を読み出す。
+    pub async fn download_texture_layer(
+        &mut self,
+        target: &TTRenderTexture,
+        mip_level: u32,
+        layer: u32,
+        download_format: Option<TexTransCoreTextureFormat>,
+        dist: &mut [u8],
+    ) -> Result<(), TTCEWgpuError> {
+        target.check_layer(mip_level, layer)?;
+        self.download_texture_region(
+            target,
+            TTTextureRegion::whole(target, mip_level, layer),
+            download_format,
+            dist,
+        )
+        .await
+    }
+
+    /// region の矩形のみを読み出す。 dist の長さは region の 幅 * 高さ * ピクセル当たりのバイト数 と一致しないといけない。
+    pub async fn download_texture_region(
+        &mut self,
+        target: &TTRenderTexture,
+        region: TTTextureRegion,
+        download_format: Option<TexTransCoreTextureFormat>,
+        dist: &mut [u8],
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        target.check_region(&region)?;
+        let layout = Self::download_layout(target, &region, download_format)?;
+        if dist.len() as u64 != layout.data_size() {
+            return Err(TTCEWgpuError::SizeMismatch);
+        }
         // 個々の手前で command buffer を投げておかないと前回 の send command までのデータしか手に入らない ... なぜ？
         self.send_command();
 
-        let read_back_buffer = self.engine.device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("read-back-buffer"),
-            size: (target.width() * target.height() * download_pixel_par_byte) as u64,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-
-        if is_format_different {
-            let mut desc = target.get_request_descriptor();
-            desc.format = RequestFormat::Manual(download_format.unwrap(), target_channel);
-            let convert_temp = self.get_render_texture_with(&desc);
-            self.convert_to_copy(&convert_temp, target);
-            self.download_impl(&convert_temp, &read_back_buffer, download_pixel_par_byte);
-        } else {
-            self.download_impl(target, &read_back_buffer, download_pixel_par_byte);
-        };
+        let read_back_buffer = self.engine.take_staging_buffer(layout.buffer_size());
+        if let Err(e) =
+            self.encode_download(target, &region, download_format, &read_back_buffer, &layout)
+        {
+            self.engine.recycle_staging_buffer(read_back_buffer);
+            return Err(e);
+        }
         self.send_command();
 
         // let timer = Instant::now();
         let rb_buffer_slice = read_back_buffer.slice(..);
-        let (sender, receiver) = tokio::sync::oneshot::channel();
-        rb_buffer_slice.map_async(wgpu::MapMode::Read, move |v| {
-            sender.send(v).unwrap();
-        });
+        // マップに失敗したものやタイムアウトしたものは状態がわからないため再利用しない
+        map_read_and_wait(&self.engine.device, &rb_buffer_slice)?;
 
-        self.engine
-            .device
-            .poll(wgpu::Maintain::wait())
-            .panic_on_timeout();
+        // let end = timer.elapsed();
+        // debug_log(&format!("readback-{}ms", end.as_millis()));
+        let result = layout.copy_to_packed(&rb_buffer_slice.get_mapped_range(), dist);
+        read_back_buffer.unmap();
+        self.engine.recycle_staging_buffer(read_back_buffer);
+        result
+    }
 
-        if receiver.await.unwrap().is_ok() {
-            // let end = timer.elapsed();
-            // debug_log(&format!("readback-{}ms", end.as_millis()));
-            Some(read_back_buffer)
-        } else {
-            None
+    /// download_texture と違い GPU の完了を待たずに返る。 TTDownloadTicket の poll や wait で完了を確認してから読み出すように。
+    /// download_texture_region と同じく region の矩形のみを読み出す。
+    /// 読み戻し用のバッファーはデバイスでプールされ、 ticket がドロップされたときに返却される。
+    pub fn request_download_texture(
+        &mut self,
+        target: &TTRenderTexture,
+        region: TTTextureRegion,
+        download_format: Option<TexTransCoreTextureFormat>,
+        on_completed: Option<DownloadCompletedCallback>,
+    ) -> Result<TTDownloadTicket, TTCEWgpuError> {
+        self.check_device_lost()?;
+        target.check_region(&region)?;
+        let layout = Self::download_layout(target, &region, download_format)?;
+        self.send_command();
+
+        let read_back_buffer = self.engine.take_staging_buffer(layout.buffer_size());
+        if let Err(e) =
+            self.encode_download(target, &region, download_format, &read_back_buffer, &layout)
+        {
+            self.engine.recycle_staging_buffer(read_back_buffer);
+            return Err(e);
+        }
+        self.send_command();
+
+        Ok(TTDownloadTicket::new(
+            self.engine.device.clone(),
+            read_back_buffer,
+            layout,
+            self.engine.staging_buffer_pool(),
+            on_completed,
+        ))
+    }
+
+    fn download_layout(
+        target: &TTRenderTexture,
+        region: &TTTextureRegion,
+        download_format: Option<TexTransCoreTextureFormat>,
+    ) -> Result<DownloadLayout, TTCEWgpuError> {
+        let (target_format, target_channel) =
+            target.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
+
+        let download_pixel_par_byte = TTRenderTexture::to_wgpu_texture_format(
+            download_format.unwrap_or(target_format),
+            target_channel,
+        )
+        .block_copy_size(None)
+        .ok_or(TTCEWgpuError::UnsupportedFormat)?;
+
+        Ok(DownloadLayout::new(
+            region.width,
+            region.height,
+            download_pixel_par_byte,
+        ))
+    }
+
+    // フォーマットが違う場合は変換してから read_back_buffer にコピーする。
+    fn encode_download(
+        &mut self,
+        target: &TTRenderTexture,
+        region: &TTTextureRegion,
+        download_format: Option<TexTransCoreTextureFormat>,
+        read_back_buffer: &wgpu::Buffer,
+        layout: &DownloadLayout,
+    ) -> Result<(), TTCEWgpuError> {
+        let (target_format, target_channel) =
+            target.tt_format().ok_or(TTCEWgpuError::UnsupportedFormat)?;
+
+        match download_format {
+            Some(download_format) if download_format != target_format => {
+                let region_desc = |format| TTRtRequestDescriptor {
+                    width: region.width,
+                    height: region.height,
+                    format: RequestFormat::Manual(format, target_channel),
+                    mip_level_count: 1,
+                    dimension: TTRtDimension::D2,
+                    depth_or_array_layers: 1,
+                };
+
+                // コンバーターは配列でない 2D の mip 0 の全体からしか読み込めないため、一度コピーしてから変換する
+                let source_temp;
+                let source = if target.is_whole_single_layer_2d(region) {
+                    target
+                } else {
+                    source_temp = self.get_temporary_render_texture(&region_desc(target_format));
+                    self.copy_rect(
+                        &source_temp,
+                        &TTTextureRegion::whole(&source_temp, 0, 0),
+                        target,
+                        region,
+                    );
+                    &source_temp
+                };
+
+                let convert_temp = self.get_temporary_render_texture(&region_desc(download_format));
+                self.convert_to_copy(&convert_temp, source)?;
+                let convert_region = TTTextureRegion::whole(&convert_temp, 0, 0);
+                self.download_impl(&convert_temp, &convert_region, read_back_buffer, layout);
+            }
+            _ => self.download_impl(target, region, read_back_buffer, layout),
         }
+        Ok(())
     }
 
     fn download_impl(
         &mut self,
         render_texture: &TTRenderTexture,
+        region: &TTTextureRegion,
         read_back_buffer: &wgpu::Buffer,
-        download_pixel_par_byte: u32,
+        layout: &DownloadLayout,
     ) {
         let encoder = self.get_command_encoder_as_mut();
         encoder.copy_texture_to_buffer(
             wgpu::TexelCopyTextureInfo {
                 texture: render_texture,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
+                mip_level: region.mip_level,
+                origin: region.origin(),
                 aspect: wgpu::TextureAspect::All,
             },
             wgpu::TexelCopyBufferInfo {
                 buffer: read_back_buffer,
                 layout: wgpu::TexelCopyBufferLayout {
                     offset: 0,
-                    bytes_per_row: Some(render_texture.width() * download_pixel_par_byte),
+                    bytes_per_row: Some(layout.padded_bytes_per_row),
                     rows_per_image: None,
                 },
             },
-            wgpu::Extent3d {
-                width: render_texture.width(),
-                height: render_texture.height(),
-                depth_or_array_layers: render_texture.depth_or_array_layers(),
-            },
+            region.extent(),
         );
     }
 }
 
+/// read back buffer 上のピクセルの並び。
+/// copy_texture_to_buffer は bytes_per_row が COPY_BYTES_PER_ROW_ALIGNMENT の倍数でないといけないため、各行の末尾にパディングが入る。
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct DownloadLayout {
+    pub height: u32,
+    pub bytes_per_row: u32,
+    pub padded_bytes_per_row: u32,
+}
+impl DownloadLayout {
+    pub(crate) fn new(width: u32, height: u32, pixel_par_byte: u32) -> Self {
+        let bytes_per_row = width * pixel_par_byte;
+        let padded_bytes_per_row = bytes_per_row.div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+            * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        DownloadLayout {
+            height,
+            bytes_per_row,
+            padded_bytes_per_row,
+        }
+    }
+    /// パディングを含んだ read back buffer の大きさ
+    pub(crate) fn buffer_size(&self) -> u64 {
+        self.padded_bytes_per_row as u64 * self.height as u64
+    }
+    /// パディングを取り除いた後の大きさ
+    pub(crate) fn data_size(&self) -> u64 {
+        self.bytes_per_row as u64 * self.height as u64
+    }
+    pub(crate) fn copy_to_packed(
+        &self,
+        mapped: &[u8],
+        dist: &mut [u8],
+    ) -> Result<(), TTCEWgpuError> {
+        if dist.len() as u64 != self.data_size() || mapped.len() as u64 != self.buffer_size() {
+            return Err(TTCEWgpuError::SizeMismatch);
+        }
+        if self.bytes_per_row == self.padded_bytes_per_row {
+            dist.copy_from_slice(mapped);
+            return Ok(());
+        }
+
+        let bytes_per_row = self.bytes_per_row as usize;
+        for (dist_row, mapped_row) in dist
+            .chunks_exact_mut(bytes_per_row)
+            .zip(mapped.chunks_exact(self.padded_bytes_per_row as usize))
+        {
+            dist_row.copy_from_slice(&mapped_row[..bytes_per_row]);
+        }
+        Ok(())
+    }
+}
+
 impl TexTransCoreEngineContext<'_> {
+    /// 得られるレンダーテクスチャーはすべて 0 で初期化されている。
     pub fn get_render_texture(
-        &self,
+        &mut self,
         width: u32,
         height: u32,
         channel: TexTransCoreTextureChannel,
     ) -> TTRenderTexture {
-        self.engine.create_render_texture(&TTRtRequestDescriptor {
+        self.get_render_texture_with(&TTRtRequestDescriptor {
             width,
             height,
             format: RequestFormat::AutoWithChannel(channel),
+            mip_level_count: 1,
+            dimension: TTRtDimension::D2,
+            depth_or_array_layers: 1,
         })
     }
-    pub(crate) fn get_render_texture_with(
-        &self,
+    pub fn get_render_texture_with(&mut self, arg_desc: &TTRtRequestDescriptor) -> TTRenderTexture {
+        let (mut render_texture, is_recycled) = self.engine.create_render_texture(arg_desc);
+        render_texture.pending_recycle = self.pending_recycle_weak();
+        if is_recycled {
+            self.clear_recycled_render_texture(&render_texture);
+        }
+        render_texture
+    }
+    // すべて書き込まれる内部の一時的なものは、クリアせずにそのまま使う
+    pub(crate) fn get_temporary_render_texture(
+        &mut self,
         arg_desc: &TTRtRequestDescriptor,
     ) -> TTRenderTexture {
-        self.engine.create_render_texture(arg_desc)
+        let mut render_texture = self.engine.create_render_texture(arg_desc).0;
+        render_texture.pending_recycle = self.pending_recycle_weak();
+        render_texture
+    }
+
+    fn clear_recycled_render_texture(&mut self, render_texture: &TTRenderTexture) {
+        if render_texture.dimension() == wgpu::TextureDimension::D3 {
+            self.clear_render_texture_3d(render_texture);
+            return;
+        }
+        self.clear_render_texture_2d(render_texture, wgpu::Color::TRANSPARENT);
+    }
+    /// 2D のテクスチャーのすべてのミップとレイヤーを、 render pass の load op で color にする。
+    pub(crate) fn clear_render_texture_2d(
+        &mut self,
+        render_texture: &TTRenderTexture,
+        color: wgpu::Color,
+    ) {
+        for mip_level in 0..render_texture.mip_level_count() {
+            for layer in 0..render_texture.depth_or_array_layers() {
+                // render attachment にはミップとレイヤーが一つの view しか使えない
+                let view = render_texture.create_view(&wgpu::TextureViewDescriptor {
+                    dimension: Some(wgpu::TextureViewDimension::D2),
+                    base_mip_level: mip_level,
+                    mip_level_count: Some(1),
+                    base_array_layer: layer,
+                    array_layer_count: Some(1),
+                    ..Default::default()
+                });
+                let encoder = self.get_command_encoder_as_mut();
+                encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("clear-render-texture"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(color),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    timestamp_writes: None,
+                    occlusion_query_set: None,
+                });
+            }
+        }
+    }
+    // 3D テクスチャーは render attachment にできないため、 0 で初期化されているバッファーからコピーする
+    fn clear_render_texture_3d(&mut self, render_texture: &TTRenderTexture) {
+        let pixel_par_byte = render_texture.format().block_copy_size(None).unwrap_or(0);
+        let mip_size = render_texture.mip_size(0);
+        let layout = DownloadLayout::new(mip_size.width, mip_size.height, pixel_par_byte);
+        let zero_buffer = self.engine.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("clear-recycled-render-texture-3d"),
+            size: layout.buffer_size() * mip_size.depth_or_array_layers as u64,
+            usage: wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+
+        for mip_level in 0..render_texture.mip_level_count() {
+            let mip_size = render_texture.mip_size(mip_level);
+            let mip_layout = DownloadLayout::new(mip_size.width, mip_size.height, pixel_par_byte);
+            let encoder = self.get_command_encoder_as_mut();
+            encoder.copy_buffer_to_texture(
+                wgpu::TexelCopyBufferInfo {
+                    buffer: &zero_buffer,
+                    layout: wgpu::TexelCopyBufferLayout {
+                        offset: 0,
+                        bytes_per_row: Some(mip_layout.padded_bytes_per_row),
+                        rows_per_image: Some(mip_size.height),
+                    },
+                },
+                wgpu::TexelCopyTextureInfo {
+                    texture: render_texture,
+                    mip_level,
+                    origin: wgpu::Origin3d::ZERO,
+                    aspect: wgpu::TextureAspect::All,
+                },
+                mip_size,
+            );
+        }
     }
 }
 impl TexTransCoreEngineDevice {
-    pub(crate) fn register_format_convertor(&mut self) {
+    /// コンバーターの ID のみを割り当て、パイプラインは初めて使われるときに作る。
+    pub fn register_format_convertor(&mut self) {
+        for cv in format_convert_table() {
+            let id = TTComputeShaderID::from(self.compute_shader.len() as u32);
+            self.compute_shader
+                .push(TTComputeShaderEntry::FormatConverter(cv));
+            self.converter_id.insert(cv, id);
+        }
+    }
+
+    pub(crate) fn get_or_create_format_converter(
+        &self,
+        key: ConvertTextureFormat,
+    ) -> Arc<TTComputeShader> {
+        let mut pipelines = self
+            .format_converter_pipelines
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(key)
+            .or_insert_with(|| Arc::new(self.create_format_converter(key)))
+            .clone()
+    }
+
+    fn create_format_converter(&self, key: ConvertTextureFormat) -> TTComputeShader {
+        let src_point = TTBindingPoint::new(0, 0);
+        let dist_point = TTBindingPoint::new(0, 1);
         let mut bind_map = HashMap::new();
-        bind_map.insert("SrcTex".to_string(), 0_u32);
-        bind_map.insert("DistTex".to_string(), 1_u32);
+        bind_map.insert("SrcTex".to_string(), src_point);
+        bind_map.insert("DistTex".to_string(), dist_point);
         let mut bind_type = HashMap::new();
-        bind_type.insert(0_u32, TTBindingType::RWRenderTexture);
-        bind_type.insert(1_u32, TTBindingType::RWRenderTexture);
-
-        for cv in FORMAT_TABLE {
-            let from_format = cv.from;
-            let to_format = cv.to;
-            let wgsl_str = FORMAT_CONVERTER_TEMPLATE
-                .replace("$$$FROM$$$", from_format.as_type_str())
-                .replace("$$$TO$$$", to_format.as_type_str());
-
-            let cs_module = self
-                .device
-                .create_shader_module(wgpu::ShaderModuleDescriptor {
-                    label: Some("format convertor shade module"),
-                    source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
-                });
-            let compute_pipeline =
-                self.device
-                    .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-                        label: Some("format convertor compute pipeline"),
-                        layout: None,
-                        module: &cs_module,
-                        entry_point: Some("CSMain"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        cache: None,
-                    });
+        bind_type.insert(
+            src_point,
+            TTBindingType::RWRenderTexture(wgpu::TextureViewDimension::D2),
+        );
+        bind_type.insert(
+            dist_point,
+            TTBindingType::RWRenderTexture(wgpu::TextureViewDimension::D2),
+        );
 
-            let id = TTComputeShaderID::from(self.compute_shader.len() as u32);
-            // println!("{id:?}-/ {wgsl_str}");
-
-            self.compute_shader.push(TTComputeShader {
-                module: cs_module,
-                pipeline: compute_pipeline,
-                binding_map: bind_map.clone(),
-                binding_type: bind_type.clone(),
-                work_group_size: WorkGroupSize { x: 16, y: 16, z: 1 },
+        let wgsl_str = FORMAT_CONVERTER_TEMPLATE
+            .replace("$$$FROM$$$", key.from.as_type_str())
+            .replace("$$$TO$$$", key.to.as_type_str())
+            .replace("$$$MAPPING$$$", key.mapping_expr());
+
+        let cs_module = self
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("format convertor shade module"),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(&wgsl_str)),
             });
+        let compute_pipeline =
+            self.device
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some("format convertor compute pipeline"),
+                    layout: None,
+                    module: &cs_module,
+                    entry_point: Some("CSMain"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    cache: self.pipeline_cache(),
+                });
 
-            self.converter_id.insert(*cv, id);
-        }
+        // 内部のシェーダーはすべてのバインディングを使っているため自動生成のレイアウトでよい
+        let layout = TTComputeShaderLayout::from_auto_layout(&compute_pipeline, &bind_type);
+        TTComputeShader::new(
+            cs_module,
+            compute_pipeline,
+            bind_map,
+            bind_type,
+            WorkGroupSize { x: 16, y: 16, z: 1 },
+            layout,
+        )
     }
 }
 
-pub const FORMAT_TABLE: &[ConvertTextureFormat] = &[
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba8Unorm,
-        to: wgpu::TextureFormat::Rgba16Unorm,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba8Unorm,
-        to: wgpu::TextureFormat::Rgba16Float,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba8Unorm,
-        to: wgpu::TextureFormat::Rgba32Float,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba16Unorm,
-        to: wgpu::TextureFormat::Rgba8Unorm,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba16Unorm,
-        to: wgpu::TextureFormat::Rgba16Float,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba16Unorm,
-        to: wgpu::TextureFormat::Rgba32Float,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba16Float,
-        to: wgpu::TextureFormat::Rgba8Unorm,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba16Float,
-        to: wgpu::TextureFormat::Rgba16Unorm,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba16Float,
-        to: wgpu::TextureFormat::Rgba32Float,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba32Float,
-        to: wgpu::TextureFormat::Rgba8Unorm,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba32Float,
-        to: wgpu::TextureFormat::Rgba16Unorm,
-    },
-    ConvertTextureFormat {
-        from: wgpu::TextureFormat::Rgba32Float,
-        to: wgpu::TextureFormat::Rgba16Float,
-    },
+const CONVERTIBLE_FORMAT: [TexTransCoreTextureFormat; 4] = [
+    TexTransCoreTextureFormat::Byte,
+    TexTransCoreTextureFormat::UShort,
+    TexTransCoreTextureFormat::Half,
+    TexTransCoreTextureFormat::Float,
 ];
+const CONVERTIBLE_CHANNEL: [TexTransCoreTextureChannel; 3] = [
+    TexTransCoreTextureChannel::R,
+    TexTransCoreTextureChannel::RG,
+    TexTransCoreTextureChannel::RGBA,
+];
+
+/// 登録されるコンバーターのすべて。
+/// チャンネルごとのフォーマットの変換と、フォーマットごとのチャンネルの変換からなり、両方を変える場合は二回に分けて変換する。
+pub fn format_convert_table() -> Vec<ConvertTextureFormat> {
+    let to_wgpu = TTRenderTexture::to_wgpu_texture_format;
+    let mut table = Vec::new();
+
+    for channel in CONVERTIBLE_CHANNEL {
+        for from in CONVERTIBLE_FORMAT {
+            for to in CONVERTIBLE_FORMAT.into_iter().filter(|to| *to != from) {
+                table.push(ConvertTextureFormat {
+                    from: to_wgpu(from, channel),
+                    to: to_wgpu(to, channel),
+                    mapping: TTChannelMapping::Default,
+                });
+            }
+        }
+    }
+
+    for format in CONVERTIBLE_FORMAT {
+        for from in CONVERTIBLE_CHANNEL {
+            for to in CONVERTIBLE_CHANNEL.into_iter().filter(|to| *to != from) {
+                let mut push = |mapping| {
+                    table.push(ConvertTextureFormat {
+                        from: to_wgpu(format, from),
+                        to: to_wgpu(format, to),
+                        mapping,
+                    })
+                };
+                push(TTChannelMapping::Default);
+                if from == TexTransCoreTextureChannel::RGBA && to == TexTransCoreTextureChannel::R {
+                    push(TTChannelMapping::Luminance);
+                    push(TTChannelMapping::Alpha);
+                }
+            }
+        }
+    }
+    table
+}
+
+impl ConvertTextureFormat {
+    // textureLoad で読んだ col から書き込む値を作る式
+    fn mapping_expr(&self) -> &'static str {
+        let from_channel = TTRenderTexture::from_wgpu_texture_format(self.from).map(|f| f.1);
+        let to_channel = TTRenderTexture::from_wgpu_texture_format(self.to).map(|f| f.1);
+        match self.mapping {
+            TTChannelMapping::Default => {
+                if from_channel == Some(TexTransCoreTextureChannel::R)
+                    && to_channel != Some(TexTransCoreTextureChannel::R)
+                {
+                    "vec4<f32>(col.r, col.r, col.r, 1.0)"
+                } else {
+                    "col"
+                }
+            }
+            TTChannelMapping::Luminance => {
+                "vec4<f32>(dot(col.rgb, vec3<f32>(0.2126, 0.7152, 0.0722)), 0.0, 0.0, 1.0)"
+            }
+            TTChannelMapping::Alpha => "vec4<f32>(col.a, 0.0, 0.0, 1.0)",
+        }
+    }
+}
 
 pub const FORMAT_CONVERTER_TEMPLATE: &str = r#"
 @group(0) @binding(0)
@@ -521,7 +1219,10 @@ var DistTex: texture_storage_2d<$$$TO$$$,write>;
 @compute @workgroup_size(16, 16, 1)
 fn CSMain(@builtin(global_invocation_id) param: vec3<u32>) {
     let pos = param.xy;
+    if (any(pos >= textureDimensions(DistTex))) {
+        return;
+    }
     let col = textureLoad(SrcTex, pos);
-    textureStore(DistTex, pos, col);
+    textureStore(DistTex, pos, $$$MAPPING$$$);
 }
 "#;
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+/// 既定のプールの上限、 4096x4096 の RGBA Float が 2 枚程度
+pub const DEFAULT_RENDER_TEXTURE_POOL_BUDGET: u64 = 512 * 1024 * 1024;
+
+/// TTRenderTexture がドロップされたときに wgpu::Texture を保持しておき、同じサイズとフォーマットの要求に再利用するためのもの。
+/// 保持しているテクスチャの合計サイズが budget を超えた場合は、古く返却されたものから破棄される。
+#[derive(Debug)]
+pub struct RenderTexturePool {
+    budget: u64,
+    idle: HashMap<RenderTexturePoolKey, Vec<IdleRenderTexture>>,
+    idle_byte: u64,
+    idle_count: u32,
+    return_order: u64,
+
+    hit_count: u64,
+    miss_count: u64,
+    evicted_count: u64,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct RenderTexturePoolKey {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    pub mip_level_count: u32,
+    pub dimension: wgpu::TextureDimension,
+    pub depth_or_array_layers: u32,
+}
+impl RenderTexturePoolKey {
+    pub(crate) fn from_texture(texture: &wgpu::Texture) -> Self {
+        RenderTexturePoolKey {
+            width: texture.width(),
+            height: texture.height(),
+            format: texture.format(),
+            mip_level_count: texture.mip_level_count(),
+            dimension: texture.dimension(),
+            depth_or_array_layers: texture.depth_or_array_layers(),
+        }
+    }
+    fn byte_size(&self) -> u64 {
+        let pixel_par_byte = self.format.block_copy_size(None).unwrap_or(0) as u64;
+        let size = wgpu::Extent3d {
+            width: self.width,
+            height: self.height,
+            depth_or_array_layers: self.depth_or_array_layers,
+        };
+        (0..self.mip_level_count)
+            .map(|mip| {
+                let mip_size = size.mip_level_size(mip, self.dimension);
+                mip_size.width as u64
+                    * mip_size.height as u64
+                    * mip_size.depth_or_array_layers as u64
+                    * pixel_par_byte
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug)]
+struct IdleRenderTexture {
+    return_order: u64,
+    texture: wgpu::Texture,
+}
+
+/// RenderTexturePool の状態、 C# 側から取得するためのもの。
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RenderTexturePoolStatistics {
+    pub budget_byte: u64,
+    pub pooled_byte: u64,
+    pub pooled_count: u32,
+    pub hit_count: u64,
+    pub miss_count: u64,
+    pub evicted_count: u64,
+}
+
+impl RenderTexturePool {
+    /// budget が 0 の場合はプールしない。
+    pub fn new(budget: u64) -> Self {
+        RenderTexturePool {
+            budget,
+            idle: HashMap::new(),
+            idle_byte: 0,
+            idle_count: 0,
+            return_order: 0,
+
+            hit_count: 0,
+            miss_count: 0,
+            evicted_count: 0,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget: u64) {
+        self.budget = budget;
+        self.evict_over_budget();
+    }
+
+    pub(crate) fn take(&mut self, key: &RenderTexturePoolKey) -> Option<wgpu::Texture> {
+        let idle = self.idle.get_mut(key).and_then(|textures| textures.pop());
+        match idle {
+            Some(idle) => {
+                self.idle_byte -= key.byte_size();
+                self.idle_count -= 1;
+                self.hit_count += 1;
+                Some(idle.texture)
+            }
+            None => {
+                self.miss_count += 1;
+                None
+            }
+        }
+    }
+
+    pub(crate) fn recycle(&mut self, texture: wgpu::Texture) {
+        let key = RenderTexturePoolKey::from_texture(&texture);
+        let byte_size = key.byte_size();
+        if byte_size > self.budget {
+            self.evicted_count += 1;
+            return;
+        }
+
+        self.return_order += 1;
+        self.idle.entry(key).or_default().push(IdleRenderTexture {
+            return_order: self.return_order,
+            texture,
+        });
+        self.idle_byte += byte_size;
+        self.idle_count += 1;
+
+        self.evict_over_budget();
+    }
+
+    pub fn clear(&mut self) {
+        self.evicted_count += self.idle_count as u64;
+        self.idle.clear();
+        self.idle_byte = 0;
+        self.idle_count = 0;
+    }
+
+    pub fn statistics(&self) -> RenderTexturePoolStatistics {
+        RenderTexturePoolStatistics {
+            budget_byte: self.budget,
+            pooled_byte: self.idle_byte,
+            pooled_count: self.idle_count,
+            hit_count: self.hit_count,
+            miss_count: self.miss_count,
+            evicted_count: self.evicted_count,
+        }
+    }
+
+    fn evict_over_budget(&mut self) {
+        while self.idle_byte > self.budget {
+            let Some(oldest_key) = self
+                .idle
+                .iter()
+                .filter_map(|(key, textures)| textures.first().map(|t| (key, t.return_order)))
+                .min_by_key(|(_, return_order)| *return_order)
+                .map(|(key, _)| *key)
+            else {
+                break;
+            };
+
+            let textures = self.idle.get_mut(&oldest_key).unwrap();
+            textures.remove(0);
+            if textures.is_empty() {
+                self.idle.remove(&oldest_key);
+            }
+            self.idle_byte -= oldest_key.byte_size();
+            self.idle_count -= 1;
+            self.evicted_count += 1;
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::tex_trans_core_engine::{TTCEWgpuError, TexTransCoreEngineContext};
+
+/// Unity の FilterMode と同じ意味
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TTSamplerFilterMode {
+    Point = 0,
+    Bilinear = 1,
+    /// ミップ間も補間する
+    Trilinear = 2,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TTSamplerAddressMode {
+    Clamp = 0,
+    Repeat = 1,
+    Mirror = 2,
+}
+impl TTSamplerAddressMode {
+    fn to_wgpu(self) -> wgpu::AddressMode {
+        match self {
+            TTSamplerAddressMode::Clamp => wgpu::AddressMode::ClampToEdge,
+            TTSamplerAddressMode::Repeat => wgpu::AddressMode::Repeat,
+            TTSamplerAddressMode::Mirror => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct TTSamplerDescriptor {
+    pub filter_mode: TTSamplerFilterMode,
+    pub address_mode_u: TTSamplerAddressMode,
+    pub address_mode_v: TTSamplerAddressMode,
+    pub address_mode_w: TTSamplerAddressMode,
+    /// 1 で異方性フィルタリングは無効、 2 以上は Trilinear の場合のみ使用でき 16 まで
+    pub anisotropy_clamp: u16,
+}
+
+/// SamplerState として宣言されたバインディングに渡すもの。コンテキストをまたいで使いまわしてよい。
+#[derive(Debug)]
+pub struct TTSampler {
+    pub(crate) sampler: wgpu::Sampler,
+}
+
+impl TexTransCoreEngineContext<'_> {
+    pub fn create_sampler(&self, desc: &TTSamplerDescriptor) -> Result<TTSampler, TTCEWgpuError> {
+        if desc.anisotropy_clamp == 0 || desc.anisotropy_clamp > 16 {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
+        // wgpu は異方性フィルタリングにすべてのフィルターが Linear であることを要求する
+        if desc.anisotropy_clamp > 1 && desc.filter_mode != TTSamplerFilterMode::Trilinear {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
+
+        let (filter, mipmap_filter) = match desc.filter_mode {
+            TTSamplerFilterMode::Point => (wgpu::FilterMode::Nearest, wgpu::FilterMode::Nearest),
+            TTSamplerFilterMode::Bilinear => (wgpu::FilterMode::Linear, wgpu::FilterMode::Nearest),
+            TTSamplerFilterMode::Trilinear => (wgpu::FilterMode::Linear, wgpu::FilterMode::Linear),
+        };
+        let sampler = self.engine.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("ttce sampler"),
+            address_mode_u: desc.address_mode_u.to_wgpu(),
+            address_mode_v: desc.address_mode_v.to_wgpu(),
+            address_mode_w: desc.address_mode_w.to_wgpu(),
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter,
+            anisotropy_clamp: desc.anisotropy_clamp,
+            ..Default::default()
+        });
+
+        Ok(TTSampler { sampler })
+    }
+}
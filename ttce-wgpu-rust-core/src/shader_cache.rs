@@ -0,0 +1,304 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::debug_log;
+
+const CACHE_FILE_MAGIC: &[u8; 8] = b"TTCESPV1";
+const CACHE_FILE_EXTENSION: &str = "ttcespv";
+
+// 同じプロセス内の別スレッドからの書き込みとも一時ファイルが重ならないようにするため
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// HLSL から DXC で生成された SPIR-V を保存するディスクキャッシュ。
+/// ソースと DXC に渡す引数などすべてをキーとし、 #include されたファイルが変更された場合は無効になる。
+#[derive(Debug)]
+pub struct ShaderCache {
+    directory: PathBuf,
+    max_size: u64,
+}
+
+/// キャッシュのキーになる物すべて。ファイル名はこれのハッシュになるが、衝突してもいいように中身も保存して比較する。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderCacheKey {
+    material: Vec<u8>,
+}
+impl ShaderCacheKey {
+    pub fn new(
+        source_name: &str,
+        source: &str,
+        entry_point: &str,
+        target_profile: &str,
+        args: &[&str],
+        defines: &[(&str, Option<&str>)],
+        default_texture_format: &str,
+    ) -> Self {
+        let mut material = Vec::new();
+        let mut push = |str: &str| {
+            material.extend_from_slice(&(str.len() as u64).to_le_bytes());
+            material.extend_from_slice(str.as_bytes());
+        };
+
+        push(env!("CARGO_PKG_VERSION"));
+        push(source_name);
+        push(entry_point);
+        push(target_profile);
+        push(default_texture_format);
+        for arg in args {
+            push(arg);
+        }
+        push("defines");
+        for (name, value) in defines {
+            push(name);
+            push(value.unwrap_or(""));
+        }
+        push("source");
+        push(source);
+
+        ShaderCacheKey { material }
+    }
+
+    fn file_name(&self) -> String {
+        format!("{:016x}.{}", fnv1a_64(&self.material), CACHE_FILE_EXTENSION)
+    }
+}
+
+/// #include されたファイルの状態。読み込み時にこれが一致しない場合は無効なキャッシュとして扱う。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IncludedFileStamp {
+    pub path: String,
+    pub size: u64,
+    pub modified_nanos: u128,
+}
+impl IncludedFileStamp {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let modified_nanos = metadata
+            .modified()
+            .ok()?
+            .duration_since(UNIX_EPOCH)
+            .ok()?
+            .as_nanos();
+        Some(IncludedFileStamp {
+            path: path.to_string(),
+            size: metadata.len(),
+            modified_nanos,
+        })
+    }
+    fn is_up_to_date(&self) -> bool {
+        Self::from_path(&self.path).as_ref() == Some(self)
+    }
+}
+
+impl ShaderCache {
+    /// max_size はキャッシュディレクトリ内のキャッシュファイルの合計の上限で、超えた場合は古く使われていない物から削除される。
+    /// 保存した直後のファイルは削除されないため、一つのファイルが max_size より大きい場合は上限を超えて残る。
+    pub fn new(directory: impl Into<PathBuf>, max_size: u64) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(ShaderCache {
+            directory,
+            max_size,
+        })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn load(&self, key: &ShaderCacheKey) -> Option<Vec<u8>> {
+        let path = self.directory.join(key.file_name());
+        let mut bytes = Vec::new();
+        File::open(&path).ok()?.read_to_end(&mut bytes).ok()?;
+
+        let Some((material, included_files, spirv)) = decode_cache_file(&bytes) else {
+            debug_log(&format!("broken shader cache removed : {}", path.display()));
+            let _ = fs::remove_file(&path);
+            return None;
+        };
+        if material != key.material {
+            return None;
+        }
+        if !included_files.iter().all(IncludedFileStamp::is_up_to_date) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+
+        // 最後に使われた時間として扱うため
+        if let Ok(file) = File::options().write(true).open(&path) {
+            let _ = file.set_modified(SystemTime::now());
+        }
+        Some(spirv)
+    }
+
+    pub fn store(
+        &self,
+        key: &ShaderCacheKey,
+        included_files: &[IncludedFileStamp],
+        spirv: &[u8],
+    ) -> io::Result<()> {
+        let file_name = key.file_name();
+        let path = self.directory.join(&file_name);
+        // 複数のプロセスが同じキーを同時に保存しても、互いの書きかけのファイルを rename しないようにする
+        let temp_path = self.directory.join(format!(
+            "{}.{}.{}.tmp",
+            file_name,
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let bytes = encode_cache_file(&key.material, included_files, spirv);
+        let write_result = File::create(&temp_path)
+            .and_then(|mut f| f.write_all(&bytes))
+            .and_then(|_| fs::rename(&temp_path, &path));
+        if write_result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        write_result?;
+
+        self.enforce_max_size(&path)
+    }
+
+    pub fn clear(&self) -> io::Result<()> {
+        for (path, _, _) in self.cache_files()? {
+            remove_file_if_exists(&path)?;
+        }
+        Ok(())
+    }
+
+    pub fn total_size(&self) -> io::Result<u64> {
+        Ok(self.cache_files()?.iter().map(|f| f.1).sum())
+    }
+
+    // keep は保存したばかりのもので、これを消すと store が意味を成さないため削除の対象から外す
+    fn enforce_max_size(&self, keep: &Path) -> io::Result<()> {
+        let mut files = self.cache_files()?;
+        let mut total_size: u64 = files.iter().map(|f| f.1).sum();
+        if total_size <= self.max_size {
+            return Ok(());
+        }
+
+        files.sort_by_key(|f| f.2);
+        for (path, size, _) in files {
+            if total_size <= self.max_size {
+                break;
+            }
+            if path == keep {
+                continue;
+            }
+            remove_file_if_exists(&path)?;
+            total_size -= size;
+        }
+        Ok(())
+    }
+
+    fn cache_files(&self) -> io::Result<Vec<(PathBuf, u64, SystemTime)>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(&self.directory)?.filter_map(Result::ok) {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(CACHE_FILE_EXTENSION) {
+                continue;
+            }
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+            files.push((path, metadata.len(), modified));
+        }
+        Ok(files)
+    }
+}
+
+// 別のプロセスが同じキャッシュディレクトリを掃除して、先に削除している場合がある
+fn remove_file_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+fn encode_cache_file(
+    material: &[u8],
+    included_files: &[IncludedFileStamp],
+    spirv: &[u8],
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(material.len() + spirv.len() + 64);
+    bytes.extend_from_slice(CACHE_FILE_MAGIC);
+
+    bytes.extend_from_slice(&(material.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(material);
+
+    bytes.extend_from_slice(&(included_files.len() as u64).to_le_bytes());
+    for included in included_files {
+        bytes.extend_from_slice(&(included.path.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(included.path.as_bytes());
+        bytes.extend_from_slice(&included.size.to_le_bytes());
+        bytes.extend_from_slice(&included.modified_nanos.to_le_bytes());
+    }
+
+    bytes.extend_from_slice(&(spirv.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(spirv);
+    bytes
+}
+
+struct CacheFileReader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> CacheFileReader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < len {
+            return None;
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Some(head)
+    }
+    fn take_u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+    fn take_u128(&mut self) -> Option<u128> {
+        Some(u128::from_le_bytes(self.take(16)?.try_into().ok()?))
+    }
+    fn take_bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.take_u64()? as usize;
+        self.take(len)
+    }
+}
+
+fn decode_cache_file(bytes: &[u8]) -> Option<(Vec<u8>, Vec<IncludedFileStamp>, Vec<u8>)> {
+    let mut reader = CacheFileReader {
+        bytes: bytes.strip_prefix(CACHE_FILE_MAGIC.as_slice())?,
+    };
+
+    let material = reader.take_bytes()?.to_vec();
+
+    let included_count = reader.take_u64()? as usize;
+    let mut included_files = Vec::new();
+    for _ in 0..included_count {
+        let path = String::from_utf8(reader.take_bytes()?.to_vec()).ok()?;
+        let size = reader.take_u64()?;
+        let modified_nanos = reader.take_u128()?;
+        included_files.push(IncludedFileStamp {
+            path,
+            size,
+            modified_nanos,
+        });
+    }
+
+    let spirv = reader.take_bytes()?.to_vec();
+
+    Some((material, included_files, spirv))
+}
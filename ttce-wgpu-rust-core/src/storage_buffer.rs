@@ -2,23 +2,25 @@ use std::sync::Arc;
 
 use wgpu::util::DeviceExt;
 
-use crate::tex_trans_core_engine::TexTransCoreEngineContext;
+use crate::tex_trans_core_engine::{map_read_and_wait, TTCEWgpuError, TexTransCoreEngineContext};
 
 pub struct TTStorageBuffer {
     pub(crate) buffer: Arc<wgpu::Buffer>,
 }
 
 impl TexTransCoreEngineContext<'_> {
-    pub fn allocate_storage_buffer(&self, buffer_len: i32, downloadable: bool) -> TTStorageBuffer {
+    /// indirect が true の場合は TTComputeHandler::dispatch_indirect の引数として使えるようになる。
+    pub fn allocate_storage_buffer(
+        &self,
+        buffer_len: i32,
+        downloadable: bool,
+        indirect: bool,
+    ) -> TTStorageBuffer {
         let label = format!("storage buffer from allocate - Length:{}", buffer_len);
         let alined_len = ((buffer_len + 4) & !3).max(4) as u64;
         let buffer_desc = wgpu::BufferDescriptor {
             label: Some(label.as_str()),
-            usage: if downloadable {
-                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::MAP_READ
-            } else {
-                wgpu::BufferUsages::STORAGE
-            },
+            usage: storage_buffer_usage(downloadable, indirect),
             size: alined_len,
             mapped_at_creation: false,
         };
@@ -34,6 +36,7 @@ impl TexTransCoreEngineContext<'_> {
         &self,
         buffer_data_span: &[u8],
         downloadable: bool,
+        indirect: bool,
     ) -> TTStorageBuffer {
         let label = format!(
             "storage buffer from upload - Length:{}",
@@ -41,11 +44,7 @@ impl TexTransCoreEngineContext<'_> {
         );
         let buffer_desc = wgpu::util::BufferInitDescriptor {
             label: Some(label.as_str()),
-            usage: if downloadable {
-                wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::MAP_READ
-            } else {
-                wgpu::BufferUsages::STORAGE
-            },
+            usage: storage_buffer_usage(downloadable, indirect),
             contents: buffer_data_span,
         };
         let buffer = self.engine.device.create_buffer_init(&buffer_desc);
@@ -58,18 +57,27 @@ impl TexTransCoreEngineContext<'_> {
     pub async fn download_storage_buffer(
         &mut self,
         storage_buffer: &TTStorageBuffer,
-    ) -> Result<(), wgpu::BufferAsyncError> {
-        let rb_buffer_slice = storage_buffer.buffer.slice(..);
-        let (sender, receiver) = tokio::sync::oneshot::channel();
-        rb_buffer_slice.map_async(wgpu::MapMode::Read, move |v| {
-            sender.send(v).unwrap();
-        });
+    ) -> Result<(), TTCEWgpuError> {
+        self.check_device_lost()?;
+        if !storage_buffer
+            .buffer
+            .usage()
+            .contains(wgpu::BufferUsages::MAP_READ)
+        {
+            return Err(TTCEWgpuError::InvalidArgument);
+        }
 
-        self.engine
-            .device
-            .poll(wgpu::Maintain::wait())
-            .panic_on_timeout();
+        map_read_and_wait(&self.engine.device, &storage_buffer.buffer.slice(..))
+    }
+}
 
-        receiver.await.unwrap()
+fn storage_buffer_usage(downloadable: bool, indirect: bool) -> wgpu::BufferUsages {
+    let mut usage = wgpu::BufferUsages::STORAGE;
+    if downloadable {
+        usage |= wgpu::BufferUsages::MAP_READ;
+    }
+    if indirect {
+        usage |= wgpu::BufferUsages::INDIRECT;
     }
+    usage
 }
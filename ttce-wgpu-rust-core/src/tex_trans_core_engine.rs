@@ -1,27 +1,68 @@
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
 
 use hassle_rs::Dxc;
 use wgpu::CommandEncoder;
 
-use crate::compute_shader::{TTComputeShader, TTComputeShaderID};
+use crate::blit::{BlitPipeline, BlitPipelineKey};
+use crate::block_compression::BlockCompressorKey;
+use crate::block_decompression::{BlockDecompressionPipeline, BlockDecompressionPipelineKey};
+use crate::color_space::{ColorSpacePipeline, ColorSpacePipelineKey, TTColorSpace};
+use crate::compute_shader::{
+    TTBindGroupStatistics, TTComputeShader, TTComputeShaderEntry, TTComputeShaderID,
+};
+use crate::constants_buffer::ConstantsBufferAllocator;
+use crate::download_ticket::{StagingBufferPool, DEFAULT_STAGING_BUFFER_POOL_BUDGET};
 use crate::dxc_ctx::DirectXCompilerContext;
+use crate::fill::{FillPipeline, FillPipelineKey};
+use crate::mipmap::{MipmapPipeline, MipmapPipelineKey};
 use crate::render_texture::{ConvertTextureFormat, TTRenderTexture};
-use crate::{TexTransCoreTextureChannel, TexTransCoreTextureFormat};
+use crate::render_texture_pool::{
+    RenderTexturePool, RenderTexturePoolKey, RenderTexturePoolStatistics,
+    DEFAULT_RENDER_TEXTURE_POOL_BUDGET,
+};
+use crate::shader_cache::ShaderCache;
+use crate::{TTChannelMapping, TexTransCoreTextureChannel, TexTransCoreTextureFormat};
 
 #[derive(Debug)]
 pub struct TexTransCoreEngineDevice {
     pub(crate) device: wgpu::Device,
     pub(crate) queue: wgpu::Queue,
 
-    pub(crate) compute_shader: Vec<TTComputeShader>,
+    pub(crate) compute_shader: Vec<TTComputeShaderEntry>,
     pub(crate) converter_id: HashMap<ConvertTextureFormat, TTComputeShaderID>,
+    pub(crate) block_compressor_id: HashMap<BlockCompressorKey, TTComputeShaderID>,
+    pub(crate) format_converter_pipelines:
+        Mutex<HashMap<ConvertTextureFormat, Arc<TTComputeShader>>>,
+    pub(crate) block_compressor_pipelines: Mutex<HashMap<BlockCompressorKey, Arc<TTComputeShader>>>,
+    pub(crate) mipmap_pipelines: Mutex<HashMap<MipmapPipelineKey, Arc<MipmapPipeline>>>,
+    pub(crate) color_space_pipelines:
+        Mutex<HashMap<ColorSpacePipelineKey, Arc<ColorSpacePipeline>>>,
+    pub(crate) block_decompression_pipelines:
+        Mutex<HashMap<BlockDecompressionPipelineKey, Arc<BlockDecompressionPipeline>>>,
+    pub(crate) blit_pipelines: Mutex<HashMap<BlitPipelineKey, Arc<BlitPipeline>>>,
+    pub(crate) fill_pipelines: Mutex<HashMap<FillPipelineKey, Arc<FillPipeline>>>,
 
-    pub(crate) dx_compiler: DirectXCompilerContext,
+    pub(crate) dx_compiler: Option<DirectXCompilerContext>,
+    pub(crate) shader_cache: Option<ShaderCache>,
+    pub(crate) pipeline_cache: Option<wgpu::PipelineCache>,
+
+    render_texture_pool: Arc<Mutex<RenderTexturePool>>,
+    staging_buffer_pool: Arc<Mutex<StagingBufferPool>>,
+    /// ブロック圧縮の出力先の STORAGE なバッファー、圧縮の完了を待ってから返却される。
+    pub(crate) block_compression_buffer_pool: Mutex<StagingBufferPool>,
 
     default_render_texture_format: TexTransCoreTextureFormat,
     max_command_stack_count: u32,
+
+    pub(crate) bind_group_created_count: AtomicU64,
+    pub(crate) bind_group_reused_count: AtomicU64,
+
+    device_lost: Arc<AtomicBool>,
 }
 
 #[derive(Debug)]
@@ -30,21 +71,62 @@ pub struct TexTransCoreEngineContext<'a> {
 
     command_encoder: Option<CommandEncoder>,
     command_stack_count: u32,
+
+    pub(crate) constants_buffer_allocator: ConstantsBufferAllocator,
+    // ドロップされたが、まだ未送信の command から使われているかもしれない TTRenderTexture のテクスチャー
+    pending_recycle_textures: Arc<Mutex<Vec<wgpu::Texture>>>,
 }
 
 impl TexTransCoreEngineDevice {
-    pub fn new(device: wgpu::Device, queue: wgpu::Queue, dxc_ctx: DirectXCompilerContext) -> Self {
+    /// dxc_ctx が None の場合は HLSL を登録できないが、フォーマットの変換などの内部のシェーダーのみで動くものは使用できる。
+    pub fn new(
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        dxc_ctx: Option<DirectXCompilerContext>,
+    ) -> Self {
+        let device_lost = Arc::new(AtomicBool::new(false));
+        let device_lost_flag = device_lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            device_lost_flag.store(true, Ordering::Release);
+            crate::debug_log(&format!("device lost : {:?} {}", reason, message));
+        });
+
         TexTransCoreEngineDevice {
             device,
             queue,
 
             compute_shader: Vec::new(),
             converter_id: HashMap::new(),
+            block_compressor_id: HashMap::new(),
+            format_converter_pipelines: Mutex::new(HashMap::new()),
+            block_compressor_pipelines: Mutex::new(HashMap::new()),
+            mipmap_pipelines: Mutex::new(HashMap::new()),
+            color_space_pipelines: Mutex::new(HashMap::new()),
+            block_decompression_pipelines: Mutex::new(HashMap::new()),
+            blit_pipelines: Mutex::new(HashMap::new()),
+            fill_pipelines: Mutex::new(HashMap::new()),
 
             dx_compiler: dxc_ctx,
+            shader_cache: None,
+            pipeline_cache: None,
+
+            render_texture_pool: Arc::new(Mutex::new(RenderTexturePool::new(
+                DEFAULT_RENDER_TEXTURE_POOL_BUDGET,
+            ))),
+            staging_buffer_pool: Arc::new(Mutex::new(StagingBufferPool::new(
+                DEFAULT_STAGING_BUFFER_POOL_BUDGET,
+            ))),
+            block_compression_buffer_pool: Mutex::new(StagingBufferPool::new(
+                DEFAULT_STAGING_BUFFER_POOL_BUDGET,
+            )),
 
             default_render_texture_format: TexTransCoreTextureFormat::Float,
             max_command_stack_count: 16,
+
+            bind_group_created_count: AtomicU64::new(0),
+            bind_group_reused_count: AtomicU64::new(0),
+
+            device_lost,
         }
     }
     pub fn create_ctx(&self) -> TexTransCoreEngineContext {
@@ -52,9 +134,39 @@ impl TexTransCoreEngineDevice {
             engine: self,
             command_encoder: None,
             command_stack_count: 0,
+
+            constants_buffer_allocator: ConstantsBufferAllocator::new(),
+            pending_recycle_textures: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// チャンネルが変わる場合は TTChannelMapping::Default のコンバーターを返す。
+    pub fn get_converter_id(
+        &self,
+        from: wgpu::TextureFormat,
+        to: wgpu::TextureFormat,
+    ) -> Option<TTComputeShaderID> {
+        self.converter_id
+            .get(&ConvertTextureFormat {
+                from,
+                to,
+                mapping: TTChannelMapping::Default,
+            })
+            .copied()
+    }
+
+    /// None を渡すとディスクキャッシュを使用しなくなる。
+    pub fn set_shader_cache(&mut self, shader_cache: Option<ShaderCache>) {
+        self.shader_cache = shader_cache;
+    }
+    pub fn shader_cache(&self) -> Option<&ShaderCache> {
+        self.shader_cache.as_ref()
+    }
+
+    pub fn is_device_lost(&self) -> bool {
+        self.device_lost.load(Ordering::Acquire)
+    }
+
     pub fn default_texture_format(&self) -> TexTransCoreTextureFormat {
         self.default_render_texture_format
     }
@@ -62,7 +174,39 @@ impl TexTransCoreEngineDevice {
         self.default_render_texture_format = format;
     }
 
-    pub(crate) fn create_render_texture(&self, desc: &TTRtRequestDescriptor) -> TTRenderTexture {
+    /// 0 を渡すとプールしなくなり、保持されているものも破棄される。
+    pub fn set_render_texture_pool_budget(&self, budget: u64) {
+        self.render_texture_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .set_budget(budget);
+    }
+    pub fn clear_render_texture_pool(&self) {
+        self.render_texture_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+    pub fn render_texture_pool_statistics(&self) -> RenderTexturePoolStatistics {
+        self.render_texture_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .statistics()
+    }
+
+    /// TTComputeHandler の dispatch で bind group が作られた回数と、前回のものが再利用された回数。
+    pub fn bind_group_statistics(&self) -> TTBindGroupStatistics {
+        TTBindGroupStatistics {
+            created_count: self.bind_group_created_count.load(Ordering::Relaxed),
+            reused_count: self.bind_group_reused_count.load(Ordering::Relaxed),
+        }
+    }
+
+    /// プールから再利用されたものは前回の内容が残っているため、 bool が true の場合は必要であればクリアするように。
+    pub(crate) fn create_render_texture(
+        &self,
+        desc: &TTRtRequestDescriptor,
+    ) -> (TTRenderTexture, bool) {
         let tex_format = match desc.format {
             RequestFormat::AutoWithChannel(tex_trans_core_texture_channel) => {
                 TTRenderTexture::to_wgpu_texture_format(
@@ -83,43 +227,233 @@ impl TexTransCoreEngineDevice {
             ),
         };
 
-        let usage = wgpu::TextureUsages::TEXTURE_BINDING
+        let pool = Arc::downgrade(&self.render_texture_pool);
+        let pool_key = RenderTexturePoolKey {
+            width: desc.width,
+            height: desc.height,
+            format: tex_format,
+            mip_level_count: desc.mip_level_count,
+            dimension: desc.dimension.to_wgpu(),
+            depth_or_array_layers: desc.depth_or_array_layers,
+        };
+        let recycled = self
+            .render_texture_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take(&pool_key);
+        if let Some(texture) = recycled {
+            return (
+                TTRenderTexture {
+                    texture,
+                    pool,
+                    pending_recycle: Weak::new(),
+                    color_space: TTColorSpace::Linear,
+                },
+                true,
+            );
+        }
+
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING
             | wgpu::TextureUsages::STORAGE_BINDING
             | wgpu::TextureUsages::COPY_SRC
-            | wgpu::TextureUsages::COPY_DST
-            | wgpu::TextureUsages::RENDER_ATTACHMENT;
+            | wgpu::TextureUsages::COPY_DST;
+        // 3D テクスチャーは render attachment にできず、付けると作成時の検証で弾かれる
+        if desc.dimension != TTRtDimension::D3 {
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
 
+        let mut view_formats = vec![tex_format];
+        if tex_format.add_srgb_suffix() != tex_format {
+            view_formats.push(tex_format.add_srgb_suffix());
+        }
         let tex_desc = wgpu::TextureDescriptor {
             size: wgpu::Extent3d {
                 width: desc.width,
                 height: desc.height,
-                depth_or_array_layers: 1,
+                depth_or_array_layers: desc.depth_or_array_layers,
             },
-            dimension: wgpu::TextureDimension::D2,
+            dimension: desc.dimension.to_wgpu(),
             format: tex_format,
             usage,
-            mip_level_count: 1,
+            mip_level_count: desc.mip_level_count,
             sample_count: 1,
             label: Some("Create-From-RenderTexture-With-ColorTexture"),
-            view_formats: &[tex_format],
+            // Srgb の印がついたものをサンプリングするときにデコードされるように、 sRGB の view を作れるようにしておく
+            view_formats: &view_formats,
         };
 
         let render_texture = self.device.create_texture(&tex_desc);
 
-        TTRenderTexture {
-            texture: render_texture,
+        (
+            TTRenderTexture {
+                texture: render_texture,
+                pool,
+                pending_recycle: Weak::new(),
+                color_space: TTColorSpace::Linear,
+            },
+            false,
+        )
+    }
+    pub(crate) fn take_staging_buffer(&self, size: u64) -> wgpu::Buffer {
+        let recycled = self
+            .staging_buffer_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take(size);
+        recycled.unwrap_or_else(|| {
+            self.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("pooled-read-back-buffer"),
+                size,
+                usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            })
+        })
+    }
+    /// マップされていない状態で返却するように。
+    pub(crate) fn recycle_staging_buffer(&self, buffer: wgpu::Buffer) {
+        self.staging_buffer_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .recycle(buffer);
+    }
+    pub(crate) fn staging_buffer_pool(&self) -> std::sync::Weak<Mutex<StagingBufferPool>> {
+        Arc::downgrade(&self.staging_buffer_pool)
+    }
+
+    /// 保留されている TTDownloadTicket の完了の callback などを進める。ブロックはしない。
+    pub fn poll(&self) {
+        let _ = self.device.poll(wgpu::Maintain::Poll);
+    }
+
+    pub(crate) fn dx_compiler(&self) -> Option<&DirectXCompilerContext> {
+        self.dx_compiler.as_ref()
+    }
+}
+
+/// GPU の完了を待つ上限、 wgpu-core が Maintain::Wait で待つ時間と同じ
+pub const DEVICE_WAIT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// is_completed が true になるまで device を poll する。
+/// Maintain::Wait はタイムアウトすると panic するため、 Poll を繰り返して DeviceTimeout を返す。
+pub(crate) fn poll_until(
+    device: &wgpu::Device,
+    mut is_completed: impl FnMut() -> bool,
+) -> Result<(), TTCEWgpuError> {
+    let deadline = Instant::now() + DEVICE_WAIT_TIMEOUT;
+    loop {
+        let _ = device.poll(wgpu::Maintain::Poll);
+        if is_completed() {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(TTCEWgpuError::DeviceTimeout);
+        }
+        std::thread::yield_now();
+    }
+}
+
+/// buffer_slice を読み取りでマップし、終わるまで待つ。
+pub(crate) fn map_read_and_wait(
+    device: &wgpu::Device,
+    buffer_slice: &wgpu::BufferSlice<'_>,
+) -> Result<(), TTCEWgpuError> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |v| {
+        let _ = sender.send(v);
+    });
+
+    let mut map_result = None;
+    poll_until(device, || {
+        map_result = receiver.try_recv().ok();
+        map_result.is_some()
+    })?;
+    match map_result {
+        Some(Ok(())) => Ok(()),
+        _ => Err(TTCEWgpuError::MappingFailure),
+    }
+}
+pub struct TTRtRequestDescriptor {
+    pub width: u32,
+    pub height: u32,
+    pub format: RequestFormat,
+    /// 1 以上 max_mip_level_count 以下でないといけない。
+    pub mip_level_count: u32,
+    pub dimension: TTRtDimension,
+    /// D2 の場合は配列のレイヤー数、 D3 の場合は奥行き。 1 以上でないといけない。
+    pub depth_or_array_layers: u32,
+}
+impl TTRtRequestDescriptor {
+    /// 1x1 になるまでのミップの数
+    pub fn max_mip_level_count(width: u32, height: u32) -> u32 {
+        u32::BITS - width.max(height).max(1).leading_zeros()
+    }
+    /// D3 の場合は奥行きも縮小されるため、それも含めたミップの数
+    pub fn max_mip_level_count_of(&self) -> u32 {
+        match self.dimension {
+            TTRtDimension::D2 => Self::max_mip_level_count(self.width, self.height),
+            TTRtDimension::D3 => {
+                Self::max_mip_level_count(self.width, self.height.max(self.depth_or_array_layers))
+            }
         }
     }
-    pub(crate) fn dx_compiler(&self) -> &DirectXCompilerContext{
-        &self.dx_compiler
+
+    /// C# 側などから渡された値が wgpu のテクスチャーの生成で検証エラーにならないかを確認する。
+    pub(crate) fn validate(&self, limits: &wgpu::Limits) -> Result<(), TTCEWgpuErrorRecord> {
+        if self.width == 0 || self.height == 0 || self.depth_or_array_layers == 0 {
+            return Err(TTCEWgpuError::InvalidArgument.with_message(format!(
+                "render texture size is zero : {}x{}x{}",
+                self.width, self.height, self.depth_or_array_layers
+            )));
+        }
+        let (max_size, max_depth_or_array_layers) = match self.dimension {
+            TTRtDimension::D2 => (
+                limits.max_texture_dimension_2d,
+                limits.max_texture_array_layers,
+            ),
+            TTRtDimension::D3 => (
+                limits.max_texture_dimension_3d,
+                limits.max_texture_dimension_3d,
+            ),
+        };
+        if self.width > max_size
+            || self.height > max_size
+            || self.depth_or_array_layers > max_depth_or_array_layers
+        {
+            return Err(TTCEWgpuError::InvalidArgument.with_message(format!(
+                "render texture size is over the limit : {}x{}x{} ({:?})",
+                self.width, self.height, self.depth_or_array_layers, self.dimension
+            )));
+        }
+        let max_mip_level_count = self.max_mip_level_count_of();
+        if self.mip_level_count == 0 || self.mip_level_count > max_mip_level_count {
+            return Err(TTCEWgpuError::InvalidArgument.with_message(format!(
+                "mip level count is out of range : {} (max {} for {}x{}x{})",
+                self.mip_level_count,
+                max_mip_level_count,
+                self.width,
+                self.height,
+                self.depth_or_array_layers
+            )));
+        }
+        Ok(())
     }
 }
-pub(crate) struct TTRtRequestDescriptor {
-    pub(crate) width: u32,
-    pub(crate) height: u32,
-    pub(crate) format: RequestFormat,
+/// D2 で depth_or_array_layers が 2 以上の場合は 2D 配列テクスチャになる。
+#[repr(u32)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TTRtDimension {
+    D2 = 0,
+    D3 = 1,
 }
-pub(crate) enum RequestFormat {
+impl TTRtDimension {
+    pub(crate) fn to_wgpu(self) -> wgpu::TextureDimension {
+        match self {
+            TTRtDimension::D2 => wgpu::TextureDimension::D2,
+            TTRtDimension::D3 => wgpu::TextureDimension::D3,
+        }
+    }
+}
+pub enum RequestFormat {
     AutoWithChannel(TexTransCoreTextureChannel),
     Manual(TexTransCoreTextureFormat, TexTransCoreTextureChannel),
 }
@@ -137,6 +471,13 @@ impl TexTransCoreEngineContext<'_> {
         self.command_encoder.as_mut().unwrap()
     }
 
+    pub fn check_device_lost(&self) -> Result<(), TTCEWgpuError> {
+        if self.engine.is_device_lost() {
+            return Err(TTCEWgpuError::DeviceLost);
+        }
+        Ok(())
+    }
+
     pub fn check_command_stack(&mut self) {
         if self.command_stack_count > self.engine.max_command_stack_count {
             self.send_command();
@@ -150,17 +491,83 @@ impl TexTransCoreEngineContext<'_> {
             self.engine.queue.submit([]);
         }
         self.command_stack_count = 0;
+        self.constants_buffer_allocator.reset();
+        // 送信した後であれば、他のコンテキストの queue.write_texture などはこの command より後に実行される
+        self.recycle_pending_render_textures();
+    }
+
+    pub(crate) fn pending_recycle_weak(&self) -> Weak<Mutex<Vec<wgpu::Texture>>> {
+        Arc::downgrade(&self.pending_recycle_textures)
+    }
+    fn recycle_pending_render_textures(&mut self) {
+        let textures = std::mem::take(
+            &mut *self
+                .pending_recycle_textures
+                .lock()
+                .unwrap_or_else(|e| e.into_inner()),
+        );
+        if textures.is_empty() {
+            return;
+        }
+        let mut pool = self
+            .engine
+            .render_texture_pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        for texture in textures {
+            pool.recycle(texture);
+        }
+    }
+}
+impl Drop for TexTransCoreEngineContext<'_> {
+    // 送信されずに残った command は捨てられるため、そのまま返却してよい
+    fn drop(&mut self) {
+        self.recycle_pending_render_textures();
     }
 }
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[repr(u32)]
 pub enum TTCEWgpuError {
     #[allow(dead_code)]
-    Unknown,
+    Unknown = 1,
+    Panic = 2,
+
+    NullHandle = 3,
+    InvalidArgument = 4,
+    InvalidUtf16 = 5,
+    FileIO = 6,
+
+    AdapterNotFound = 7,
+    DeviceRequestFailed = 8,
+    DeviceLost = 9,
+    DirectXCompilerNotFound = 10,
+
+    CompileError = 11,
+    UnregisteredComputeShader = 12,
+
+    BindingNotFound = 13,
+    BindingIsNotConstantsBuffer = 14,
+    BindingIsNotStorageBuffer = 15,
+    BindingIsNotTexture = 16,
 
-    BindingNotFound,
-    BindingIsNotConstantsBuffer,
-    BindingIsNotStorageBuffer,
-    BindingIsNotRWTexture,
+    SizeMismatch = 17,
+    UnsupportedFormat = 18,
+    MappingFailure = 19,
+    DownloadNotReady = 20,
+    TextureDimensionMismatch = 21,
+    BindingIsNotSampler = 22,
+    /// サンプラーで読まれるテクスチャーのバインディングに、フィルタリングできないフォーマットのものを渡した
+    TextureIsNotFilterable = 23,
+    /// GPU の完了を DEVICE_WAIT_TIMEOUT まで待っても終わらなかった
+    DeviceTimeout = 24,
+}
+impl TTCEWgpuError {
+    pub fn with_message(self, message: impl Into<String>) -> TTCEWgpuErrorRecord {
+        TTCEWgpuErrorRecord {
+            kind: self,
+            message: message.into(),
+        }
+    }
 }
 impl Display for TTCEWgpuError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -168,3 +575,24 @@ impl Display for TTCEWgpuError {
     }
 }
 impl Error for TTCEWgpuError {}
+
+/// TTCEWgpuError の種類と、何が起きたかのメッセージの組。 FFI の last error として C# 側から取得される。
+#[derive(Clone, Debug)]
+pub struct TTCEWgpuErrorRecord {
+    pub kind: TTCEWgpuError,
+    pub message: String,
+}
+impl From<TTCEWgpuError> for TTCEWgpuErrorRecord {
+    fn from(value: TTCEWgpuError) -> Self {
+        TTCEWgpuErrorRecord {
+            kind: value,
+            message: value.to_string(),
+        }
+    }
+}
+impl Display for TTCEWgpuErrorRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{:?} : {}", self.kind, self.message))
+    }
+}
+impl Error for TTCEWgpuErrorRecord {}
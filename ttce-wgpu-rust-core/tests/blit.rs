@@ -0,0 +1,167 @@
+mod common;
+
+use common::{bytes_to_f32, create_test_device, desc, download, f32_to_bytes, pseudo_random_bytes};
+use ttce_wgpu_rust_core::{
+    blit::TTBlitFilter, tex_trans_core_engine::TTCEWgpuError, TTChannelMapping,
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+const ALL_FILTER: [TTBlitFilter; 4] = [
+    TTBlitFilter::Nearest,
+    TTBlitFilter::Bilinear,
+    TTBlitFilter::Bicubic,
+    TTBlitFilter::Lanczos,
+];
+
+#[test]
+fn nearest_upscale_repeats_pixels() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let byte_rgba = |w, h| {
+        desc(
+            w,
+            h,
+            TexTransCoreTextureFormat::Byte,
+            TexTransCoreTextureChannel::RGBA,
+        )
+    };
+
+    let src = ctx.get_render_texture_with(&byte_rgba(5, 3));
+    let dist = ctx.get_render_texture_with(&byte_rgba(10, 6));
+    let data = pseudo_random_bytes(5 * 3 * 4, 1);
+    ctx.upload_texture(&src, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    ctx.blit(
+        &dist,
+        &src,
+        TTBlitFilter::Nearest,
+        TTChannelMapping::Default,
+    )
+    .unwrap();
+    let result = download(&mut ctx, &dist, TexTransCoreTextureFormat::Byte);
+    for y in 0..6 {
+        for x in 0..10 {
+            let d = (y * 10 + x) * 4;
+            let s = (y / 2 * 5 + x / 2) * 4;
+            assert_eq!(result[d..d + 4], data[s..s + 4], "{} {}", x, y);
+        }
+    }
+}
+
+#[test]
+fn uniform_color_is_kept_by_every_filter() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let float_rgba = |w, h| {
+        desc(
+            w,
+            h,
+            TexTransCoreTextureFormat::Float,
+            TexTransCoreTextureChannel::RGBA,
+        )
+    };
+
+    let color = [0.25, 0.5, 0.75, 1.0];
+    let src = ctx.get_render_texture_with(&float_rgba(7, 5));
+    let data: Vec<f32> = (0..7 * 5).flat_map(|_| color).collect();
+    ctx.upload_texture(&src, &f32_to_bytes(&data), TexTransCoreTextureFormat::Float)
+        .unwrap();
+
+    // 端で重みが欠けても正規化されていれば色は変わらない
+    for (width, height) in [(13, 9), (3, 2), (16, 3)] {
+        let dist = ctx.get_render_texture_with(&float_rgba(width, height));
+        for filter in ALL_FILTER {
+            ctx.blit(&dist, &src, filter, TTChannelMapping::Default)
+                .unwrap();
+            let result = bytes_to_f32(&download(&mut ctx, &dist, TexTransCoreTextureFormat::Float));
+            for (value, expected) in result.iter().zip(color.iter().cycle()) {
+                assert!(
+                    (value - expected).abs() < 0.0001,
+                    "{:?} {}x{} {}",
+                    filter,
+                    width,
+                    height,
+                    value
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn bilinear_upscale_interpolates_and_changes_channel() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let src = ctx.get_render_texture_with(&desc(
+        2,
+        1,
+        TexTransCoreTextureFormat::Float,
+        TexTransCoreTextureChannel::R,
+    ));
+    ctx.upload_texture(
+        &src,
+        &f32_to_bytes(&[0.0, 1.0]),
+        TexTransCoreTextureFormat::Float,
+    )
+    .unwrap();
+    let dist = ctx.get_render_texture_with(&desc(
+        4,
+        1,
+        TexTransCoreTextureFormat::Float,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+
+    ctx.blit(
+        &dist,
+        &src,
+        TTBlitFilter::Bilinear,
+        TTChannelMapping::Default,
+    )
+    .unwrap();
+    let result = bytes_to_f32(&download(&mut ctx, &dist, TexTransCoreTextureFormat::Float));
+    // 端の外は端のピクセルが続くため、両端は src の値のまま
+    for (pixel, expected) in result.chunks_exact(4).zip([0.0, 0.25, 0.75, 1.0]) {
+        for (value, expected) in pixel.iter().zip([expected, expected, expected, 1.0]) {
+            assert!((value - expected).abs() < 0.0001, "{:?}", pixel);
+        }
+    }
+}
+
+#[test]
+fn blit_same_texture_or_array_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture(8, 8, TexTransCoreTextureChannel::RGBA);
+    assert_eq!(
+        ctx.blit(&rt, &rt, TTBlitFilter::Bilinear, TTChannelMapping::Default),
+        Err(TTCEWgpuError::InvalidArgument)
+    );
+
+    let mut array_desc = desc(
+        8,
+        8,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+    );
+    array_desc.depth_or_array_layers = 2;
+    let array = ctx.get_render_texture_with(&array_desc);
+    assert_eq!(
+        ctx.blit(
+            &rt,
+            &array,
+            TTBlitFilter::Bilinear,
+            TTChannelMapping::Default
+        ),
+        Err(TTCEWgpuError::TextureDimensionMismatch)
+    );
+}
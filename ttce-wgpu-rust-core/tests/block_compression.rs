@@ -0,0 +1,167 @@
+mod common;
+
+use common::{
+    block_compression::decode_blocks, block_on, byte_rgba_desc, create_test_device,
+    create_test_device_without_convertor, pseudo_random_bytes,
+};
+use ttce_wgpu_rust_core::{
+    block_compression::{TTBlockCompressionFormat, TTBlockCompressionQuality},
+    tex_trans_core_engine::TTCEWgpuError,
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+// 4 の倍数でない解像度で、端のブロックも確かめる
+const WIDTH: u32 = 37;
+const HEIGHT: u32 = 21;
+
+const ALL_QUALITY: [TTBlockCompressionQuality; 3] = [
+    TTBlockCompressionQuality::Fast,
+    TTBlockCompressionQuality::Normal,
+    TTBlockCompressionQuality::High,
+];
+
+/// なめらかなグラデーションに少しのノイズを乗せたもの
+fn test_image() -> Vec<u8> {
+    let noise = pseudo_random_bytes((WIDTH * HEIGHT * 4) as usize, 7);
+    let mut data = Vec::with_capacity(noise.len());
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let base = [x * 6, y * 11, (x + y) * 4, 255 - x * 3];
+            for value in base {
+                let n = noise[data.len()] as i32 % 9 - 4;
+                data.push((value as i32 + n).clamp(0, 255) as u8);
+            }
+        }
+    }
+    data
+}
+
+/// 圧縮に含まれるチャンネルのみの RMSE を 0 から 255 で返す
+fn rmse(format: TTBlockCompressionFormat, source: &[u8], decoded: &[[f32; 4]]) -> f32 {
+    let channels = match format {
+        TTBlockCompressionFormat::BC1 => 3,
+        TTBlockCompressionFormat::BC4 => 1,
+        TTBlockCompressionFormat::BC5 => 2,
+        _ => 4,
+    };
+    let mut sum = 0.0;
+    for (src, dec) in source.chunks_exact(4).zip(decoded) {
+        for (s, d) in src.iter().zip(dec).take(channels) {
+            let diff = *s as f32 - d * 255.0;
+            sum += diff * diff;
+        }
+    }
+    (sum / (decoded.len() * channels) as f32).sqrt()
+}
+
+#[test]
+fn encode_all_format_and_quality_matches_reference_decoder() {
+    let Some(mut engine) = create_test_device() else {
+        return;
+    };
+    engine.register_block_compressor();
+    let mut ctx = engine.create_ctx();
+
+    let source = test_image();
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    ctx.upload_texture(&rt, &source, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    let formats = [
+        (TTBlockCompressionFormat::BC1, 6.0),
+        (TTBlockCompressionFormat::BC3, 6.0),
+        (TTBlockCompressionFormat::BC4, 3.0),
+        (TTBlockCompressionFormat::BC5, 3.0),
+        (TTBlockCompressionFormat::BC7, 4.0),
+    ];
+    for (format, max_rmse) in formats {
+        let mut errors = Vec::new();
+        for quality in ALL_QUALITY {
+            let mut blocks = vec![0; format.compressed_size(WIDTH, HEIGHT) as usize];
+            block_on(ctx.encode_block_compression(&rt, format, quality, &mut blocks)).unwrap();
+
+            let decoded = decode_blocks(format, WIDTH, HEIGHT, &blocks);
+            let error = rmse(format, &source, &decoded);
+            assert!(error < max_rmse, "{:?} {:?} {}", format, quality, error);
+            errors.push(error);
+        }
+        // High は Fast より悪くならない
+        assert!(errors[2] <= errors[0] + 0.01, "{:?} {:?}", format, errors);
+    }
+}
+
+#[test]
+fn bc1_keeps_punch_through_alpha() {
+    let Some(mut engine) = create_test_device() else {
+        return;
+    };
+    engine.register_block_compressor();
+    let mut ctx = engine.create_ctx();
+
+    // 左半分が透明な 8x4
+    let source: Vec<u8> = (0..32)
+        .flat_map(|i| {
+            let alpha = if i % 8 < 4 { 0 } else { 255 };
+            [200, 100, 50, alpha]
+        })
+        .collect();
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(8, 4));
+    ctx.upload_texture(&rt, &source, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    let format = TTBlockCompressionFormat::BC1;
+    let mut blocks = vec![0; format.compressed_size(8, 4) as usize];
+    block_on(ctx.encode_block_compression(
+        &rt,
+        format,
+        TTBlockCompressionQuality::Normal,
+        &mut blocks,
+    ))
+    .unwrap();
+
+    let decoded = decode_blocks(format, 8, 4, &blocks);
+    for (i, pixel) in decoded.iter().enumerate() {
+        if i % 8 < 4 {
+            assert_eq!(pixel[3], 0.0, "{}", i);
+        } else {
+            assert_eq!(pixel[3], 1.0, "{}", i);
+            assert!((pixel[0] * 255.0 - 200.0).abs() < 5.0, "{}", i);
+        }
+    }
+}
+
+#[test]
+fn encode_without_register_or_size_mismatch_is_error() {
+    let Some(mut engine) = create_test_device_without_convertor() else {
+        return;
+    };
+    engine.register_format_convertor();
+    {
+        let mut ctx = engine.create_ctx();
+        let rt = ctx.get_render_texture(8, 8, TexTransCoreTextureChannel::RGBA);
+        let mut blocks = vec![0; 64];
+        assert_eq!(
+            block_on(ctx.encode_block_compression(
+                &rt,
+                TTBlockCompressionFormat::BC7,
+                TTBlockCompressionQuality::Fast,
+                &mut blocks,
+            )),
+            Err(TTCEWgpuError::UnregisteredComputeShader)
+        );
+    }
+
+    engine.register_block_compressor();
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture(8, 8, TexTransCoreTextureChannel::RGBA);
+    let mut blocks = vec![0; 63];
+    assert_eq!(
+        block_on(ctx.encode_block_compression(
+            &rt,
+            TTBlockCompressionFormat::BC7,
+            TTBlockCompressionQuality::Fast,
+            &mut blocks,
+        )),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+}
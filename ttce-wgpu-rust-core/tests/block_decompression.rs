@@ -0,0 +1,168 @@
+mod common;
+
+use common::{
+    block_compression::decode_blocks, bytes_to_f32, create_test_device, desc_with, download,
+    float_rgba_desc, pseudo_random_bytes,
+};
+use ttce_wgpu_rust_core::{
+    block_compression::TTBlockCompressionFormat,
+    block_decompression::TTBlockDecompressionPath,
+    render_texture::TTRenderTexture,
+    tex_trans_core_engine::{TTCEWgpuError, TTRtDimension, TexTransCoreEngineContext},
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+// 4 の倍数でない解像度で、端のブロックも確かめる
+const WIDTH: u32 = 37;
+const HEIGHT: u32 = 21;
+
+fn download_pixels(ctx: &mut TexTransCoreEngineContext, rt: &TTRenderTexture) -> Vec<[f32; 4]> {
+    bytes_to_f32(&download(ctx, rt, TexTransCoreTextureFormat::Float))
+        .chunks_exact(4)
+        .map(|p| p.try_into().unwrap())
+        .collect()
+}
+
+fn random_blocks(format: TTBlockCompressionFormat, seed: u32) -> Vec<u8> {
+    pseudo_random_bytes(format.compressed_size(WIDTH, HEIGHT) as usize, seed)
+}
+
+// BC6H は 1 を超える値になるため、 1 を超えるものは相対的な誤差で比べる
+fn assert_pixels_near(expected: &[[f32; 4]], actual: &[[f32; 4]], tolerance: f32, label: &str) {
+    assert_eq!(expected.len(), actual.len());
+    for (i, (e, a)) in expected.iter().zip(actual).enumerate() {
+        for (ec, ac) in e.iter().zip(a) {
+            assert!(
+                (ec - ac).abs() <= tolerance * ec.abs().max(1.0),
+                "{} pixel {} : {:?} {:?}",
+                label,
+                i,
+                e,
+                a
+            );
+        }
+    }
+}
+
+#[test]
+fn compute_shader_decode_matches_reference_decoder() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+
+    let formats = [
+        TTBlockCompressionFormat::BC1,
+        TTBlockCompressionFormat::BC2,
+        TTBlockCompressionFormat::BC3,
+        TTBlockCompressionFormat::BC4,
+        TTBlockCompressionFormat::BC5,
+        TTBlockCompressionFormat::BC7,
+        TTBlockCompressionFormat::BC6H,
+    ];
+    for (i, format) in formats.into_iter().enumerate() {
+        let mut blocks = random_blocks(format, i as u32 + 1);
+        // リファレンスデコーダーは BC7 のモード 6 と BC6H のモード 11 のみのため、モードのビットを揃える
+        for block in blocks.chunks_exact_mut(16) {
+            match format {
+                TTBlockCompressionFormat::BC7 => block[0] = (block[0] & 0x80) | 0x40,
+                TTBlockCompressionFormat::BC6H => block[0] = (block[0] & 0xE0) | 0x03,
+                _ => {}
+            }
+        }
+
+        ctx.upload_block_compressed_texture_with(
+            &rt,
+            &blocks,
+            format,
+            TTBlockDecompressionPath::ComputeShader,
+        )
+        .unwrap();
+        let expected = decode_blocks(format, WIDTH, HEIGHT, &blocks);
+        let actual = download_pixels(&mut ctx, &rt);
+        assert_pixels_near(&expected, &actual, 0.001, &format!("{:?}", format));
+    }
+}
+
+#[test]
+fn hardware_and_compute_shader_decode_agree() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let hardware_rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+    let compute_rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+
+    // BC7 と BC6H はランダムなデータで全てのモードと partition を通す
+    let formats = [
+        (TTBlockCompressionFormat::BC1, 4.0),
+        (TTBlockCompressionFormat::BC2, 4.0),
+        (TTBlockCompressionFormat::BC3, 4.0),
+        (TTBlockCompressionFormat::BC4, 1.5),
+        (TTBlockCompressionFormat::BC5, 1.5),
+        (TTBlockCompressionFormat::BC7, 1.0),
+        (TTBlockCompressionFormat::BC6H, 0.25),
+    ];
+    for (i, (format, tolerance)) in formats.into_iter().enumerate() {
+        let blocks = random_blocks(format, i as u32 + 11);
+        match ctx.upload_block_compressed_texture_with(
+            &hardware_rt,
+            &blocks,
+            format,
+            TTBlockDecompressionPath::Hardware,
+        ) {
+            Ok(()) => {}
+            // TEXTURE_COMPRESSION_BC が無い環境では比べられない
+            Err(TTCEWgpuError::UnsupportedFormat) => return,
+            Err(e) => panic!("{:?}", e),
+        }
+        ctx.upload_block_compressed_texture_with(
+            &compute_rt,
+            &blocks,
+            format,
+            TTBlockDecompressionPath::ComputeShader,
+        )
+        .unwrap();
+
+        let hardware = download_pixels(&mut ctx, &hardware_rt);
+        let compute = download_pixels(&mut ctx, &compute_rt);
+        assert_pixels_near(
+            &hardware,
+            &compute,
+            tolerance / 255.0,
+            &format!("{:?}", format),
+        );
+    }
+}
+
+#[test]
+fn invalid_upload_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+    let format = TTBlockCompressionFormat::BC7;
+
+    let short = vec![0; format.compressed_size(WIDTH, HEIGHT) as usize - 16];
+    assert_eq!(
+        ctx.upload_block_compressed_texture(&rt, &short, format),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+
+    let array = ctx.get_render_texture_with(&desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Float,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D2,
+        2,
+        1,
+    ));
+    let blocks = random_blocks(format, 3);
+    assert_eq!(
+        ctx.upload_block_compressed_texture(&array, &blocks, format),
+        Err(TTCEWgpuError::TextureDimensionMismatch)
+    );
+}
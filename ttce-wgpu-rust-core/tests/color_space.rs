@@ -0,0 +1,122 @@
+mod common;
+
+use common::{
+    bytes_to_f32, create_test_device, desc_with, download, f32_to_bytes, float_rgba_desc,
+};
+use ttce_wgpu_rust_core::{
+    color_space::TTColorSpace,
+    tex_trans_core_engine::{TTCEWgpuError, TTRtDimension},
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 16;
+const HEIGHT: u32 = 8;
+
+fn gradient() -> Vec<f32> {
+    let pixel_count = (WIDTH * HEIGHT) as usize;
+    (0..pixel_count)
+        .flat_map(|i| {
+            let t = i as f32 / (pixel_count - 1) as f32;
+            [t, 1.0 - t, t * 0.5, 0.25]
+        })
+        .collect()
+}
+
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+#[test]
+fn srgb_to_linear_conversion() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let data = gradient();
+
+    let mut rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+    assert_eq!(rt.color_space(), TTColorSpace::Linear);
+    ctx.upload_texture(&rt, &f32_to_bytes(&data), TexTransCoreTextureFormat::Float)
+        .unwrap();
+    rt.set_color_space(TTColorSpace::Srgb);
+
+    ctx.convert_color_space(&mut rt, TTColorSpace::Linear)
+        .unwrap();
+    assert_eq!(rt.color_space(), TTColorSpace::Linear);
+
+    let converted = bytes_to_f32(&download(&mut ctx, &rt, TexTransCoreTextureFormat::Float));
+    for (i, (expected, value)) in data.iter().zip(converted.iter()).enumerate() {
+        // A は変換されない
+        let expected = if i % 4 == 3 {
+            *expected
+        } else {
+            srgb_to_linear(*expected)
+        };
+        assert!(
+            (expected - value).abs() < 0.001,
+            "{} {} {}",
+            i,
+            expected,
+            value
+        );
+    }
+}
+
+#[test]
+fn acescg_round_trip() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let data = gradient();
+
+    let mut rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+    ctx.upload_texture(&rt, &f32_to_bytes(&data), TexTransCoreTextureFormat::Float)
+        .unwrap();
+
+    ctx.convert_color_space(&mut rt, TTColorSpace::AcesCg)
+        .unwrap();
+    let acescg = bytes_to_f32(&download(&mut ctx, &rt, TexTransCoreTextureFormat::Float));
+    assert_ne!(data, acescg);
+
+    ctx.convert_color_space(&mut rt, TTColorSpace::Srgb)
+        .unwrap();
+    ctx.convert_color_space(&mut rt, TTColorSpace::Linear)
+        .unwrap();
+    let round_trip = bytes_to_f32(&download(&mut ctx, &rt, TexTransCoreTextureFormat::Float));
+    for (expected, value) in data.iter().zip(round_trip.iter()) {
+        assert!((expected - value).abs() < 0.001);
+    }
+}
+
+#[test]
+fn color_space_is_reset_on_recycle_and_arrays_are_rejected() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let mut rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+    rt.set_color_space(TTColorSpace::Srgb);
+    drop(rt);
+    let rt = ctx.get_render_texture_with(&float_rgba_desc(WIDTH, HEIGHT));
+    assert_eq!(rt.color_space(), TTColorSpace::Linear);
+
+    let mut array = ctx.get_render_texture_with(&desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Float,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D2,
+        2,
+        1,
+    ));
+    assert_eq!(
+        ctx.convert_color_space(&mut array, TTColorSpace::Srgb),
+        Err(TTCEWgpuError::TextureDimensionMismatch)
+    );
+}
@@ -0,0 +1,223 @@
+//! ブロック圧縮の CPU のリファレンスデコーダー、 GPU のエンコーダーの結果を確かめるためのもの。
+
+use ttce_wgpu_rust_core::block_compression::TTBlockCompressionFormat;
+
+/// blocks をデコードし、 width * height の RGBA を返す。 BC6H 以外は 0.0 から 1.0 になる。
+/// BC7 はモード 6 のみ、 BC6H はモード 11 のみに対応している。
+pub fn decode_blocks(
+    format: TTBlockCompressionFormat,
+    width: u32,
+    height: u32,
+    blocks: &[u8],
+) -> Vec<[f32; 4]> {
+    let block_size = format.block_size() as usize;
+    let blocks_x = width.div_ceil(4) as usize;
+    let mut pixels = vec![[0.0; 4]; (width * height) as usize];
+
+    for (block_index, block) in blocks.chunks_exact(block_size).enumerate() {
+        let decoded = decode_block(format, block);
+        let bx = block_index % blocks_x * 4;
+        let by = block_index / blocks_x * 4;
+        for (i, pixel) in decoded.into_iter().enumerate() {
+            let x = bx + i % 4;
+            let y = by + i / 4;
+            if x < width as usize && y < height as usize {
+                pixels[y * width as usize + x] = pixel;
+            }
+        }
+    }
+    pixels
+}
+
+pub fn decode_block(format: TTBlockCompressionFormat, block: &[u8]) -> [[f32; 4]; 16] {
+    let mut pixels = [[0.0, 0.0, 0.0, 1.0]; 16];
+    match format {
+        TTBlockCompressionFormat::BC1 => pixels = decode_color_block(block, true),
+        TTBlockCompressionFormat::BC3 => {
+            let alpha = decode_single_channel_block(&block[0..8]);
+            pixels = decode_color_block(&block[8..16], false);
+            for (pixel, a) in pixels.iter_mut().zip(alpha) {
+                pixel[3] = a;
+            }
+        }
+        TTBlockCompressionFormat::BC4 => {
+            let r = decode_single_channel_block(block);
+            for (pixel, r) in pixels.iter_mut().zip(r) {
+                pixel[0] = r;
+            }
+        }
+        TTBlockCompressionFormat::BC5 => {
+            let r = decode_single_channel_block(&block[0..8]);
+            let g = decode_single_channel_block(&block[8..16]);
+            for ((pixel, r), g) in pixels.iter_mut().zip(r).zip(g) {
+                pixel[0] = r;
+                pixel[1] = g;
+            }
+        }
+        TTBlockCompressionFormat::BC7 => pixels = decode_bc7_mode6_block(block),
+        TTBlockCompressionFormat::BC2 => {
+            pixels = decode_color_block(&block[8..16], false);
+            for (i, pixel) in pixels.iter_mut().enumerate() {
+                let alpha = (block[i / 2] >> (i % 2 * 4)) & 15;
+                pixel[3] = alpha as f32 / 15.0;
+            }
+        }
+        TTBlockCompressionFormat::BC6H => pixels = decode_bc6h_mode11_block(block),
+    }
+    pixels
+}
+
+fn expand_565(v: u16) -> [f32; 3] {
+    let r = ((v >> 11) & 31) as u32;
+    let g = ((v >> 5) & 63) as u32;
+    let b = (v & 31) as u32;
+    [
+        ((r << 3) | (r >> 2)) as f32 / 255.0,
+        ((g << 2) | (g >> 4)) as f32 / 255.0,
+        ((b << 3) | (b >> 2)) as f32 / 255.0,
+    ]
+}
+
+fn decode_color_block(block: &[u8], allow_three_color: bool) -> [[f32; 4]; 16] {
+    let c0 = u16::from_le_bytes([block[0], block[1]]);
+    let c1 = u16::from_le_bytes([block[2], block[3]]);
+    let bits = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+    let p0 = expand_565(c0);
+    let p1 = expand_565(c1);
+    let lerp = |t: f32| -> [f32; 4] {
+        [
+            p0[0] + (p1[0] - p0[0]) * t,
+            p0[1] + (p1[1] - p0[1]) * t,
+            p0[2] + (p1[2] - p0[2]) * t,
+            1.0,
+        ]
+    };
+    let palette = if c0 > c1 || !allow_three_color {
+        [lerp(0.0), lerp(1.0), lerp(1.0 / 3.0), lerp(2.0 / 3.0)]
+    } else {
+        [lerp(0.0), lerp(1.0), lerp(0.5), [0.0, 0.0, 0.0, 0.0]]
+    };
+
+    std::array::from_fn(|i| palette[((bits >> (i * 2)) & 3) as usize])
+}
+
+fn decode_single_channel_block(block: &[u8]) -> [f32; 16] {
+    let r0 = block[0] as f32;
+    let r1 = block[1] as f32;
+    let mut bits = 0_u64;
+    for (i, b) in block[2..8].iter().enumerate() {
+        bits |= (*b as u64) << (i * 8);
+    }
+    let palette: [f32; 8] = if r0 > r1 {
+        std::array::from_fn(|k| match k {
+            0 => r0,
+            1 => r1,
+            k => ((8 - k) as f32 * r0 + (k - 1) as f32 * r1) / 7.0,
+        })
+    } else {
+        std::array::from_fn(|k| match k {
+            0 => r0,
+            1 => r1,
+            6 => 0.0,
+            7 => 255.0,
+            k => ((6 - k) as f32 * r0 + (k - 1) as f32 * r1) / 5.0,
+        })
+    };
+    std::array::from_fn(|i| palette[((bits >> (i * 3)) & 7) as usize] / 255.0)
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+impl BitReader<'_> {
+    fn read(&mut self, count: usize) -> u32 {
+        let mut value = 0;
+        for i in 0..count {
+            let bit = (self.data[(self.offset + i) / 8] >> ((self.offset + i) % 8)) & 1;
+            value |= (bit as u32) << i;
+        }
+        self.offset += count;
+        value
+    }
+}
+
+const BC7_WEIGHTS_4: [u32; 16] = [0, 4, 9, 13, 17, 21, 26, 30, 34, 38, 43, 47, 51, 55, 60, 64];
+
+fn decode_bc7_mode6_block(block: &[u8]) -> [[f32; 4]; 16] {
+    let mut reader = BitReader {
+        data: block,
+        offset: 0,
+    };
+    assert_eq!(reader.read(7), 1 << 6, "only mode 6 is supported");
+
+    let mut endpoints = [[0_u32; 4]; 2];
+    for channel in 0..4 {
+        endpoints[0][channel] = reader.read(7);
+        endpoints[1][channel] = reader.read(7);
+    }
+    let p0 = reader.read(1);
+    let p1 = reader.read(1);
+    for channel in 0..4 {
+        endpoints[0][channel] = (endpoints[0][channel] << 1) | p0;
+        endpoints[1][channel] = (endpoints[1][channel] << 1) | p1;
+    }
+
+    std::array::from_fn(|i| {
+        let index = reader.read(if i == 0 { 3 } else { 4 });
+        let w = BC7_WEIGHTS_4[index as usize];
+        std::array::from_fn(|channel| {
+            (((64 - w) * endpoints[0][channel] + w * endpoints[1][channel] + 32) >> 6) as f32
+                / 255.0
+        })
+    })
+}
+
+fn bc6h_unquantize(v: u32, bits: u32) -> u32 {
+    if v == 0 {
+        0
+    } else if v == (1 << bits) - 1 {
+        0xFFFF
+    } else {
+        ((v << 16) + 0x8000) >> bits
+    }
+}
+
+// 符号なしの half のビットを f32 にする
+fn unsigned_half_to_f32(bits: u32) -> f32 {
+    let exponent = bits >> 10;
+    let mantissa = (bits & 0x3FF) as f32;
+    if exponent == 0 {
+        mantissa * 2.0_f32.powi(-24)
+    } else {
+        (1.0 + mantissa / 1024.0) * 2.0_f32.powi(exponent as i32 - 15)
+    }
+}
+
+// モード 11 は 10bit の端点をそのまま持つ一つの region のもの
+fn decode_bc6h_mode11_block(block: &[u8]) -> [[f32; 4]; 16] {
+    let mut reader = BitReader {
+        data: block,
+        offset: 0,
+    };
+    assert_eq!(reader.read(5), 0x03, "only mode 11 is supported");
+
+    let mut endpoints = [[0_u32; 3]; 2];
+    for endpoint in endpoints.iter_mut() {
+        for channel in endpoint.iter_mut() {
+            *channel = bc6h_unquantize(reader.read(10), 10);
+        }
+    }
+
+    std::array::from_fn(|i| {
+        let index = reader.read(if i == 0 { 3 } else { 4 });
+        let w = BC7_WEIGHTS_4[index as usize];
+        let mut pixel = [1.0; 4];
+        for (channel, value) in pixel.iter_mut().take(3).enumerate() {
+            let interpolated =
+                ((64 - w) * endpoints[0][channel] + w * endpoints[1][channel] + 32) >> 6;
+            *value = unsigned_half_to_f32((interpolated * 31) >> 6);
+        }
+        pixel
+    })
+}
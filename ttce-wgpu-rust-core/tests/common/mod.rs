@@ -0,0 +1,239 @@
+#![allow(dead_code)]
+
+pub mod block_compression;
+
+use std::{
+    env::consts::{DLL_PREFIX, DLL_SUFFIX},
+    future::Future,
+    path::Path,
+};
+
+use ttce_wgpu_rust_core::{
+    adapter,
+    dxc_ctx::DirectXCompilerContext,
+    render_texture::{TTRenderTexture, TTTextureRegion},
+    tex_trans_core_engine::{
+        RequestFormat, TTRtDimension, TTRtRequestDescriptor, TexTransCoreEngineContext,
+        TexTransCoreEngineDevice,
+    },
+    RequestDevicePreference, TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+pub const ALL_FORMAT: [TexTransCoreTextureFormat; 4] = [
+    TexTransCoreTextureFormat::Byte,
+    TexTransCoreTextureFormat::UShort,
+    TexTransCoreTextureFormat::Half,
+    TexTransCoreTextureFormat::Float,
+];
+pub const ALL_CHANNEL: [TexTransCoreTextureChannel; 3] = [
+    TexTransCoreTextureChannel::R,
+    TexTransCoreTextureChannel::RG,
+    TexTransCoreTextureChannel::RGBA,
+];
+
+/// "auto" にすると GPU も含めて使えるアダプターを選ぶ。既定ではソフトウェアのアダプターのみを使う。
+pub const TEST_ADAPTER_ENV: &str = "TTCE_WGPU_TEST_ADAPTER";
+/// "1" にするとアダプターが見つからない場合にテストをスキップする。既定ではパニックしてテストを失敗させる。
+pub const SKIP_GPU_TESTS_ENV: &str = "TTCE_WGPU_SKIP_GPU_TESTS";
+
+/// None が返るのは SKIP_GPU_TESTS_ENV でスキップを明示した場合のみで、そのテストはスキップされる。
+pub fn create_test_device() -> Option<TexTransCoreEngineDevice> {
+    let mut engine = create_test_device_without_convertor()?;
+    engine.register_format_convertor();
+    Some(engine)
+}
+/// フォーマットコンバーターを登録する前の状態のデバイス
+pub fn create_test_device_without_convertor() -> Option<TexTransCoreEngineDevice> {
+    let (device, queue) = request_test_device_and_queue(wgpu::Features::all())?;
+    Some(TexTransCoreEngineDevice::new(device, queue, None))
+}
+/// HLSL を登録できるデバイス、 dxcompiler はリポジトリの dxcompiler_build から読み込む。
+pub fn create_test_device_with_dxc() -> Option<TexTransCoreEngineDevice> {
+    create_test_device_with_dxc_and_features(wgpu::Features::all())
+}
+/// optional_features に含まれない任意の機能を無効にしたデバイス、機能が無い環境の再現に使う。
+pub fn create_test_device_with_dxc_and_features(
+    optional_features: wgpu::Features,
+) -> Option<TexTransCoreEngineDevice> {
+    let library_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("../dxcompiler_build")
+        .join(format!("{}dxcompiler{}", DLL_PREFIX, DLL_SUFFIX));
+    let dxc_ctx = match DirectXCompilerContext::from_library_path(library_path.clone()) {
+        Ok(dxc_ctx) => dxc_ctx,
+        Err(e) => {
+            return skip_or_panic(&format!(
+                "dxcompiler is not found at {} : {:?}",
+                library_path.display(),
+                e
+            ))
+        }
+    };
+    let (device, queue) = request_test_device_and_queue(optional_features)?;
+    let mut engine = TexTransCoreEngineDevice::new(device, queue, Some(dxc_ctx));
+    engine.register_format_convertor();
+    Some(engine)
+}
+fn request_test_device_and_queue(
+    optional_features: wgpu::Features,
+) -> Option<(wgpu::Device, wgpu::Queue)> {
+    let preference = match std::env::var(TEST_ADAPTER_ENV).as_deref() {
+        Err(_) | Ok("software") => RequestDevicePreference::SoftwareFallback,
+        Ok("auto") => RequestDevicePreference::Auto,
+        Ok(other) => panic!("unknown {} : {}", TEST_ADAPTER_ENV, other),
+    };
+
+    match adapter::request_device_with_preference_and_features(preference, optional_features) {
+        Ok(device_and_queue) => Some(device_and_queue),
+        Err(e) => skip_or_panic(&format!(
+            "adapter is not found with {:?} : {}",
+            preference, e
+        )),
+    }
+}
+
+/// SKIP_GPU_TESTS_ENV が "1" の場合は None を返し、それ以外はパニックする。
+pub fn skip_or_panic<T>(message: &str) -> Option<T> {
+    if std::env::var(SKIP_GPU_TESTS_ENV).as_deref() == Ok("1") {
+        eprintln!("skipped, {}", message);
+        return None;
+    }
+    panic!(
+        "{} (set {}=1 to skip, or {}=auto to use any adapter)",
+        message, SKIP_GPU_TESTS_ENV, TEST_ADAPTER_ENV
+    );
+}
+
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+        .block_on(future)
+}
+
+pub fn bytes_per_pixel(
+    format: TexTransCoreTextureFormat,
+    channel: TexTransCoreTextureChannel,
+) -> usize {
+    let component_size = match format {
+        TexTransCoreTextureFormat::Byte => 1,
+        TexTransCoreTextureFormat::UShort => 2,
+        TexTransCoreTextureFormat::Half => 2,
+        TexTransCoreTextureFormat::Float => 4,
+    };
+    component_size * channel as usize
+}
+
+/// 再現性のあるテスト用のデータ (xorshift32)
+pub fn pseudo_random_bytes(len: usize, seed: u32) -> Vec<u8> {
+    let mut state = seed.max(1);
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect()
+}
+
+pub fn download(
+    ctx: &mut TexTransCoreEngineContext,
+    render_texture: &TTRenderTexture,
+    format: TexTransCoreTextureFormat,
+) -> Vec<u8> {
+    let (_, channel) = render_texture.tt_format().unwrap();
+    let mut data = vec![
+        0_u8;
+        render_texture.width() as usize
+            * render_texture.height() as usize
+            * bytes_per_pixel(format, channel)
+    ];
+    block_on(ctx.download_texture(render_texture, Some(format), &mut data)).unwrap();
+    data
+}
+
+/// 2D で mip も layer も 1 つのレンダーテクスチャーの記述子
+pub fn desc(
+    width: u32,
+    height: u32,
+    format: TexTransCoreTextureFormat,
+    channel: TexTransCoreTextureChannel,
+) -> TTRtRequestDescriptor {
+    desc_with(width, height, format, channel, TTRtDimension::D2, 1, 1)
+}
+pub fn desc_with(
+    width: u32,
+    height: u32,
+    format: TexTransCoreTextureFormat,
+    channel: TexTransCoreTextureChannel,
+    dimension: TTRtDimension,
+    depth_or_array_layers: u32,
+    mip_level_count: u32,
+) -> TTRtRequestDescriptor {
+    TTRtRequestDescriptor {
+        width,
+        height,
+        format: RequestFormat::Manual(format, channel),
+        mip_level_count,
+        dimension,
+        depth_or_array_layers,
+    }
+}
+pub fn byte_rgba_desc(width: u32, height: u32) -> TTRtRequestDescriptor {
+    desc(
+        width,
+        height,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+    )
+}
+pub fn float_rgba_desc(width: u32, height: u32) -> TTRtRequestDescriptor {
+    desc(
+        width,
+        height,
+        TexTransCoreTextureFormat::Float,
+        TexTransCoreTextureChannel::RGBA,
+    )
+}
+
+/// mip 0 の矩形
+pub fn region(layer: u32, x: u32, y: u32, width: u32, height: u32) -> TTTextureRegion {
+    TTTextureRegion {
+        mip_level: 0,
+        layer,
+        x,
+        y,
+        width,
+        height,
+    }
+}
+
+/// RGBA の Byte として mip_level と layer の 2D の面を読み出す
+pub fn download_layer(
+    ctx: &mut TexTransCoreEngineContext,
+    rt: &TTRenderTexture,
+    mip_level: u32,
+    layer: u32,
+) -> Vec<u8> {
+    let size = rt.mip_size(mip_level);
+    let mut data = vec![0_u8; size.width as usize * size.height as usize * 4];
+    block_on(ctx.download_texture_layer(
+        rt,
+        mip_level,
+        layer,
+        Some(TexTransCoreTextureFormat::Byte),
+        &mut data,
+    ))
+    .unwrap();
+    data
+}
+
+pub fn f32_to_bytes(values: &[f32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+pub fn bytes_to_f32(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect()
+}
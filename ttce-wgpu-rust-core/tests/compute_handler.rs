@@ -0,0 +1,349 @@
+mod common;
+
+use common::{
+    byte_rgba_desc, create_test_device, create_test_device_with_dxc, desc, download,
+    pseudo_random_bytes,
+};
+use ttce_wgpu_rust_core::{
+    compute_shader::{TTBindingPoint, TTComputeShaderID},
+    tex_trans_core_engine::TTCEWgpuError,
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 16;
+
+#[test]
+fn unregistered_compute_shader_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    assert!(matches!(
+        ctx.get_compute_handler(&TTComputeShaderID::from(u32::MAX)),
+        Err(TTCEWgpuError::UnregisteredComputeShader)
+    ));
+}
+
+#[test]
+fn register_hlsl_without_dxc_is_compile_error() {
+    // create_test_device は dxcompiler を読み込まずに作られる
+    let Some(mut engine) = create_test_device() else {
+        return;
+    };
+
+    let result = engine.register_compute_shader_from_hlsl("two_group.hlsl", Some(TWO_GROUP_HLSL));
+    assert_eq!(result.unwrap_err().kind, TTCEWgpuError::CompileError);
+}
+
+#[test]
+fn dispatch_converter_with_handler() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let converter_id = engine
+        .get_converter_id(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba16Unorm,
+        )
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+
+    let src = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let dist = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::UShort,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 3);
+    ctx.upload_texture(&src, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    {
+        let mut handler = ctx.get_compute_handler(&converter_id).unwrap();
+        let src_index = handler.get_bind_index("SrcTex").unwrap();
+        let dist_index = handler.get_bind_index("DistTex").unwrap();
+        handler.set_render_texture(src_index, &src).unwrap();
+        handler.set_render_texture(dist_index, &dist).unwrap();
+
+        let wg_size = handler.get_work_group_size();
+        handler
+            .dispatch(WIDTH / wg_size.x, HEIGHT / wg_size.y, 1)
+            .unwrap();
+    }
+
+    assert_eq!(
+        data,
+        download(&mut ctx, &dist, TexTransCoreTextureFormat::Byte)
+    );
+}
+
+#[test]
+fn dispatch_indirect_converter_with_handler() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let converter_id = engine
+        .get_converter_id(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba16Unorm,
+        )
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+
+    let src = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let dist = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::UShort,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 7);
+    ctx.upload_texture(&src, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    // 先頭の 4 byte は読み飛ばされる
+    let args: Vec<u8> = [0, WIDTH / 16, HEIGHT / 16, 1]
+        .iter()
+        .flat_map(|v: &u32| v.to_le_bytes())
+        .collect();
+    let indirect_buffer = ctx.upload_storage_buffer(&args, false, true);
+    let not_indirect_buffer = ctx.upload_storage_buffer(&args, false, false);
+
+    {
+        let mut handler = ctx.get_compute_handler(&converter_id).unwrap();
+        let src_index = handler.get_bind_index("SrcTex").unwrap();
+        let dist_index = handler.get_bind_index("DistTex").unwrap();
+        handler.set_render_texture(src_index, &src).unwrap();
+        handler.set_render_texture(dist_index, &dist).unwrap();
+
+        assert_eq!(
+            handler.dispatch_indirect(&not_indirect_buffer, 4),
+            Err(TTCEWgpuError::InvalidArgument)
+        );
+        assert_eq!(
+            handler.dispatch_indirect(&indirect_buffer, 2),
+            Err(TTCEWgpuError::InvalidArgument)
+        );
+        assert_eq!(
+            handler.dispatch_indirect(&indirect_buffer, 8),
+            Err(TTCEWgpuError::InvalidArgument)
+        );
+        // 足すとあふれる位置もパニックせずにエラーになる
+        assert_eq!(
+            handler.dispatch_indirect(&indirect_buffer, u64::MAX - 3),
+            Err(TTCEWgpuError::InvalidArgument)
+        );
+        handler.dispatch_indirect(&indirect_buffer, 4).unwrap();
+    }
+
+    assert_eq!(
+        data,
+        download(&mut ctx, &dist, TexTransCoreTextureFormat::Byte)
+    );
+}
+
+#[test]
+fn bind_group_is_reused_until_binding_changed() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let converter_id = engine
+        .get_converter_id(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba16Unorm,
+        )
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+
+    let src = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let dist = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::UShort,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+    let other_dist = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::UShort,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 5);
+    ctx.upload_texture(&src, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    let before = engine.bind_group_statistics();
+    {
+        let mut handler = ctx.get_compute_handler(&converter_id).unwrap();
+        let src_index = handler.get_bind_index("SrcTex").unwrap();
+        let dist_index = handler.get_bind_index("DistTex").unwrap();
+        let wg_size = handler.get_work_group_size();
+
+        for _ in 0..3 {
+            handler.set_render_texture(src_index, &src).unwrap();
+            handler.set_render_texture(dist_index, &dist).unwrap();
+            handler
+                .dispatch(WIDTH / wg_size.x, HEIGHT / wg_size.y, 1)
+                .unwrap();
+        }
+        let statistics = engine.bind_group_statistics();
+        assert_eq!(statistics.created_count - before.created_count, 1);
+        assert_eq!(statistics.reused_count - before.reused_count, 2);
+
+        handler.set_render_texture(dist_index, &other_dist).unwrap();
+        handler
+            .dispatch(WIDTH / wg_size.x, HEIGHT / wg_size.y, 1)
+            .unwrap();
+        let statistics = engine.bind_group_statistics();
+        assert_eq!(statistics.created_count - before.created_count, 2);
+        assert_eq!(statistics.reused_count - before.reused_count, 2);
+    }
+
+    assert_eq!(
+        data,
+        download(&mut ctx, &other_dist, TexTransCoreTextureFormat::Byte)
+    );
+}
+
+#[test]
+fn binding_type_mismatch_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let converter_id = engine
+        .get_converter_id(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba32Float,
+        )
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+    let storage_buffer = ctx.allocate_storage_buffer(16, false, false);
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+
+    let mut handler = ctx.get_compute_handler(&converter_id).unwrap();
+    let src_index = handler.get_bind_index("SrcTex").unwrap();
+
+    assert_eq!(handler.get_bind_index("NotExist"), None);
+    assert_eq!(src_index, TTBindingPoint::new(0, 0));
+    assert_eq!(
+        handler.get_bind_index("DistTex"),
+        Some(TTBindingPoint::new(0, 1))
+    );
+    // 同じ binding でも group が違えば別物として扱う
+    assert_eq!(
+        handler.set_render_texture(TTBindingPoint::new(1, 0), &rt),
+        Err(TTCEWgpuError::BindingNotFound)
+    );
+    assert_eq!(
+        handler.set_render_texture(TTBindingPoint::new(0, u32::MAX), &rt),
+        Err(TTCEWgpuError::BindingNotFound)
+    );
+    assert_eq!(
+        handler.upload_constants_buffer(src_index, &[0; 16]),
+        Err(TTCEWgpuError::BindingIsNotConstantsBuffer)
+    );
+    assert_eq!(
+        handler.set_storage_buffer(src_index, &storage_buffer),
+        Err(TTCEWgpuError::BindingIsNotStorageBuffer)
+    );
+}
+
+// register の space がそのまま group になる、 DXC は番号を種類ごとにずらさないため同じ space の中で番号を重ねない
+const TWO_GROUP_HLSL: &str = r#"
+cbuffer Pos : register(b0) { uint4 Position; };
+cbuffer Params : register(b0, space1) { float4 Value; };
+RWTexture2D<float4> DistTex : register(u1, space1);
+
+[numthreads(1, 1, 1)]
+void CSMain(uint3 id : SV_DispatchThreadID)
+{
+    DistTex[Position.xy] = Value;
+}
+"#;
+
+#[test]
+fn dispatch_with_bindings_in_two_groups() {
+    let Some(mut engine) = create_test_device_with_dxc() else {
+        return;
+    };
+    let shader_id = engine
+        .register_compute_shader_from_hlsl("two_group.hlsl", Some(TWO_GROUP_HLSL))
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture(4, 1, TexTransCoreTextureChannel::RGBA);
+
+    {
+        let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+        let pos = handler.get_bind_index("Pos").unwrap();
+        let params = handler.get_bind_index("Params").unwrap();
+        let dist = handler.get_bind_index("DistTex").unwrap();
+        assert_eq!(pos, TTBindingPoint::new(0, 0));
+        assert_eq!(params, TTBindingPoint::new(1, 0));
+        assert_eq!(dist, TTBindingPoint::new(1, 1));
+
+        handler.set_render_texture(dist, &rt).unwrap();
+        let position: Vec<u8> = [2_u32, 0, 0, 0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        // Byte で誤差なく表せる値にしておく
+        let value: Vec<u8> = [51.0_f32 / 255.0, 102.0 / 255.0, 153.0 / 255.0, 1.0]
+            .iter()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        handler.upload_constants_buffer(pos, &position).unwrap();
+        handler.upload_constants_buffer(params, &value).unwrap();
+        handler.dispatch(1, 1, 1).unwrap();
+    }
+
+    let result = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+    for (x, pixel) in result.chunks_exact(4).enumerate() {
+        let expected = if x == 2 { [51, 102, 153, 255] } else { [0; 4] };
+        assert_eq!(pixel, expected, "{}", x);
+    }
+}
+
+// 使われていない space2 のバインディングがあっても、使われている group までで dispatch できる
+const UNUSED_GROUP_HLSL: &str = r#"
+RWTexture2D<float4> DistTex : register(u0);
+Texture2D<float4> UnusedTex : register(t0, space2);
+
+[numthreads(1, 1, 1)]
+void CSMain(uint3 id : SV_DispatchThreadID)
+{
+    DistTex[id.xy] = float4(0.2, 0.4, 0.6, 1.0);
+}
+"#;
+
+#[test]
+fn dispatch_with_unused_binding_in_trailing_group() {
+    let Some(mut engine) = create_test_device_with_dxc() else {
+        return;
+    };
+    let shader_id = engine
+        .register_compute_shader_from_hlsl("unused_group.hlsl", Some(UNUSED_GROUP_HLSL))
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture(4, 1, TexTransCoreTextureChannel::RGBA);
+    let unused_rt = ctx.get_render_texture(4, 1, TexTransCoreTextureChannel::RGBA);
+
+    {
+        let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+        let dist = handler.get_bind_index("DistTex").unwrap();
+        handler.set_render_texture(dist, &rt).unwrap();
+        // DXC が取り除かずに残した場合も、渡したものは bind されずに無視される
+        if let Some(unused) = handler.get_bind_index("UnusedTex") {
+            assert_eq!(unused, TTBindingPoint::new(2, 0));
+            handler.set_render_texture(unused, &unused_rt).unwrap();
+        }
+        handler.dispatch(4, 1, 1).unwrap();
+    }
+
+    let result = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+    for (x, pixel) in result.chunks_exact(4).enumerate() {
+        assert_eq!(pixel, [51, 102, 153, 255], "{}", x);
+    }
+}
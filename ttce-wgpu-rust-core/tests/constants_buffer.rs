@@ -0,0 +1,242 @@
+mod common;
+
+use common::{create_test_device_with_dxc_and_features, download};
+use ttce_wgpu_rust_core::{
+    compute_shader::TTComputeShaderID, constants_buffer::CONSTANTS_BUFFER_CHUNK_SIZE,
+    tex_trans_core_engine::TexTransCoreEngineDevice, TexTransCoreTextureChannel,
+    TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 64;
+
+// Value と Position を別の cbuffer にして、片方だけを書き換えられるようにする
+const WRITE_PIXEL_HLSL: &str = r#"
+cbuffer Params { float4 Value; };
+cbuffer Pos { uint4 Position; };
+RWTexture2D<float4> DistTex;
+
+[numthreads(1, 1, 1)]
+void CSMain(uint3 id : SV_DispatchThreadID)
+{
+    DistTex[Position.xy] = Value;
+}
+"#;
+
+// push constant が使えると cbuffer の一つがチャンクを使わなくなるため、チャンクの使われ方を見るテストでは無効にする
+fn create_device_without_push_constants() -> Option<TexTransCoreEngineDevice> {
+    create_test_device_with_dxc_and_features(wgpu::Features::all() - wgpu::Features::PUSH_CONSTANTS)
+}
+
+fn register_write_pixel(engine: &mut TexTransCoreEngineDevice) -> TTComputeShaderID {
+    engine
+        .register_compute_shader_from_hlsl("write_pixel.hlsl", Some(WRITE_PIXEL_HLSL))
+        .unwrap()
+}
+
+/// Byte で誤差なく表せる値にしておく
+fn value_bytes(value: u8) -> Vec<u8> {
+    [value as f32 / 255.0, 0.0, 0.0, 1.0]
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+fn position_bytes(x: u32) -> Vec<u8> {
+    [x, 0, 0, 0].iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[test]
+fn many_dispatch_reuse_one_chunk() {
+    let Some(mut engine) = create_device_without_push_constants() else {
+        return;
+    };
+    let shader_id = register_write_pixel(&mut engine);
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture(WIDTH, 1, TexTransCoreTextureChannel::RGBA);
+
+    {
+        let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+        let params = handler.get_bind_index("Params").unwrap();
+        let pos = handler.get_bind_index("Pos").unwrap();
+        let dist = handler.get_bind_index("DistTex").unwrap();
+        handler.set_render_texture(dist, &rt).unwrap();
+
+        // 途中で何度か send_command されても、チャンクは先頭から使いまわされる
+        for x in 0..WIDTH {
+            handler
+                .upload_constants_buffer(params, &value_bytes(x as u8 * 2))
+                .unwrap();
+            handler
+                .upload_constants_buffer(pos, &position_bytes(x))
+                .unwrap();
+            handler.dispatch(1, 1, 1).unwrap();
+        }
+    }
+
+    let statistics = ctx.constants_buffer_statistics();
+    assert_eq!(statistics.chunk_count, 1);
+    assert_eq!(statistics.buffer_created_count, 1);
+    assert_eq!(statistics.suballocated_count, WIDTH as u64 * 2);
+
+    let result = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+    for (x, pixel) in result.chunks_exact(4).enumerate() {
+        assert_eq!(pixel, [x as u8 * 2, 0, 0, 255], "{}", x);
+    }
+}
+
+#[test]
+fn offsets_are_aligned_and_large_data_gets_own_buffer() {
+    let Some(mut engine) = create_device_without_push_constants() else {
+        return;
+    };
+    let shader_id = register_write_pixel(&mut engine);
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture(WIDTH, 1, TexTransCoreTextureChannel::RGBA);
+
+    // デバイスは既定の limits で要求しているため、アライメントは既定値になる
+    let alignment = wgpu::Limits::default().min_uniform_buffer_offset_alignment as u64;
+    let per_chunk = CONSTANTS_BUFFER_CHUNK_SIZE / alignment;
+    {
+        let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+        let pos = handler.get_bind_index("Pos").unwrap();
+        // 16 byte のデータでもアライメントの分だけ進むため、 per_chunk 個でちょうど一つのチャンクが埋まる
+        for i in 0..per_chunk {
+            handler
+                .upload_constants_buffer(pos, &position_bytes(i as u32))
+                .unwrap();
+        }
+    }
+    let statistics = ctx.constants_buffer_statistics();
+    assert_eq!(statistics.chunk_count, 1);
+    assert_eq!(statistics.suballocated_count, per_chunk);
+
+    {
+        let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+        let params = handler.get_bind_index("Params").unwrap();
+        let pos = handler.get_bind_index("Pos").unwrap();
+        let dist = handler.get_bind_index("DistTex").unwrap();
+        handler.set_render_texture(dist, &rt).unwrap();
+
+        // 二つ目のチャンクに入る、アライメントが合っていなければ dispatch で検証エラーになる
+        handler
+            .upload_constants_buffer(params, &value_bytes(10))
+            .unwrap();
+        handler
+            .upload_constants_buffer(pos, &position_bytes(3))
+            .unwrap();
+        handler.dispatch(1, 1, 1).unwrap();
+
+        // チャンクに収まらないものは専用のバッファーになり、チャンクは増えない
+        let large = vec![0; CONSTANTS_BUFFER_CHUNK_SIZE as usize + 16];
+        handler.upload_constants_buffer(params, &large).unwrap();
+    }
+
+    let statistics = ctx.constants_buffer_statistics();
+    assert_eq!(statistics.chunk_count, 2);
+    assert_eq!(statistics.buffer_created_count, 3);
+    assert_eq!(statistics.suballocated_count, per_chunk + 2);
+
+    let result = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+    assert_eq!(result[3 * 4..4 * 4], [10, 0, 0, 255]);
+}
+
+#[test]
+fn cached_bind_group_is_rebound_after_send_command() {
+    let Some(mut engine) = create_device_without_push_constants() else {
+        return;
+    };
+    let shader_id = register_write_pixel(&mut engine);
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture(WIDTH, 1, TexTransCoreTextureChannel::RGBA);
+    ctx.send_command();
+
+    {
+        let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+        let params = handler.get_bind_index("Params").unwrap();
+        let pos = handler.get_bind_index("Pos").unwrap();
+        let dist = handler.get_bind_index("DistTex").unwrap();
+        handler.set_render_texture(dist, &rt).unwrap();
+
+        // Pos がチャンクの先頭に書き込まれる
+        handler
+            .upload_constants_buffer(pos, &position_bytes(5))
+            .unwrap();
+        handler
+            .upload_constants_buffer(params, &value_bytes(1))
+            .unwrap();
+        // max_command_stack_count の既定値 16 を超えた最後の dispatch の後で送信される
+        for _ in 0..17 {
+            handler.dispatch(1, 1, 1).unwrap();
+        }
+
+        // 送信後はチャンクの先頭が Params で上書きされるため、古い bind group のままだと Pos が壊れる
+        handler
+            .upload_constants_buffer(params, &value_bytes(2))
+            .unwrap();
+        handler.dispatch(1, 1, 1).unwrap();
+    }
+
+    let result = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+    for (x, pixel) in result.chunks_exact(4).enumerate() {
+        let expected = if x == 5 { [2, 0, 0, 255] } else { [0; 4] };
+        assert_eq!(pixel, expected, "{}", x);
+    }
+}
+
+#[test]
+fn changing_constants_does_not_recreate_bind_group() {
+    for optional_features in [
+        wgpu::Features::all(),
+        wgpu::Features::all() - wgpu::Features::PUSH_CONSTANTS,
+    ] {
+        let Some(mut engine) = create_test_device_with_dxc_and_features(optional_features) else {
+            return;
+        };
+        let shader_id = register_write_pixel(&mut engine);
+        let mut ctx = engine.create_ctx();
+        let rt = ctx.get_render_texture(WIDTH, 1, TexTransCoreTextureChannel::RGBA);
+
+        let before = engine.bind_group_statistics();
+        {
+            let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+            let params = handler.get_bind_index("Params").unwrap();
+            let pos = handler.get_bind_index("Pos").unwrap();
+            let dist = handler.get_bind_index("DistTex").unwrap();
+            handler.set_render_texture(dist, &rt).unwrap();
+
+            // オフセットは dispatch のたびに渡され、途中の send_command の後も同じチャンクに戻るため bind group は一つのまま
+            for x in 0..WIDTH {
+                handler
+                    .upload_constants_buffer(params, &value_bytes(x as u8 * 3))
+                    .unwrap();
+                handler
+                    .upload_constants_buffer(pos, &position_bytes(x))
+                    .unwrap();
+                handler.dispatch(1, 1, 1).unwrap();
+            }
+        }
+        let statistics = engine.bind_group_statistics();
+        assert_eq!(
+            statistics.created_count - before.created_count,
+            1,
+            "{:?}",
+            optional_features
+        );
+        assert_eq!(
+            statistics.reused_count - before.reused_count,
+            WIDTH as u64 - 1,
+            "{:?}",
+            optional_features
+        );
+
+        let result = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+        for (x, pixel) in result.chunks_exact(4).enumerate() {
+            assert_eq!(
+                pixel,
+                [x as u8 * 3, 0, 0, 255],
+                "{:?} {}",
+                optional_features,
+                x
+            );
+        }
+    }
+}
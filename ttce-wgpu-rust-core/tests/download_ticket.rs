@@ -0,0 +1,181 @@
+mod common;
+
+use std::sync::mpsc;
+
+use common::{block_on, byte_rgba_desc, create_test_device, desc, pseudo_random_bytes, region};
+use ttce_wgpu_rust_core::{
+    download_ticket::TTDownloadTicketState, render_texture::TTTextureRegion,
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+
+#[test]
+fn request_download_and_wait() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 3);
+    ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    let ticket = ctx
+        .request_download_texture(
+            &rt,
+            TTTextureRegion::whole(&rt, 0, 0),
+            Some(TexTransCoreTextureFormat::Byte),
+            None,
+        )
+        .unwrap();
+    assert_eq!(ticket.data_size(), data.len() as u64);
+    assert_eq!(ticket.wait(), Ok(TTDownloadTicketState::Ready));
+
+    let mut downloaded = vec![0_u8; ticket.data_size() as usize];
+    ticket.read(&mut downloaded).unwrap();
+    assert_eq!(data, downloaded);
+}
+
+#[test]
+fn completed_callback_is_called_by_poll() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+
+    let (sender, receiver) = mpsc::channel();
+    let tickets: Vec<_> = [
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureFormat::Float,
+    ]
+    .into_iter()
+    .map(|format| {
+        let sender = sender.clone();
+        ctx.request_download_texture(
+            &rt,
+            TTTextureRegion::whole(&rt, 0, 0),
+            Some(format),
+            Some(Box::new(move |state| sender.send(state).unwrap())),
+        )
+        .unwrap()
+    })
+    .collect();
+
+    while tickets
+        .iter()
+        .any(|t| t.state() == TTDownloadTicketState::Pending)
+    {
+        engine.poll();
+        std::thread::yield_now();
+    }
+
+    let states: Vec<_> = receiver.try_iter().collect();
+    assert_eq!(states, vec![TTDownloadTicketState::Ready; 2]);
+
+    let mut downloaded = vec![1_u8; tickets[1].data_size() as usize];
+    tickets[1].read(&mut downloaded).unwrap();
+    assert!(downloaded.iter().all(|b| *b == 0));
+}
+
+#[test]
+fn read_size_mismatch_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+
+    let ticket = ctx
+        .request_download_texture(&rt, TTTextureRegion::whole(&rt, 0, 0), None, None)
+        .unwrap();
+    ticket.wait().unwrap();
+    let mut downloaded = vec![0_u8; 3];
+    assert!(ticket.read(&mut downloaded).is_err());
+}
+
+#[test]
+fn request_download_unaligned_width() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let rt = ctx.get_render_texture_with(&desc(
+        100,
+        7,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::R,
+    ));
+    let data = pseudo_random_bytes(100 * 7, 11);
+    ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    let ticket = ctx
+        .request_download_texture(&rt, TTTextureRegion::whole(&rt, 0, 0), None, None)
+        .unwrap();
+    assert_eq!(ticket.data_size(), data.len() as u64);
+    assert_eq!(ticket.wait(), Ok(TTDownloadTicketState::Ready));
+
+    let mut downloaded = vec![0_u8; data.len()];
+    ticket.read(&mut downloaded).unwrap();
+    assert_eq!(data, downloaded);
+}
+
+#[test]
+fn request_download_region() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 5);
+    ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    let download_region = region(0, 3, 10, 21, 5);
+    // 変換を挟む場合も同じ矩形が読まれる
+    for format in [
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureFormat::Float,
+    ] {
+        let ticket = ctx
+            .request_download_texture(&rt, download_region, Some(format), None)
+            .unwrap();
+        assert_eq!(ticket.wait(), Ok(TTDownloadTicketState::Ready));
+        let mut downloaded = vec![0_u8; ticket.data_size() as usize];
+        ticket.read(&mut downloaded).unwrap();
+
+        let mut region_downloaded = vec![0_u8; downloaded.len()];
+        block_on(ctx.download_texture_region(
+            &rt,
+            download_region,
+            Some(format),
+            &mut region_downloaded,
+        ))
+        .unwrap();
+        assert_eq!(downloaded, region_downloaded, "{:?}", format);
+
+        if format == TexTransCoreTextureFormat::Byte {
+            let expected: Vec<u8> = (download_region.y..download_region.y + download_region.height)
+                .flat_map(|y| {
+                    let start = ((y * WIDTH + download_region.x) * 4) as usize;
+                    data[start..start + (download_region.width * 4) as usize].to_vec()
+                })
+                .collect();
+            assert_eq!(downloaded, expected);
+        }
+    }
+
+    let out_of_range = TTTextureRegion {
+        x: 60,
+        ..download_region
+    };
+    assert!(ctx
+        .request_download_texture(&rt, out_of_range, None, None)
+        .is_err());
+}
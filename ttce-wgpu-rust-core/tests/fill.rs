@@ -0,0 +1,127 @@
+mod common;
+
+use common::{
+    byte_rgba_desc, create_test_device, desc_with, download_layer, pseudo_random_bytes, region,
+};
+use ttce_wgpu_rust_core::{
+    tex_trans_core_engine::{TTCEWgpuError, TTRtDimension},
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 20;
+const HEIGHT: u32 = 12;
+
+// Byte で誤差なく表せる値にしておく
+const COLOR: [f32; 4] = [51.0 / 255.0, 102.0 / 255.0, 153.0 / 255.0, 1.0];
+const COLOR_BYTES: [u8; 4] = [51, 102, 153, 255];
+
+#[test]
+fn clear_every_mip_and_layer() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    // 2D の配列は render pass 、 3D はシェーダーで塗られる
+    for dimension in [TTRtDimension::D2, TTRtDimension::D3] {
+        let rt = ctx.get_render_texture_with(&desc_with(
+            WIDTH,
+            HEIGHT,
+            TexTransCoreTextureFormat::Byte,
+            TexTransCoreTextureChannel::RGBA,
+            dimension,
+            3,
+            2,
+        ));
+        ctx.clear_render_texture(&rt, COLOR).unwrap();
+        for mip_level in 0..rt.mip_level_count() {
+            for layer in 0..rt.layer_count(mip_level) {
+                let data = download_layer(&mut ctx, &rt, mip_level, layer);
+                assert!(
+                    data.chunks_exact(4).all(|p| p == COLOR_BYTES),
+                    "{:?} {} {}",
+                    dimension,
+                    mip_level,
+                    layer
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn fill_rect_keeps_outside() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    for dimension in [TTRtDimension::D2, TTRtDimension::D3] {
+        let rt = ctx.get_render_texture_with(&desc_with(
+            WIDTH,
+            HEIGHT,
+            TexTransCoreTextureFormat::Byte,
+            TexTransCoreTextureChannel::RGBA,
+            dimension,
+            2,
+            1,
+        ));
+        let data = pseudo_random_bytes((WIDTH * HEIGHT * 4) as usize, 1);
+        for layer in 0..2 {
+            ctx.upload_texture_layer(&rt, 0, layer, &data, TexTransCoreTextureFormat::Byte)
+                .unwrap();
+        }
+
+        let fill_region = region(1, 3, 5, 7, 4);
+        ctx.fill_rect(&rt, fill_region, COLOR).unwrap();
+
+        assert_eq!(download_layer(&mut ctx, &rt, 0, 0), data, "{:?}", dimension);
+        let filled = download_layer(&mut ctx, &rt, 0, 1);
+        for (i, (pixel, original)) in filled.chunks_exact(4).zip(data.chunks_exact(4)).enumerate() {
+            let (x, y) = (i as u32 % WIDTH, i as u32 / WIDTH);
+            let inside = (3..10).contains(&x) && (5..9).contains(&y);
+            let expected = if inside { &COLOR_BYTES[..] } else { original };
+            assert_eq!(pixel, expected, "{:?} {} {}", dimension, x, y);
+        }
+    }
+}
+
+#[test]
+fn fill_rect_out_of_range_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+
+    for invalid in [
+        region(0, WIDTH - 3, 0, 4, 4),
+        region(0, 0, 0, 0, 4),
+        region(1, 0, 0, 4, 4),
+    ] {
+        assert_eq!(
+            ctx.fill_rect(&rt, invalid, COLOR),
+            Err(TTCEWgpuError::InvalidArgument)
+        );
+    }
+}
+
+#[test]
+fn fill_params_use_constants_buffer_chunk() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+
+    let before = ctx.constants_buffer_statistics();
+    for x in 0..4 {
+        ctx.fill_rect(&rt, region(0, x, 0, 1, 1), COLOR).unwrap();
+    }
+    let statistics = ctx.constants_buffer_statistics();
+    assert_eq!(statistics.suballocated_count - before.suballocated_count, 4);
+    assert!(statistics.buffer_created_count - before.buffer_created_count <= 1);
+
+    let data = download_layer(&mut ctx, &rt, 0, 0);
+    assert!(data[..4 * 4].chunks_exact(4).all(|p| p == COLOR_BYTES));
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{byte_rgba_desc, create_test_device, desc, download, pseudo_random_bytes, ALL_FORMAT};
+use ttce_wgpu_rust_core::{
+    tex_trans_core_engine::TTCEWgpuError, TTChannelMapping, TexTransCoreTextureChannel,
+    TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 16;
+
+#[test]
+fn rgba_byte_round_trip_through_every_format() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 64);
+
+    for format in ALL_FORMAT {
+        let rt = ctx.get_render_texture_with(&desc(
+            WIDTH,
+            HEIGHT,
+            format,
+            TexTransCoreTextureChannel::RGBA,
+        ));
+
+        ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+            .unwrap();
+        let downloaded = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+
+        assert_eq!(data, downloaded, "{:?}", format);
+    }
+}
+
+#[test]
+fn round_trip_with_size_not_multiple_of_work_group() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    // ワークグループの 16 で割り切れない大きさでも、右端と下端が変換されること
+    let (width, height) = (37, 21);
+    let data = pseudo_random_bytes(width as usize * height as usize * 4, 17);
+
+    for format in ALL_FORMAT {
+        let rt = ctx.get_render_texture_with(&desc(
+            width,
+            height,
+            format,
+            TexTransCoreTextureChannel::RGBA,
+        ));
+
+        ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+            .unwrap();
+        let downloaded = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+
+        assert_eq!(data, downloaded, "{:?}", format);
+    }
+}
+
+#[test]
+fn rgba_float_download_from_byte_texture() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 255);
+
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+    let downloaded = download(&mut ctx, &rt, TexTransCoreTextureFormat::Float);
+
+    for (byte, float) in data.iter().zip(downloaded.chunks_exact(4)) {
+        let float = f32::from_le_bytes(float.try_into().unwrap());
+        assert!((*byte as f32 / 255.0 - float).abs() < 0.5 / 255.0);
+    }
+}
+
+#[test]
+fn r_and_rg_byte_round_trip_through_every_format() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    for channel in [
+        TexTransCoreTextureChannel::R,
+        TexTransCoreTextureChannel::RG,
+    ] {
+        let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * channel as usize, 9);
+        for format in ALL_FORMAT {
+            let rt = ctx.get_render_texture_with(&desc(WIDTH, HEIGHT, format, channel));
+
+            ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+                .unwrap();
+            let downloaded = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+
+            assert_eq!(data, downloaded, "{:?} {:?}", format, channel);
+        }
+    }
+}
+
+#[test]
+fn convert_texture_changes_channel() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let pixel_count = WIDTH as usize * HEIGHT as usize;
+
+    let rgba_data = pseudo_random_bytes(pixel_count * 4, 21);
+    let rgba = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+    ctx.upload_texture(&rgba, &rgba_data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    // フォーマットも同時に変える
+    let alpha = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Half,
+        TexTransCoreTextureChannel::R,
+    ));
+    ctx.convert_texture(&alpha, &rgba, TTChannelMapping::Alpha)
+        .unwrap();
+    let expected: Vec<u8> = rgba_data.chunks_exact(4).map(|p| p[3]).collect();
+    assert_eq!(
+        expected,
+        download(&mut ctx, &alpha, TexTransCoreTextureFormat::Byte)
+    );
+
+    let luminance = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Float,
+        TexTransCoreTextureChannel::R,
+    ));
+    ctx.convert_texture(&luminance, &rgba, TTChannelMapping::Luminance)
+        .unwrap();
+    let downloaded = download(&mut ctx, &luminance, TexTransCoreTextureFormat::Float);
+    for (pixel, value) in rgba_data.chunks_exact(4).zip(downloaded.chunks_exact(4)) {
+        let value = f32::from_le_bytes(value.try_into().unwrap());
+        let expected =
+            (pixel[0] as f32 * 0.2126 + pixel[1] as f32 * 0.7152 + pixel[2] as f32 * 0.0722)
+                / 255.0;
+        assert!((expected - value).abs() < 0.01);
+    }
+
+    // R から RGBA へは RGB に広げられ、 A は 1 になる
+    let r_data = pseudo_random_bytes(pixel_count, 22);
+    let r = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::R,
+    ));
+    ctx.upload_texture(&r, &r_data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+    let broadcast = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::UShort,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+    ctx.convert_texture(&broadcast, &r, TTChannelMapping::Default)
+        .unwrap();
+    let expected: Vec<u8> = r_data.iter().flat_map(|v| [*v, *v, *v, 255]).collect();
+    assert_eq!(
+        expected,
+        download(&mut ctx, &broadcast, TexTransCoreTextureFormat::Byte)
+    );
+
+    assert_eq!(
+        ctx.convert_texture(&rgba, &r, TTChannelMapping::Luminance),
+        Err(TTCEWgpuError::UnsupportedFormat)
+    );
+}
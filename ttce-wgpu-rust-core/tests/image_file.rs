@@ -0,0 +1,161 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{block_on, bytes_per_pixel, create_test_device, desc, download, pseudo_random_bytes};
+use ttce_wgpu_rust_core::{
+    color_space::TTColorSpace, image_file::TTImageFileFormat, TexTransCoreTextureChannel,
+    TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 24;
+const HEIGHT: u32 = 12;
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("ttce-image-file-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn png_round_trip_keeps_format_and_channel() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let cases = [
+        (
+            TexTransCoreTextureFormat::Byte,
+            TexTransCoreTextureChannel::R,
+        ),
+        (
+            TexTransCoreTextureFormat::Byte,
+            TexTransCoreTextureChannel::RGBA,
+        ),
+        (
+            TexTransCoreTextureFormat::UShort,
+            TexTransCoreTextureChannel::RGBA,
+        ),
+    ];
+    for (i, (format, channel)) in cases.into_iter().enumerate() {
+        let rt = ctx.get_render_texture_with(&desc(WIDTH, HEIGHT, format, channel));
+        let data = pseudo_random_bytes(
+            (WIDTH * HEIGHT) as usize * bytes_per_pixel(format, channel),
+            i as u32 + 1,
+        );
+        ctx.upload_texture(&rt, &data, format).unwrap();
+
+        let path = temp_path(&format!("{}.png", i));
+        block_on(ctx.save_image_file(&rt, &path, TTImageFileFormat::Png)).unwrap();
+        let loaded = ctx.load_image_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.tt_format(), Some((format, channel)));
+        assert_eq!(loaded.color_space(), TTColorSpace::Srgb);
+        assert_eq!(data, download(&mut ctx, &loaded, format), "{}", i);
+    }
+}
+
+#[test]
+fn png_widens_rg_to_opaque_rgba() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    for (i, format) in [
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureFormat::UShort,
+    ]
+    .into_iter()
+    .enumerate()
+    {
+        let rt = ctx.get_render_texture_with(&desc(
+            WIDTH,
+            HEIGHT,
+            format,
+            TexTransCoreTextureChannel::RG,
+        ));
+        let data = pseudo_random_bytes(
+            (WIDTH * HEIGHT) as usize * bytes_per_pixel(format, TexTransCoreTextureChannel::RG),
+            i as u32 + 10,
+        );
+        ctx.upload_texture(&rt, &data, format).unwrap();
+
+        let path = temp_path(&format!("rg-{}.png", i));
+        block_on(ctx.save_image_file(&rt, &path, TTImageFileFormat::Png)).unwrap();
+        let loaded = ctx.load_image_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // G がアルファとして書かれず、 B は 0 、 A は 1 で埋められる
+        assert_eq!(
+            loaded.tt_format(),
+            Some((format, TexTransCoreTextureChannel::RGBA))
+        );
+        let component_size = bytes_per_pixel(format, TexTransCoreTextureChannel::R);
+        let mut expected = Vec::new();
+        for rg in data.chunks_exact(component_size * 2) {
+            expected.extend_from_slice(rg);
+            expected.resize(expected.len() + component_size, 0);
+            expected.resize(expected.len() + component_size, u8::MAX);
+        }
+        assert_eq!(
+            expected,
+            download(&mut ctx, &loaded, format),
+            "{:?}",
+            format
+        );
+    }
+}
+
+#[test]
+fn exr_round_trip_widens_to_rgba_float() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let rt = ctx.get_render_texture_with(&desc(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Float,
+        TexTransCoreTextureChannel::R,
+    ));
+    let values: Vec<f32> = (0..WIDTH * HEIGHT).map(|i| i as f32 * 0.25).collect();
+    let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Float)
+        .unwrap();
+
+    let path = temp_path("r.exr");
+    block_on(ctx.save_image_file(&rt, &path, TTImageFileFormat::Exr)).unwrap();
+    let loaded = ctx.load_image_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        loaded.tt_format(),
+        Some((
+            TexTransCoreTextureFormat::Float,
+            TexTransCoreTextureChannel::RGBA
+        ))
+    );
+    assert_eq!(loaded.color_space(), TTColorSpace::Linear);
+
+    let downloaded = download(&mut ctx, &loaded, TexTransCoreTextureFormat::Float);
+    let pixels: Vec<f32> = downloaded
+        .chunks_exact(4)
+        .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+        .collect();
+    for (value, pixel) in values.iter().zip(pixels.chunks_exact(4)) {
+        assert_eq!(pixel, [*value, *value, *value, 1.0]);
+    }
+}
+
+#[test]
+fn missing_file_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    assert!(ctx.load_image_file(&temp_path("missing.png")).is_err());
+    assert!(ctx.load_image_file(&temp_path("missing.dds")).is_err());
+}
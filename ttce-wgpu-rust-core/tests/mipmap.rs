@@ -0,0 +1,163 @@
+mod common;
+
+use common::{block_on, create_test_device, desc_with, pseudo_random_bytes};
+use ttce_wgpu_rust_core::{
+    mipmap::MipmapFilter,
+    render_texture::TTRenderTexture,
+    tex_trans_core_engine::{
+        TTCEWgpuError, TTRtDimension, TTRtRequestDescriptor, TexTransCoreEngineContext,
+    },
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+fn mipmapped_desc(width: u32, height: u32) -> TTRtRequestDescriptor {
+    desc_with(
+        width,
+        height,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D2,
+        1,
+        TTRtRequestDescriptor::max_mip_level_count(width, height),
+    )
+}
+
+fn download_mip(
+    ctx: &mut TexTransCoreEngineContext,
+    rt: &TTRenderTexture,
+    mip_level: u32,
+) -> Vec<u8> {
+    let size = rt.mip_size(mip_level);
+    let mut data = vec![0_u8; size.width as usize * size.height as usize * 4];
+    block_on(ctx.download_texture_mip(
+        rt,
+        mip_level,
+        Some(TexTransCoreTextureFormat::Byte),
+        &mut data,
+    ))
+    .unwrap();
+    data
+}
+
+fn assert_all_pixel_near(data: &[u8], expected: [u8; 4], tolerance: u8) {
+    for pixel in data.chunks_exact(4) {
+        for (v, e) in pixel.iter().zip(expected) {
+            assert!(v.abs_diff(e) <= tolerance, "{:?} != {:?}", pixel, expected);
+        }
+    }
+}
+
+#[test]
+fn max_mip_level_count() {
+    assert_eq!(TTRtRequestDescriptor::max_mip_level_count(1, 1), 1);
+    assert_eq!(TTRtRequestDescriptor::max_mip_level_count(64, 32), 7);
+    assert_eq!(TTRtRequestDescriptor::max_mip_level_count(100, 1), 7);
+    assert_eq!(TTRtRequestDescriptor::max_mip_level_count(4096, 4096), 13);
+}
+
+#[test]
+fn upload_download_each_mip() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    for format in [
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureFormat::Float,
+    ] {
+        let rt = ctx.get_render_texture_with(&desc_with(
+            40,
+            24,
+            format,
+            TexTransCoreTextureChannel::RGBA,
+            TTRtDimension::D2,
+            1,
+            TTRtRequestDescriptor::max_mip_level_count(40, 24),
+        ));
+        assert_eq!(rt.mip_level_count(), 6);
+
+        for mip_level in 0..rt.mip_level_count() {
+            let size = rt.mip_size(mip_level);
+            let data = pseudo_random_bytes(
+                size.width as usize * size.height as usize * 4,
+                mip_level + 1,
+            );
+            ctx.upload_texture_mip(&rt, mip_level, &data, TexTransCoreTextureFormat::Byte)
+                .unwrap();
+            assert_eq!(
+                download_mip(&mut ctx, &rt, mip_level),
+                data,
+                "{:?} {}",
+                format,
+                mip_level
+            );
+        }
+    }
+}
+
+#[test]
+fn mip_level_out_of_range_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&mipmapped_desc(4, 4));
+
+    assert_eq!(
+        ctx.upload_texture_mip(&rt, 3, &[0; 4], TexTransCoreTextureFormat::Byte),
+        Err(TTCEWgpuError::InvalidArgument)
+    );
+}
+
+#[test]
+fn generate_mipmap_keeps_uniform_color() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let color = [10, 200, 30, 255];
+
+    for (width, height) in [(16, 16), (5, 3), (33, 7)] {
+        for filter in [MipmapFilter::Box, MipmapFilter::Kaiser] {
+            for gamma_aware in [false, true] {
+                let rt = ctx.get_render_texture_with(&mipmapped_desc(width, height));
+                let data = color.repeat(width as usize * height as usize);
+                ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+                    .unwrap();
+
+                ctx.generate_mipmap(&rt, filter, gamma_aware).unwrap();
+
+                for mip_level in 1..rt.mip_level_count() {
+                    assert_all_pixel_near(&download_mip(&mut ctx, &rt, mip_level), color, 1);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn generate_mipmap_box_gamma_aware() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    // 白黒の市松模様
+    let data: Vec<u8> = (0..4 * 4)
+        .flat_map(|i| {
+            let v = if (i % 4 + i / 4) % 2 == 0 { 255 } else { 0 };
+            [v, v, v, 255]
+        })
+        .collect();
+
+    let rt = ctx.get_render_texture_with(&mipmapped_desc(4, 4));
+    ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+    ctx.generate_mipmap(&rt, MipmapFilter::Box, false).unwrap();
+    assert_all_pixel_near(&download_mip(&mut ctx, &rt, 1), [128, 128, 128, 255], 1);
+
+    // リニアで 0.5 は sRGB で 0.735 程度
+    ctx.generate_mipmap(&rt, MipmapFilter::Box, true).unwrap();
+    assert_all_pixel_near(&download_mip(&mut ctx, &rt, 1), [188, 188, 188, 255], 1);
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{create_test_device, create_test_device_without_convertor};
+use ttce_wgpu_rust_core::tex_trans_core_engine::TTCEWgpuError;
+
+#[test]
+fn save_and_load_pipeline_cache() {
+    let Some(mut device) = create_test_device_without_convertor() else {
+        return;
+    };
+    let dir = std::env::temp_dir().join(format!("ttce-pipeline-cache-test-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("pipeline.cache");
+
+    let supported = unsafe { device.load_pipeline_cache_from_file(&path) }.unwrap();
+    if !supported {
+        // 対応していない場合は保存も何もしない
+        assert!(!device.save_pipeline_cache_to_file(&path).unwrap());
+        assert!(!path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+        return;
+    }
+
+    device.register_format_convertor();
+    // コンバーターのパイプラインは使われるときに作られるため、一つ使ってキャッシュに載せる
+    let converter_id = device
+        .get_converter_id(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba32Float,
+        )
+        .unwrap();
+    device
+        .create_ctx()
+        .get_compute_handler(&converter_id)
+        .unwrap();
+    if device.save_pipeline_cache_to_file(&path).unwrap() {
+        assert!(path.exists());
+
+        let mut reloaded = create_test_device_without_convertor().unwrap();
+        assert!(unsafe { reloaded.load_pipeline_cache_from_file(&path) }.unwrap());
+        reloaded.register_format_convertor();
+    }
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn load_pipeline_cache_after_registration_is_error() {
+    let Some(mut device) = create_test_device() else {
+        return;
+    };
+    // 登録済みのシェーダーにはキャッシュが使われないため、ファイルを読む前にエラーになる
+    let path = std::env::temp_dir().join("ttce-pipeline-cache-test-not-loaded.cache");
+    let result = unsafe { device.load_pipeline_cache_from_file(&path) };
+    assert_eq!(result.unwrap_err().kind, TTCEWgpuError::InvalidArgument);
+}
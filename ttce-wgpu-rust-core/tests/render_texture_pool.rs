@@ -0,0 +1,113 @@
+mod common;
+
+use common::{byte_rgba_desc, create_test_device, download, pseudo_random_bytes};
+use ttce_wgpu_rust_core::{TexTransCoreTextureChannel, TexTransCoreTextureFormat};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 32;
+const BYTE_SIZE: u64 = WIDTH as u64 * HEIGHT as u64 * 4;
+
+#[test]
+fn dropped_render_texture_is_reused_and_cleared() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let data = pseudo_random_bytes(BYTE_SIZE as usize, 7);
+    ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+    drop(rt);
+    ctx.send_command();
+
+    let statistics = engine.render_texture_pool_statistics();
+    assert_eq!(statistics.pooled_count, 1);
+    assert_eq!(statistics.pooled_byte, BYTE_SIZE);
+
+    let hit_count = statistics.hit_count;
+    let reused = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let statistics = engine.render_texture_pool_statistics();
+    assert_eq!(statistics.hit_count, hit_count + 1);
+    assert_eq!(statistics.pooled_count, 0);
+
+    let downloaded = download(&mut ctx, &reused, TexTransCoreTextureFormat::Byte);
+    assert!(downloaded.iter().all(|b| *b == 0));
+}
+
+#[test]
+fn different_size_is_not_reused() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    drop(ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT)));
+    ctx.send_command();
+    let miss_count = engine.render_texture_pool_statistics().miss_count;
+
+    let _rt = ctx.get_render_texture(WIDTH * 2, HEIGHT, TexTransCoreTextureChannel::RGBA);
+    let statistics = engine.render_texture_pool_statistics();
+    assert_eq!(statistics.miss_count, miss_count + 1);
+    assert_eq!(statistics.pooled_count, 1);
+}
+
+#[test]
+fn pool_budget_evicts_oldest() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    engine.set_render_texture_pool_budget(BYTE_SIZE);
+
+    let a = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let b = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    drop(a);
+    drop(b);
+    ctx.send_command();
+
+    let statistics = engine.render_texture_pool_statistics();
+    assert_eq!(statistics.pooled_count, 1);
+    assert_eq!(statistics.evicted_count, 1);
+
+    engine.set_render_texture_pool_budget(0);
+    let statistics = engine.render_texture_pool_statistics();
+    assert_eq!(statistics.pooled_count, 0);
+    assert_eq!(statistics.pooled_byte, 0);
+
+    drop(ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT)));
+    ctx.send_command();
+    assert_eq!(engine.render_texture_pool_statistics().pooled_count, 0);
+}
+
+#[test]
+fn dropped_render_texture_waits_for_pending_command() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx_a = engine.create_ctx();
+    let mut ctx_b = engine.create_ctx();
+
+    let rt = ctx_a.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    ctx_a.clear_render_texture(&rt, [1.0; 4]).unwrap();
+    drop(rt);
+    assert_eq!(engine.render_texture_pool_statistics().pooled_count, 0);
+
+    // ctx_a のクリアが未送信のうちは、ctx_b には別のテクスチャーが渡される
+    let other = ctx_b.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let data = pseudo_random_bytes(BYTE_SIZE as usize, 3);
+    ctx_b
+        .upload_texture(&other, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+    ctx_a.send_command();
+    assert_eq!(
+        download(&mut ctx_b, &other, TexTransCoreTextureFormat::Byte),
+        data
+    );
+    assert_eq!(engine.render_texture_pool_statistics().pooled_count, 1);
+
+    // コンテキストが破棄された場合も返却される
+    drop(ctx_a.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT)));
+    drop(ctx_a);
+    assert_eq!(engine.render_texture_pool_statistics().pooled_count, 1);
+}
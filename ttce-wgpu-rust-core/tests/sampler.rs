@@ -0,0 +1,197 @@
+mod common;
+
+use common::{
+    create_test_device, create_test_device_with_dxc_and_features, desc, download,
+    pseudo_random_bytes,
+};
+use ttce_wgpu_rust_core::{
+    compute_shader::TTBindingPoint,
+    sampler::{TTSamplerAddressMode, TTSamplerDescriptor, TTSamplerFilterMode},
+    tex_trans_core_engine::TTCEWgpuError,
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+// SAMPLE_HLSL の uv の計算と合わせる
+const SAMPLE_SIZE: u32 = 16;
+const SAMPLE_HLSL: &str = r#"
+Texture2D<float4> SrcTex;
+SamplerState SrcSampler;
+RWTexture2D<float4> DistTex;
+
+[numthreads(8, 8, 1)]
+void CSMain(uint3 id : SV_DispatchThreadID)
+{
+    float2 uv = (id.xy + 0.5) / 16.0;
+    DistTex[id.xy] = SrcTex.SampleLevel(SrcSampler, uv, 0);
+}
+"#;
+const LOAD_HLSL: &str = r#"
+Texture2D<float4> SrcTex;
+RWTexture2D<float4> DistTex;
+
+[numthreads(8, 8, 1)]
+void CSMain(uint3 id : SV_DispatchThreadID)
+{
+    DistTex[id.xy] = SrcTex.Load(int3(id.xy, 0));
+}
+"#;
+
+fn sampler_desc(filter_mode: TTSamplerFilterMode, anisotropy_clamp: u16) -> TTSamplerDescriptor {
+    TTSamplerDescriptor {
+        filter_mode,
+        address_mode_u: TTSamplerAddressMode::Repeat,
+        address_mode_v: TTSamplerAddressMode::Clamp,
+        address_mode_w: TTSamplerAddressMode::Mirror,
+        anisotropy_clamp,
+    }
+}
+
+#[test]
+fn create_sampler_validation() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let ctx = engine.create_ctx();
+
+    for filter_mode in [
+        TTSamplerFilterMode::Point,
+        TTSamplerFilterMode::Bilinear,
+        TTSamplerFilterMode::Trilinear,
+    ] {
+        assert!(ctx.create_sampler(&sampler_desc(filter_mode, 1)).is_ok());
+    }
+    assert!(ctx
+        .create_sampler(&sampler_desc(TTSamplerFilterMode::Trilinear, 16))
+        .is_ok());
+
+    assert!(matches!(
+        ctx.create_sampler(&sampler_desc(TTSamplerFilterMode::Trilinear, 0)),
+        Err(TTCEWgpuError::InvalidArgument)
+    ));
+    assert!(matches!(
+        ctx.create_sampler(&sampler_desc(TTSamplerFilterMode::Trilinear, 17)),
+        Err(TTCEWgpuError::InvalidArgument)
+    ));
+    // 異方性フィルタリングはすべて Linear でないといけない
+    assert!(matches!(
+        ctx.create_sampler(&sampler_desc(TTSamplerFilterMode::Bilinear, 4)),
+        Err(TTCEWgpuError::InvalidArgument)
+    ));
+}
+
+#[test]
+fn set_sampler_to_texture_binding_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let converter_id = engine
+        .get_converter_id(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba32Float,
+        )
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+    let sampler = ctx
+        .create_sampler(&sampler_desc(TTSamplerFilterMode::Bilinear, 1))
+        .unwrap();
+
+    let mut handler = ctx.get_compute_handler(&converter_id).unwrap();
+    let src_index = handler.get_bind_index("SrcTex").unwrap();
+    assert_eq!(
+        handler.set_sampler(src_index, &sampler),
+        Err(TTCEWgpuError::BindingIsNotSampler)
+    );
+    assert_eq!(
+        handler.set_sampler(TTBindingPoint::new(0, u32::MAX), &sampler),
+        Err(TTCEWgpuError::BindingNotFound)
+    );
+}
+
+fn float_filterable_features(float32_filterable: bool) -> wgpu::Features {
+    if float32_filterable {
+        wgpu::Features::all()
+    } else {
+        wgpu::Features::all() - wgpu::Features::FLOAT32_FILTERABLE
+    }
+}
+
+// src_format のテクスチャーを hlsl で DistTex にそのまま写し、 SrcTex のバインドの結果を返す
+fn copy_with_shader(
+    float32_filterable: bool,
+    hlsl: &str,
+    src_format: TexTransCoreTextureFormat,
+) -> Option<Result<(), TTCEWgpuError>> {
+    let mut engine =
+        create_test_device_with_dxc_and_features(float_filterable_features(float32_filterable))?;
+    let shader_id = engine
+        .register_compute_shader_from_hlsl("sample.hlsl", Some(hlsl))
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+    let src = ctx.get_render_texture_with(&desc(
+        SAMPLE_SIZE,
+        SAMPLE_SIZE,
+        src_format,
+        TexTransCoreTextureChannel::RGBA,
+    ));
+    let dist = ctx.get_render_texture(SAMPLE_SIZE, SAMPLE_SIZE, TexTransCoreTextureChannel::RGBA);
+    let data = pseudo_random_bytes((SAMPLE_SIZE * SAMPLE_SIZE * 4) as usize, 7);
+    ctx.upload_texture(&src, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+    let sampler = ctx
+        .create_sampler(&sampler_desc(TTSamplerFilterMode::Bilinear, 1))
+        .unwrap();
+
+    {
+        let mut handler = ctx.get_compute_handler(&shader_id).unwrap();
+        let src_index = handler.get_bind_index("SrcTex").unwrap();
+        let dist_index = handler.get_bind_index("DistTex").unwrap();
+        if let Some(sampler_index) = handler.get_bind_index("SrcSampler") {
+            handler.set_sampler(sampler_index, &sampler).unwrap();
+        }
+        handler.set_render_texture(dist_index, &dist).unwrap();
+        if let Err(e) = handler.set_render_texture(src_index, &src) {
+            return Some(Err(e));
+        }
+        // 同じテクスチャーを再度バインドしても変わらない
+        handler.set_render_texture(src_index, &src).unwrap();
+        handler
+            .dispatch(SAMPLE_SIZE / 8, SAMPLE_SIZE / 8, 1)
+            .unwrap();
+    }
+
+    // テクセルの中心を読んでいるため、補間の有無にかかわらずそのままの値になる
+    let result = download(&mut ctx, &dist, TexTransCoreTextureFormat::Byte);
+    assert_eq!(result, data, "{} {:?}", float32_filterable, src_format);
+    Some(Ok(()))
+}
+
+#[test]
+fn sample_filterable_texture_with_bilinear_sampler() {
+    for (float32_filterable, src_format) in [
+        (true, TexTransCoreTextureFormat::Float),
+        (true, TexTransCoreTextureFormat::Half),
+        (false, TexTransCoreTextureFormat::Half),
+        (false, TexTransCoreTextureFormat::Byte),
+    ] {
+        let Some(result) = copy_with_shader(float32_filterable, SAMPLE_HLSL, src_format) else {
+            return;
+        };
+        assert_eq!(result, Ok(()), "{} {:?}", float32_filterable, src_format);
+    }
+}
+
+#[test]
+fn unfilterable_float_texture_is_error_only_when_sampled() {
+    // サンプラーで読まれるところには渡せない
+    let Some(result) = copy_with_shader(false, SAMPLE_HLSL, TexTransCoreTextureFormat::Float)
+    else {
+        return;
+    };
+    assert_eq!(result, Err(TTCEWgpuError::TextureIsNotFilterable));
+
+    // Load でのみ読まれる場合は渡せる
+    let Some(result) = copy_with_shader(false, LOAD_HLSL, TexTransCoreTextureFormat::Float) else {
+        return;
+    };
+    assert_eq!(result, Ok(()));
+}
@@ -0,0 +1,108 @@
+use std::{fs, path::PathBuf};
+
+use ttce_wgpu_rust_core::shader_cache::{IncludedFileStamp, ShaderCache, ShaderCacheKey};
+
+fn temp_cache_directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!(
+        "ttce-wgpu-shader-cache-test-{}-{}",
+        name,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&directory);
+    directory
+}
+
+fn key(source: &str) -> ShaderCacheKey {
+    ShaderCacheKey::new(
+        "test.hlsl",
+        source,
+        "CSMain",
+        "cs_6_0",
+        &["-spirv"],
+        &[],
+        "Byte",
+    )
+}
+
+#[test]
+fn store_and_load() {
+    let directory = temp_cache_directory("store-and-load");
+    let cache = ShaderCache::new(&directory, u64::MAX).unwrap();
+    let spirv = vec![1, 2, 3, 4, 5, 6, 7, 8];
+
+    assert_eq!(cache.load(&key("a")), None);
+    cache.store(&key("a"), &[], &spirv).unwrap();
+
+    assert_eq!(cache.load(&key("a")), Some(spirv));
+    assert_eq!(cache.load(&key("b")), None);
+
+    cache.clear().unwrap();
+    assert_eq!(cache.load(&key("a")), None);
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn key_contains_compile_options() {
+    let a = ShaderCacheKey::new("test.hlsl", "src", "CSMain", "cs_6_0", &[], &[], "Byte");
+    let b = ShaderCacheKey::new("test.hlsl", "src", "CSMain", "cs_6_0", &[], &[], "Float");
+    let c = ShaderCacheKey::new(
+        "test.hlsl",
+        "src",
+        "CSMain",
+        "cs_6_0",
+        &[],
+        &[("DEF", Some("1"))],
+        "Byte",
+    );
+    assert_ne!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn invalidate_when_included_file_changed() {
+    let directory = temp_cache_directory("include");
+    let cache = ShaderCache::new(&directory, u64::MAX).unwrap();
+    let include_path = directory.join("include.hlsl");
+    let include_path_str = include_path.to_str().unwrap();
+
+    fs::write(&include_path, "float a;").unwrap();
+    let stamp = IncludedFileStamp::from_path(include_path_str).unwrap();
+    cache.store(&key("a"), &[stamp], &[0; 4]).unwrap();
+    assert!(cache.load(&key("a")).is_some());
+
+    fs::write(&include_path, "float a; float b;").unwrap();
+    assert_eq!(cache.load(&key("a")), None);
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn max_size_removes_old_cache() {
+    let directory = temp_cache_directory("max-size");
+    let spirv = vec![0_u8; 1024];
+    let cache = ShaderCache::new(&directory, 1500).unwrap();
+
+    cache.store(&key("a"), &[], &spirv).unwrap();
+    cache.store(&key("b"), &[], &spirv).unwrap();
+
+    assert!(cache.total_size().unwrap() <= 1500);
+    assert!(cache.load(&key("a")).is_some() != cache.load(&key("b")).is_some());
+    fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn max_size_keeps_stored_cache() {
+    let directory = temp_cache_directory("max-size-zero");
+    let spirv = vec![0_u8; 1024];
+    let cache = ShaderCache::new(&directory, 0).unwrap();
+
+    // 上限より大きくても保存した直後のものは残り、それ以前のものは削除される
+    cache.store(&key("a"), &[], &spirv).unwrap();
+    assert_eq!(cache.load(&key("a")), Some(spirv.clone()));
+    cache.store(&key("b"), &[], &spirv).unwrap();
+    assert_eq!(cache.load(&key("a")), None);
+    assert_eq!(cache.load(&key("b")), Some(spirv));
+
+    // 一時ファイルは残らない
+    assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+    fs::remove_dir_all(directory).unwrap();
+}
@@ -0,0 +1,259 @@
+mod common;
+
+use common::{create_test_device, desc_with, download_layer, pseudo_random_bytes};
+use ttce_wgpu_rust_core::{
+    mipmap::MipmapFilter,
+    tex_trans_core_engine::{TTCEWgpuError, TTRtDimension},
+    TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 20;
+const HEIGHT: u32 = 12;
+
+fn upload_download_each_layer(dimension: TTRtDimension) {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    for format in [
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureFormat::Float,
+    ] {
+        let rt = ctx.get_render_texture_with(&desc_with(
+            WIDTH,
+            HEIGHT,
+            format,
+            TexTransCoreTextureChannel::RGBA,
+            dimension,
+            4,
+            2,
+        ));
+
+        for mip_level in 0..rt.mip_level_count() {
+            let size = rt.mip_size(mip_level);
+            let layers: Vec<_> = (0..rt.layer_count(mip_level))
+                .map(|layer| {
+                    pseudo_random_bytes(
+                        size.width as usize * size.height as usize * 4,
+                        mip_level * 16 + layer + 1,
+                    )
+                })
+                .collect();
+            for (layer, data) in layers.iter().enumerate() {
+                ctx.upload_texture_layer(
+                    &rt,
+                    mip_level,
+                    layer as u32,
+                    data,
+                    TexTransCoreTextureFormat::Byte,
+                )
+                .unwrap();
+            }
+            // 他のレイヤーへの書き込みで壊れていないか
+            for (layer, data) in layers.iter().enumerate() {
+                assert_eq!(
+                    &download_layer(&mut ctx, &rt, mip_level, layer as u32),
+                    data,
+                    "{:?} {:?} mip {} layer {}",
+                    dimension,
+                    format,
+                    mip_level,
+                    layer
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn upload_download_each_layer_2d_array() {
+    upload_download_each_layer(TTRtDimension::D2);
+}
+
+#[test]
+fn upload_download_each_layer_3d() {
+    upload_download_each_layer(TTRtDimension::D3);
+}
+
+#[test]
+fn layer_out_of_range_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let data = vec![0_u8; WIDTH as usize * HEIGHT as usize * 4];
+
+    let array = ctx.get_render_texture_with(&desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D2,
+        3,
+        1,
+    ));
+    assert_eq!(
+        ctx.upload_texture_layer(&array, 0, 3, &data, TexTransCoreTextureFormat::Byte),
+        Err(TTCEWgpuError::InvalidArgument)
+    );
+
+    // 3D は奥行きもミップで縮小される
+    let volume = ctx.get_render_texture_with(&desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D3,
+        4,
+        2,
+    ));
+    assert_eq!(volume.layer_count(1), 2);
+    assert_eq!(
+        ctx.upload_texture_layer(
+            &volume,
+            1,
+            2,
+            &data[..10 * 6 * 4],
+            TexTransCoreTextureFormat::Byte
+        ),
+        Err(TTCEWgpuError::InvalidArgument)
+    );
+}
+
+#[test]
+fn copy_texture_2d_array() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let desc = desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D2,
+        3,
+        1,
+    );
+    let src = ctx.get_render_texture_with(&desc);
+    let dist = ctx.get_render_texture_with(&desc);
+
+    let layers: Vec<_> = (0..3)
+        .map(|layer| pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, layer + 7))
+        .collect();
+    for (layer, data) in layers.iter().enumerate() {
+        ctx.upload_texture_layer(&src, 0, layer as u32, data, TexTransCoreTextureFormat::Byte)
+            .unwrap();
+    }
+
+    ctx.copy_texture(&dist, &src).unwrap();
+
+    for (layer, data) in layers.iter().enumerate() {
+        assert_eq!(&download_layer(&mut ctx, &dist, 0, layer as u32), data);
+    }
+
+    let single = ctx.get_render_texture_with(&desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D2,
+        1,
+        1,
+    ));
+    assert_eq!(
+        ctx.copy_texture(&single, &src),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+}
+
+#[test]
+fn recycled_texture_is_cleared_every_layer() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let data = vec![255_u8; WIDTH as usize * HEIGHT as usize * 4];
+
+    for dimension in [TTRtDimension::D2, TTRtDimension::D3] {
+        let desc = desc_with(
+            WIDTH,
+            HEIGHT,
+            TexTransCoreTextureFormat::Byte,
+            TexTransCoreTextureChannel::RGBA,
+            dimension,
+            3,
+            1,
+        );
+        {
+            let rt = ctx.get_render_texture_with(&desc);
+            for layer in 0..3 {
+                ctx.upload_texture_layer(&rt, 0, layer, &data, TexTransCoreTextureFormat::Byte)
+                    .unwrap();
+            }
+        }
+        ctx.send_command();
+
+        let hit_count = engine.render_texture_pool_statistics().hit_count;
+        let rt = ctx.get_render_texture_with(&desc);
+        assert_eq!(
+            engine.render_texture_pool_statistics().hit_count,
+            hit_count + 1
+        );
+        for layer in 0..3 {
+            assert!(download_layer(&mut ctx, &rt, 0, layer)
+                .iter()
+                .all(|v| *v == 0));
+        }
+    }
+}
+
+#[test]
+fn binding_dimension_mismatch_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let converter_id = engine
+        .get_converter_id(
+            wgpu::TextureFormat::Rgba8Unorm,
+            wgpu::TextureFormat::Rgba32Float,
+        )
+        .unwrap();
+    let mut ctx = engine.create_ctx();
+    let array = ctx.get_render_texture_with(&desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D2,
+        2,
+        1,
+    ));
+    let volume = ctx.get_render_texture_with(&desc_with(
+        WIDTH,
+        HEIGHT,
+        TexTransCoreTextureFormat::Byte,
+        TexTransCoreTextureChannel::RGBA,
+        TTRtDimension::D3,
+        2,
+        1,
+    ));
+
+    assert_eq!(
+        ctx.generate_mipmap(&array, MipmapFilter::Box, false),
+        Err(TTCEWgpuError::TextureDimensionMismatch)
+    );
+
+    // コンバーターは RWTexture2D なので配列や 3D は渡せない
+    let mut handler = ctx.get_compute_handler(&converter_id).unwrap();
+    let src_index = handler.get_bind_index("SrcTex").unwrap();
+    assert_eq!(
+        handler.set_render_texture(src_index, &array),
+        Err(TTCEWgpuError::TextureDimensionMismatch)
+    );
+    assert_eq!(
+        handler.set_render_texture(src_index, &volume),
+        Err(TTCEWgpuError::TextureDimensionMismatch)
+    );
+}
@@ -0,0 +1,134 @@
+mod common;
+
+use common::{
+    bytes_per_pixel, create_test_device, desc, download, pseudo_random_bytes, ALL_CHANNEL,
+    ALL_FORMAT,
+};
+use ttce_wgpu_rust_core::{
+    tex_trans_core_engine::TTCEWgpuError, TexTransCoreTextureChannel, TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 256;
+const HEIGHT: u32 = 16;
+
+#[test]
+fn upload_download_round_trip_all_format_and_channel() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    for (i, format) in ALL_FORMAT.into_iter().enumerate() {
+        for (j, channel) in ALL_CHANNEL.into_iter().enumerate() {
+            let rt = ctx.get_render_texture_with(&desc(WIDTH, HEIGHT, format, channel));
+            assert_eq!(rt.tt_format(), Some((format, channel)));
+
+            let data_len = WIDTH as usize * HEIGHT as usize * bytes_per_pixel(format, channel);
+            let data = pseudo_random_bytes(data_len, (i * 16 + j) as u32 + 1);
+
+            ctx.upload_texture(&rt, &data, format).unwrap();
+            let downloaded = download(&mut ctx, &rt, format);
+
+            assert_eq!(data, downloaded, "{:?} {:?}", format, channel as i32);
+        }
+    }
+}
+
+#[test]
+fn upload_size_mismatch_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let rt = ctx.get_render_texture(WIDTH, HEIGHT, TexTransCoreTextureChannel::RGBA);
+    let data = vec![0_u8; 3];
+
+    assert_eq!(
+        ctx.upload_texture(&rt, &data, TexTransCoreTextureFormat::Byte),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+}
+
+#[test]
+fn copy_texture() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let format = TexTransCoreTextureFormat::Byte;
+    let rt_desc = desc(WIDTH, HEIGHT, format, TexTransCoreTextureChannel::RGBA);
+
+    let src = ctx.get_render_texture_with(&rt_desc);
+    let dist = ctx.get_render_texture_with(&rt_desc);
+    let data = pseudo_random_bytes(WIDTH as usize * HEIGHT as usize * 4, 7);
+
+    ctx.upload_texture(&src, &data, format).unwrap();
+    ctx.copy_texture(&dist, &src).unwrap();
+
+    assert_eq!(data, download(&mut ctx, &dist, format));
+}
+
+#[test]
+fn copy_texture_size_mismatch_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let src = ctx.get_render_texture(WIDTH, HEIGHT, TexTransCoreTextureChannel::RGBA);
+    let dist = ctx.get_render_texture(WIDTH, HEIGHT * 2, TexTransCoreTextureChannel::RGBA);
+
+    assert_eq!(
+        ctx.copy_texture(&dist, &src),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+}
+
+#[test]
+fn upload_download_round_trip_unaligned_width() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    for width in [1, 3, 63, 100, 257, 513] {
+        for format in ALL_FORMAT {
+            for channel in ALL_CHANNEL {
+                let rt = ctx.get_render_texture_with(&desc(width, 5, format, channel));
+
+                let data_len = width as usize * 5 * bytes_per_pixel(format, channel);
+                let data = pseudo_random_bytes(data_len, width);
+
+                ctx.upload_texture(&rt, &data, format).unwrap();
+                let downloaded = download(&mut ctx, &rt, format);
+
+                assert_eq!(
+                    data, downloaded,
+                    "{} {:?} {:?}",
+                    width, format, channel as i32
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn download_size_mismatch_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+
+    let rt = ctx.get_render_texture(100, 3, TexTransCoreTextureChannel::RGBA);
+    // パディングを含んだ長さは受け付けない
+    let mut data = vec![0_u8; 256 * 3 * 4];
+    assert_eq!(
+        common::block_on(ctx.download_texture(
+            &rt,
+            Some(TexTransCoreTextureFormat::Byte),
+            &mut data
+        )),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+}
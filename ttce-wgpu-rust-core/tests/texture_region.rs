@@ -0,0 +1,168 @@
+mod common;
+
+use common::{block_on, byte_rgba_desc, create_test_device, download, pseudo_random_bytes, region};
+use ttce_wgpu_rust_core::{
+    render_texture::TTTextureRegion, tex_trans_core_engine::TTCEWgpuError,
+    TexTransCoreTextureFormat,
+};
+
+const WIDTH: u32 = 20;
+const HEIGHT: u32 = 12;
+
+/// RGBA の Byte の画像から region の部分を切り出す
+fn crop(data: &[u8], width: u32, region: &TTTextureRegion) -> Vec<u8> {
+    (region.y..region.y + region.height)
+        .flat_map(|y| {
+            let begin = ((y * width + region.x) * 4) as usize;
+            data[begin..begin + region.width as usize * 4]
+                .iter()
+                .copied()
+        })
+        .collect()
+}
+
+#[test]
+fn upload_and_download_region() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let rt = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+
+    let upload_region = region(0, 3, 5, 7, 4);
+    let data = pseudo_random_bytes(7 * 4 * 4, 1);
+    ctx.upload_texture_region(&rt, upload_region, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    // 範囲外は 0 のまま
+    let whole = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+    let mut expected = vec![0; whole.len()];
+    for (row, chunk) in data.chunks_exact(7 * 4).enumerate() {
+        let begin = (((5 + row as u32) * WIDTH + 3) * 4) as usize;
+        expected[begin..begin + chunk.len()].copy_from_slice(chunk);
+    }
+    assert_eq!(whole, expected);
+
+    let mut downloaded = vec![0; data.len()];
+    block_on(ctx.download_texture_region(
+        &rt,
+        upload_region,
+        Some(TexTransCoreTextureFormat::Byte),
+        &mut downloaded,
+    ))
+    .unwrap();
+    assert_eq!(downloaded, data);
+
+    // フォーマットを変換しながらの場合も同じ位置が読み書きされる
+    let float_data: Vec<u8> = data
+        .iter()
+        .flat_map(|v| (*v as f32 / 255.0).to_le_bytes())
+        .collect();
+    let float_region = region(0, 11, 1, 7, 4);
+    ctx.upload_texture_region(
+        &rt,
+        float_region,
+        &float_data,
+        TexTransCoreTextureFormat::Float,
+    )
+    .unwrap();
+    let whole = download(&mut ctx, &rt, TexTransCoreTextureFormat::Byte);
+    assert_eq!(crop(&whole, WIDTH, &float_region), data);
+
+    let mut downloaded = vec![0; float_data.len()];
+    block_on(ctx.download_texture_region(
+        &rt,
+        float_region,
+        Some(TexTransCoreTextureFormat::Float),
+        &mut downloaded,
+    ))
+    .unwrap();
+    for (value, expected) in downloaded.chunks_exact(4).zip(&data) {
+        let value = f32::from_le_bytes(value.try_into().unwrap());
+        assert!((value * 255.0 - *expected as f32).abs() < 0.01);
+    }
+}
+
+#[test]
+fn copy_region_to_offset() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let src = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let dist = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let data = pseudo_random_bytes((WIDTH * HEIGHT * 4) as usize, 2);
+    ctx.upload_texture(&src, &data, TexTransCoreTextureFormat::Byte)
+        .unwrap();
+
+    let src_region = region(0, 2, 3, 6, 5);
+    let dist_region = region(0, 13, 7, 6, 5);
+    ctx.copy_texture_region(&dist, dist_region, &src, src_region)
+        .unwrap();
+    let copied = download(&mut ctx, &dist, TexTransCoreTextureFormat::Byte);
+    assert_eq!(
+        crop(&copied, WIDTH, &dist_region),
+        crop(&data, WIDTH, &src_region)
+    );
+    assert!(crop(&copied, WIDTH, &region(0, 0, 0, WIDTH, 7))
+        .iter()
+        .all(|v| *v == 0));
+
+    // 重なる同じテクスチャー内でのコピー
+    let overlap_dist = region(0, 4, 4, 6, 5);
+    ctx.copy_texture_region(&src, overlap_dist, &src, src_region)
+        .unwrap();
+    let copied = download(&mut ctx, &src, TexTransCoreTextureFormat::Byte);
+    assert_eq!(
+        crop(&copied, WIDTH, &overlap_dist),
+        crop(&data, WIDTH, &src_region)
+    );
+}
+
+#[test]
+fn out_of_range_region_is_error() {
+    let Some(engine) = create_test_device() else {
+        return;
+    };
+    let mut ctx = engine.create_ctx();
+    let src = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+    let dist = ctx.get_render_texture_with(&byte_rgba_desc(WIDTH, HEIGHT));
+
+    let data = vec![0; 4 * 4 * 4];
+    assert_eq!(
+        ctx.upload_texture_region(
+            &src,
+            region(0, WIDTH - 3, 0, 4, 4),
+            &data,
+            TexTransCoreTextureFormat::Byte
+        ),
+        Err(TTCEWgpuError::InvalidArgument)
+    );
+    assert_eq!(
+        ctx.upload_texture_region(
+            &src,
+            region(0, 0, 0, 4, 3),
+            &data,
+            TexTransCoreTextureFormat::Byte
+        ),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+    assert_eq!(
+        ctx.copy_texture_region(
+            &dist,
+            region(0, 0, u32::MAX, 4, 4),
+            &src,
+            region(0, 0, 0, 4, 4)
+        ),
+        Err(TTCEWgpuError::InvalidArgument)
+    );
+    assert_eq!(
+        ctx.copy_texture_region(&dist, region(0, 0, 0, 4, 4), &src, region(0, 0, 0, 4, 5)),
+        Err(TTCEWgpuError::SizeMismatch)
+    );
+    let mut downloaded = Vec::new();
+    assert_eq!(
+        block_on(ctx.download_texture_region(&src, region(0, 0, 0, 0, 0), None, &mut downloaded)),
+        Err(TTCEWgpuError::InvalidArgument)
+    );
+}